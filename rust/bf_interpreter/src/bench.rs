@@ -0,0 +1,94 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use clap::Args;
+
+use crate::{
+    interpreter::{options::InterpreterOptions, Interpreter},
+    ir::{self, Machine, Program},
+};
+
+/// Programs measured when none are given
+const PROGRAMS: [(&str, &str); 3] = [
+    ("hello.bf", include_str!("../programs/hello.bf")),
+    ("squares.bf", include_str!("../programs/squares.bf")),
+    ("bench.bf", include_str!("../programs/bench.bf")),
+];
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Programs to measure, a few standard ones if empty
+    pub programs: Vec<PathBuf>,
+    #[command(flatten)]
+    pub options: InterpreterOptions,
+}
+
+/// Compare the interpreter with the optimized and the compiled form of each program
+pub fn bench(args: &BenchArgs) -> anyhow::Result<()> {
+    let programs = if args.programs.is_empty() {
+        PROGRAMS
+            .iter()
+            .map(|(name, code)| (name.to_string(), code.to_string()))
+            .collect::<Vec<_>>()
+    } else {
+        args.programs
+            .iter()
+            .map(|path| {
+                std::fs::read_to_string(path)
+                    .map(|code| (path.display().to_string(), code))
+                    .with_context(|| format!("failed to read {}", path.display()))
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    println!(
+        "{:<20} {:>12} {:>12} {:>12}",
+        "program", "interpreter", "optimized", "compiled"
+    );
+    for (name, code) in programs {
+        let mut interpreter = code
+            .parse::<Interpreter>()
+            .with_context(|| format!("failed to parse {name}"))?;
+        interpreter.options = args.options;
+        interpreter.input_closed = true;
+        let input = interpreter.input_buf.iter().copied().collect::<Vec<_>>();
+
+        let (interpreted, interpreted_time) = time(|| {
+            while !interpreter.tick()? {}
+            Ok(std::mem::take(&mut interpreter.output))
+        })?;
+
+        let ops = ir::optimize(&interpreter.instructions, &args.options);
+        let (optimized, optimized_time) = time(|| {
+            let mut machine = Machine::new(&input, args.options);
+            machine.run(&ops)?;
+            Ok(machine.output)
+        })?;
+
+        let program = Program::compile(&ops);
+        let (compiled, compiled_time) = time(|| {
+            let mut machine = Machine::new(&input, args.options);
+            program.run(&mut machine)?;
+            Ok(machine.output)
+        })?;
+
+        if optimized != interpreted || compiled != interpreted {
+            bail!("{name}: output differs between the interpreter and the optimized program");
+        }
+        println!(
+            "{name:<20} {:>12.2?} {:>12.2?} {:>12.2?}",
+            interpreted_time, optimized_time, compiled_time
+        );
+    }
+
+    Ok(())
+}
+
+fn time<T>(f: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<(T, Duration)> {
+    let start = Instant::now();
+    let result = f()?;
+    Ok((result, start.elapsed()))
+}
@@ -0,0 +1,129 @@
+use std::{fmt::Write as _, path::PathBuf};
+
+use anyhow::{bail, Context};
+use clap::Args;
+
+use crate::{
+    interpreter::{
+        options::{CellWidth, Eof, InterpreterOptions, Overflow},
+        Interpreter,
+    },
+    ir::{self, Op},
+};
+
+#[derive(Debug, Args)]
+pub struct CompileArgs {
+    pub program: PathBuf,
+    /// Where to write the Rust source, build it with `rustc -O`
+    #[arg(short, long)]
+    pub output: PathBuf,
+    #[command(flatten)]
+    pub options: InterpreterOptions,
+}
+
+pub fn compile(args: &CompileArgs) -> anyhow::Result<()> {
+    let interpreter = Interpreter::from_file(&args.program)
+        .with_context(|| format!("failed to load {}", args.program.display()))?;
+    let source = to_rust(
+        &ir::optimize(&interpreter.instructions, &args.options),
+        &args.options,
+    )?;
+    std::fs::write(&args.output, source)
+        .with_context(|| format!("failed to write {}", args.output.display()))
+}
+
+/// Standalone Rust program doing the same as `ops`, reading stdin and writing stdout.
+/// Only the growable tape is supported.
+pub fn to_rust(ops: &[Op], options: &InterpreterOptions) -> anyhow::Result<String> {
+    if options.tape_len.is_some() {
+        bail!("compiled programs only support a growable tape");
+    }
+
+    let cell = match options.cell_width {
+        CellWidth::U8 => "u8",
+        CellWidth::U16 => "u16",
+        CellWidth::U32 => "u32",
+    };
+    let eof = match options.eof {
+        Eof::Zero => "0".to_string(),
+        Eof::MinusOne => format!("{cell}::MAX"),
+        Eof::Unchanged => "tape[ptr]".to_string(),
+    };
+
+    let mut out = String::new();
+    writeln!(out, "// generated by bf_interpreter compile")?;
+    writeln!(out, "#![allow(unused)]")?;
+    writeln!(out, "use std::io::{{Read, Write}};")?;
+    writeln!(out)?;
+    writeln!(out, "fn main() {{")?;
+    writeln!(out, "    let mut tape: Vec<{cell}> = vec![0];")?;
+    writeln!(out, "    let mut ptr: usize = 0;")?;
+    writeln!(out, "    let mut input = std::io::stdin().lock().bytes();")?;
+    writeln!(
+        out,
+        "    let mut output = std::io::BufWriter::new(std::io::stdout().lock());"
+    )?;
+    write_block(&mut out, ops, 1, options, &eof)?;
+    writeln!(out, "    output.flush().unwrap();")?;
+    writeln!(out, "}}")?;
+    Ok(out)
+}
+
+fn write_block(
+    out: &mut String,
+    ops: &[Op],
+    depth: usize,
+    options: &InterpreterOptions,
+    eof: &str,
+) -> std::fmt::Result {
+    let indent = "    ".repeat(depth);
+    for op in ops {
+        match op {
+            Op::Add(delta) => {
+                let method = match (options.overflow, *delta > 0) {
+                    (Overflow::Wrap, true) => "wrapping_add",
+                    (Overflow::Wrap, false) => "wrapping_sub",
+                    (Overflow::Saturate, true) => "saturating_add",
+                    (Overflow::Saturate, false) => "saturating_sub",
+                };
+                // keep the literal in range of the cell type
+                let max = u64::from(options.cell_width.max());
+                let amount = match options.overflow {
+                    Overflow::Wrap => delta.unsigned_abs() % (max + 1),
+                    Overflow::Saturate => delta.unsigned_abs().min(max),
+                };
+                writeln!(out, "{indent}tape[ptr] = tape[ptr].{method}({amount});")?;
+            }
+            Op::Move(offset) if *offset > 0 => {
+                writeln!(out, "{indent}ptr += {offset};")?;
+                writeln!(
+                    out,
+                    "{indent}if ptr >= tape.len() {{ tape.resize(ptr + 1, 0); }}"
+                )?;
+            }
+            Op::Move(offset) => writeln!(
+                out,
+                "{indent}ptr = ptr.checked_sub({}).expect(\"moved past the start of the tape\");",
+                offset.unsigned_abs()
+            )?,
+            Op::Clear => writeln!(out, "{indent}tape[ptr] = 0;")?,
+            Op::Print => writeln!(
+                out,
+                "{indent}output.write_all(&[tape[ptr] as u8]).unwrap();"
+            )?,
+            Op::Read => {
+                writeln!(out, "{indent}output.flush().unwrap();")?;
+                writeln!(
+                    out,
+                    "{indent}tape[ptr] = match input.next() {{ Some(Ok(byte)) => byte.into(), _ => {eof} }};"
+                )?;
+            }
+            Op::Loop(body) => {
+                writeln!(out, "{indent}while tape[ptr] != 0 {{")?;
+                write_block(out, body, depth + 1, options, eof)?;
+                writeln!(out, "{indent}}}")?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -9,3 +9,18 @@ pub enum Instruction {
     JmpNext(usize),
     JmpPrev(usize),
 }
+
+impl Instruction {
+    pub fn symbol(&self) -> char {
+        match self {
+            Instruction::PtrInc => '>',
+            Instruction::PtrDec => '<',
+            Instruction::Inc => '+',
+            Instruction::Dec => '-',
+            Instruction::Prt => '.',
+            Instruction::Read => ',',
+            Instruction::JmpNext(_) => '[',
+            Instruction::JmpPrev(_) => ']',
+        }
+    }
+}
@@ -1,21 +1,42 @@
-use std::{collections::VecDeque, path::Path, str::FromStr};
+use std::{collections::VecDeque, path::Path, str::FromStr, time::Instant};
 
 use anyhow::{bail, Context};
 use functional_utils::FunctionalUtils;
 
+use self::{
+    heatmap::Heatmap,
+    history::History,
+    options::{InterpreterOptions, TapeEdge},
+    profile::Profile,
+};
 use crate::instruction::Instruction;
 
+pub mod heatmap;
+pub mod history;
+pub mod options;
+pub mod profile;
+
 #[derive(Debug, Default)]
 pub struct Interpreter {
     pub instructions: Vec<Instruction>,
 
     pub input_buf: VecDeque<u8>,
     pub waitting_input: bool,
+    /// No more input will come, reads follow the `eof` option instead of waiting
+    pub input_closed: bool,
     pub output: Vec<u8>,
 
-    pub memory: Vec<u8>,
+    pub memory: Vec<u32>,
     pub memory_ptr: usize,
     pub instruction_ptr: usize,
+
+    pub options: InterpreterOptions,
+    /// Collected while `Some`, see [`Interpreter::set_profiling`]
+    pub profile: Option<Profile>,
+    /// Recorded while `Some`, see [`Interpreter::set_history`]
+    pub history: Option<History>,
+    /// Collected while `Some`, see [`Interpreter::set_heatmap`]
+    pub heatmap: Option<Heatmap>,
 }
 
 impl FromStr for Interpreter {
@@ -26,13 +47,12 @@ impl FromStr for Interpreter {
 
         let mut lines = s.lines();
 
-        while let Some(line) = lines.next() {
+        for line in lines.by_ref() {
             if line == "/end" {
                 break;
             }
 
             line.chars()
-                .into_iter()
                 .filter_map(|it| match it {
                     '>' => Instruction::PtrInc.some(),
                     '<' => Instruction::PtrDec.some(),
@@ -108,15 +128,63 @@ impl Interpreter {
     }
 
     pub fn reset(&mut self) {
+        let history = self.history.is_some();
         *self = Self {
             instructions: self.instructions.clone(),
+            options: self.options,
+            profile: self
+                .profile
+                .as_ref()
+                .map(|_| Profile::new(self.instructions.len())),
+            heatmap: self.heatmap.as_ref().map(|_| Heatmap::default()),
             ..Default::default()
+        };
+        if history {
+            self.set_history(true);
         }
     }
 
-    pub fn tick(&mut self) -> bool {
+    /// Start collecting a new profile, or stop and drop the current one
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profile = enabled.then(|| Profile::new(self.instructions.len()));
+    }
+
+    /// Start counting memory accesses from zero, or stop and drop the counts
+    pub fn set_heatmap(&mut self, enabled: bool) {
+        self.heatmap = enabled.then(Heatmap::default);
+    }
+
+    /// Execute one instruction, returns whether the program halted
+    pub fn tick(&mut self) -> anyhow::Result<bool> {
+        if self.profile.is_none() && self.history.is_none() && self.heatmap.is_none() {
+            return self.execute();
+        }
+
+        let idx = self.instruction_ptr;
+        let ptr = self.memory_ptr;
+        let entry = self.history.as_ref().map(|_| self.journal_entry());
+        // reading the clock costs about as much as an instruction, so only when profiling
+        let start = self.profile.as_ref().map(|_| Instant::now());
+        let halted = self.execute()?;
+        if halted || self.waitting_input {
+            return Ok(halted);
+        }
+
+        if let (Some(profile), Some(start)) = (&mut self.profile, start) {
+            profile.record(idx, start.elapsed());
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(self.instructions[idx], ptr);
+        }
+        if let Some(entry) = entry {
+            self.record_history(entry);
+        }
+        Ok(false)
+    }
+
+    fn execute(&mut self) -> anyhow::Result<bool> {
         if self.instruction_ptr >= self.instructions.len() {
-            return true;
+            return Ok(true);
         }
 
         if self.memory.is_empty() {
@@ -130,7 +198,12 @@ impl Interpreter {
             instruction_ptr,
             input_buf,
             waitting_input,
+            input_closed,
             output,
+            options,
+            profile: _,
+            history: _,
+            heatmap: _,
         } = self;
 
         let instruction = instructions[*instruction_ptr];
@@ -140,27 +213,49 @@ impl Interpreter {
         match instruction {
             Instruction::PtrInc => {
                 *memory_ptr += 1;
+                if options.tape_len.is_some_and(|len| *memory_ptr >= len) {
+                    match options.tape_edge {
+                        TapeEdge::Error => {
+                            *memory_ptr -= 1;
+                            bail!("moved past the end of the tape");
+                        }
+                        TapeEdge::Wrap => *memory_ptr = 0,
+                    }
+                }
                 if memory.len() == *memory_ptr {
                     memory.push(0);
                 }
             }
             Instruction::PtrDec => {
-                *memory_ptr -= 1;
+                if *memory_ptr > 0 {
+                    *memory_ptr -= 1;
+                } else {
+                    match (options.tape_len, options.tape_edge) {
+                        (Some(len), TapeEdge::Wrap) => {
+                            memory.resize(len.max(1), 0);
+                            *memory_ptr = memory.len() - 1;
+                        }
+                        _ => bail!("moved past the start of the tape"),
+                    }
+                }
             }
             Instruction::Inc => {
-                memory[*memory_ptr] = memory[*memory_ptr].wrapping_add(1);
+                memory[*memory_ptr] = options.inc(memory[*memory_ptr]);
             }
-            Instruction::Dec => memory[*memory_ptr] = memory[*memory_ptr].wrapping_sub(1),
+            Instruction::Dec => memory[*memory_ptr] = options.dec(memory[*memory_ptr]),
             Instruction::Prt => {
-                output.push(memory[*memory_ptr]);
+                output.push(memory[*memory_ptr] as u8);
             }
             Instruction::Read => {
                 if let Some(value) = input_buf.pop_front() {
                     *waitting_input = false;
-                    memory[*memory_ptr] = value;
+                    memory[*memory_ptr] = value.into();
+                } else if *input_closed {
+                    *waitting_input = false;
+                    memory[*memory_ptr] = options.eof(memory[*memory_ptr]);
                 } else {
                     *waitting_input = true;
-                    return false;
+                    return Ok(false);
                 }
             }
             Instruction::JmpNext(addr) => {
@@ -177,6 +272,6 @@ impl Interpreter {
 
         *instruction_ptr = new_instruction_ptr;
 
-        false
+        Ok(false)
     }
 }
@@ -0,0 +1,48 @@
+use crate::instruction::Instruction;
+
+/// Read and write count of every memory cell
+#[derive(Debug, Clone, Default)]
+pub struct Heatmap {
+    pub reads: Vec<u64>,
+    pub writes: Vec<u64>,
+}
+
+impl Heatmap {
+    /// Count the accesses of `instruction` executed at cell `ptr`
+    pub fn record(&mut self, instruction: Instruction, ptr: usize) {
+        let (read, write) = match instruction {
+            Instruction::Inc | Instruction::Dec => (true, true),
+            Instruction::Prt | Instruction::JmpNext(_) | Instruction::JmpPrev(_) => (true, false),
+            Instruction::Read => (false, true),
+            Instruction::PtrInc | Instruction::PtrDec => return,
+        };
+
+        if self.reads.len() <= ptr {
+            self.reads.resize(ptr + 1, 0);
+            self.writes.resize(ptr + 1, 0);
+        }
+        self.reads[ptr] += read as u64;
+        self.writes[ptr] += write as u64;
+    }
+
+    /// Reads and writes of a cell
+    pub fn accesses(&self, ptr: usize) -> u64 {
+        self.reads.get(ptr).copied().unwrap_or(0) + self.writes.get(ptr).copied().unwrap_or(0)
+    }
+
+    pub fn max_accesses(&self) -> u64 {
+        (0..self.reads.len())
+            .map(|ptr| self.accesses(ptr))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Accesses of a cell from 0 to 1 relative to `max`, on a log scale
+    /// so rarely touched cells still show up next to the hot loop counters
+    pub fn heat(&self, ptr: usize, max: u64) -> f64 {
+        if max == 0 {
+            return 0.0;
+        }
+        (self.accesses(ptr) as f64).ln_1p() / (max as f64).ln_1p()
+    }
+}
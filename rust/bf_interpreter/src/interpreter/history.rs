@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+
+use super::Interpreter;
+
+/// Instructions between two snapshots
+const SNAPSHOT_INTERVAL: u64 = 1024;
+/// Snapshots kept, older ones are dropped
+const MAX_SNAPSHOTS: usize = 256;
+/// Instructions which can be undone without going through a snapshot
+const JOURNAL_LEN: usize = 4096;
+
+/// Record of past states for stepping backwards
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Instructions executed since history was enabled
+    pub step: u64,
+    journal: VecDeque<JournalEntry>,
+    snapshots: VecDeque<Snapshot>,
+    /// Every input byte read, so it can be given back when stepping back
+    input_log: Vec<u8>,
+}
+
+/// What one instruction changed, enough to undo it
+#[derive(Debug, Clone, Copy)]
+pub struct JournalEntry {
+    instruction_ptr: usize,
+    memory_ptr: usize,
+    memory_len: usize,
+    /// Value of the current cell before the instruction
+    cell: u32,
+    output_len: usize,
+    input_len: usize,
+    input_front: Option<u8>,
+    /// Whether the instruction consumed `input_front`, set once it executed
+    read: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    step: u64,
+    memory: Vec<u32>,
+    memory_ptr: usize,
+    instruction_ptr: usize,
+    output_len: usize,
+    input_read: usize,
+}
+
+impl Interpreter {
+    /// Start recording history, or stop and drop it
+    pub fn set_history(&mut self, enabled: bool) {
+        self.history = None;
+        if enabled {
+            let mut history = History::default();
+            history.snapshots.push_back(self.snapshot(&history));
+            self.history = Some(history);
+        }
+    }
+
+    pub(super) fn journal_entry(&self) -> JournalEntry {
+        JournalEntry {
+            instruction_ptr: self.instruction_ptr,
+            memory_ptr: self.memory_ptr,
+            memory_len: self.memory.len(),
+            cell: self.memory.get(self.memory_ptr).copied().unwrap_or(0),
+            output_len: self.output.len(),
+            input_len: self.input_buf.len(),
+            input_front: self.input_buf.front().copied(),
+            read: false,
+        }
+    }
+
+    /// Record an instruction which just executed
+    pub(super) fn record_history(&mut self, mut entry: JournalEntry) {
+        let Some(mut history) = self.history.take() else {
+            return;
+        };
+
+        history.step += 1;
+        entry.read = self.input_buf.len() < entry.input_len;
+        if entry.read {
+            history
+                .input_log
+                .push(entry.input_front.expect("read from empty input"));
+        }
+        if history.journal.len() == JOURNAL_LEN {
+            history.journal.pop_front();
+        }
+        history.journal.push_back(entry);
+
+        if history.step.is_multiple_of(SNAPSHOT_INTERVAL) {
+            if history.snapshots.len() == MAX_SNAPSHOTS {
+                history.snapshots.pop_front();
+            }
+            history.snapshots.push_back(self.snapshot(&history));
+        }
+        self.history = Some(history);
+    }
+
+    fn snapshot(&self, history: &History) -> Snapshot {
+        Snapshot {
+            step: history.step,
+            memory: self.memory.clone(),
+            memory_ptr: self.memory_ptr,
+            instruction_ptr: self.instruction_ptr,
+            output_len: self.output.len(),
+            input_read: history.input_log.len(),
+        }
+    }
+
+    /// Step back up to `steps` instructions, returns how many were undone
+    pub fn back(&mut self, steps: u64) -> anyhow::Result<u64> {
+        let Some(mut history) = self.history.take() else {
+            return Ok(0);
+        };
+        let start = history.step;
+        let target = start.saturating_sub(steps);
+
+        if start - target <= history.journal.len() as u64 {
+            while history.step > target {
+                let entry = history.journal.pop_back().expect("journal checked above");
+                self.undo(&mut history, entry);
+            }
+            self.history = Some(history);
+            return Ok(start - target);
+        }
+
+        // too far for the journal, go to the last snapshot before the target and replay
+        while history.snapshots.len() > 1 && history.snapshots.back().unwrap().step > target {
+            history.snapshots.pop_back();
+        }
+        let snapshot = history.snapshots.back().unwrap().clone();
+        self.memory = snapshot.memory;
+        self.memory_ptr = snapshot.memory_ptr;
+        self.instruction_ptr = snapshot.instruction_ptr;
+        self.output.truncate(snapshot.output_len);
+        for &byte in history.input_log[snapshot.input_read..].iter().rev() {
+            self.input_buf.push_front(byte);
+        }
+        history.input_log.truncate(snapshot.input_read);
+        history.journal.clear();
+        history.step = snapshot.step;
+        self.waitting_input = false;
+
+        // the replayed instructions were counted when they first ran
+        let replay = target.max(snapshot.step) - snapshot.step;
+        self.history = Some(history);
+        let profile = self.profile.take();
+        let heatmap = self.heatmap.take();
+        let replayed = (0..replay).try_for_each(|_| self.tick().map(|_| ()));
+        self.profile = profile;
+        self.heatmap = heatmap;
+        replayed?;
+        Ok(start - target.max(snapshot.step))
+    }
+
+    fn undo(&mut self, history: &mut History, entry: JournalEntry) {
+        self.instruction_ptr = entry.instruction_ptr;
+        self.memory_ptr = entry.memory_ptr;
+        self.memory.truncate(entry.memory_len);
+        if let Some(cell) = self.memory.get_mut(entry.memory_ptr) {
+            *cell = entry.cell;
+        }
+        self.output.truncate(entry.output_len);
+        if entry.read {
+            let byte = history
+                .input_log
+                .pop()
+                .expect("input log matches the journal");
+            self.input_buf.push_front(byte);
+        }
+        self.waitting_input = false;
+        history.step -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads, prints, and walks right forever
+    const PROGRAM: &str = "+[>,.>++++++[-<+>]<]\n/end\nsome input";
+
+    fn run(steps: u64, history: bool) -> Interpreter {
+        let mut interpreter = PROGRAM.parse::<Interpreter>().unwrap();
+        interpreter.input_closed = true;
+        interpreter.set_history(history);
+        interpreter.set_profiling(true);
+        interpreter.set_heatmap(true);
+        for _ in 0..steps {
+            assert!(!interpreter.tick().unwrap());
+        }
+        interpreter
+    }
+
+    fn assert_same_state(interpreter: &Interpreter, expected: &Interpreter) {
+        assert_eq!(interpreter.memory, expected.memory);
+        assert_eq!(interpreter.memory_ptr, expected.memory_ptr);
+        assert_eq!(interpreter.instruction_ptr, expected.instruction_ptr);
+        assert_eq!(interpreter.output, expected.output);
+        assert_eq!(interpreter.input_buf, expected.input_buf);
+    }
+
+    #[test]
+    fn test_back_journal() {
+        let mut interpreter = run(3000, true);
+        for (steps, expected) in [(1, 2999), (500, 2499), (2499, 0)] {
+            assert_eq!(interpreter.back(steps).unwrap(), steps);
+            assert_same_state(&interpreter, &run(expected, false));
+        }
+        assert_eq!(interpreter.back(1).unwrap(), 0);
+
+        // forward again from the undone state
+        for _ in 0..100 {
+            interpreter.tick().unwrap();
+        }
+        assert_same_state(&interpreter, &run(100, false));
+    }
+
+    #[test]
+    fn test_back_replay() {
+        let steps = 20_000;
+        let mut interpreter = run(steps, true);
+        let profile = interpreter.profile.clone().unwrap();
+        let heatmap = interpreter.heatmap.clone().unwrap();
+
+        let back = JOURNAL_LEN as u64 + 1500;
+        assert_eq!(interpreter.back(back).unwrap(), back);
+        assert_same_state(&interpreter, &run(steps - back, false));
+        assert_eq!(interpreter.history.as_ref().unwrap().step, steps - back);
+
+        // replaying isn't counted again
+        assert_eq!(interpreter.profile.as_ref().unwrap().counts, profile.counts);
+        assert_eq!(interpreter.heatmap.as_ref().unwrap().reads, heatmap.reads);
+        assert_eq!(interpreter.heatmap.as_ref().unwrap().writes, heatmap.writes);
+
+        // the journal is rebuilt by the replay
+        assert_eq!(interpreter.back(10).unwrap(), 10);
+        assert_same_state(&interpreter, &run(steps - back - 10, false));
+    }
+}
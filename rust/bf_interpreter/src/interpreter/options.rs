@@ -0,0 +1,142 @@
+use clap::{Args, ValueEnum};
+
+/// Semantics which differ between brainfuck implementations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Args)]
+pub struct InterpreterOptions {
+    /// Bits per memory cell
+    #[arg(long, value_enum, default_value_t = CellWidth::U8)]
+    pub cell_width: CellWidth,
+    /// What `+` and `-` do past the range of a cell
+    #[arg(long, value_enum, default_value_t = Overflow::Wrap)]
+    pub overflow: Overflow,
+    /// Fixed number of cells, the tape grows to the right as needed if not set
+    #[arg(long, value_name = "CELLS")]
+    pub tape_len: Option<usize>,
+    /// What moving past an end of the tape does, a growable tape only has a left end
+    #[arg(long, value_enum, default_value_t = TapeEdge::Error)]
+    pub tape_edge: TapeEdge,
+    /// What `,` does once the input is closed
+    #[arg(long, value_enum, default_value_t = Eof::Zero)]
+    pub eof: Eof,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CellWidth {
+    #[default]
+    #[value(name = "8")]
+    U8,
+    #[value(name = "16")]
+    U16,
+    #[value(name = "32")]
+    U32,
+}
+
+impl CellWidth {
+    pub fn max(self) -> u32 {
+        match self {
+            CellWidth::U8 => u8::MAX.into(),
+            CellWidth::U16 => u16::MAX.into(),
+            CellWidth::U32 => u32::MAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Overflow {
+    /// `255 + 1 == 0` and `0 - 1 == 255`
+    #[default]
+    Wrap,
+    /// Stay at the largest or smallest value
+    Saturate,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TapeEdge {
+    /// Stop with an error
+    #[default]
+    Error,
+    /// Continue from the other end, only possible on a fixed tape
+    Wrap,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Eof {
+    /// Set the cell to 0
+    #[default]
+    #[value(name = "0")]
+    Zero,
+    /// Set the cell to -1, the largest value of a cell
+    #[value(name = "-1")]
+    MinusOne,
+    /// Leave the cell unchanged
+    Unchanged,
+}
+
+impl InterpreterOptions {
+    /// Cells past the range of the cell width, left by a change of it, are brought back in
+    /// range first like by [`InterpreterOptions::fit`]
+    pub fn inc(&self, value: u32) -> u32 {
+        let max = self.cell_width.max();
+        match (value >= max, self.overflow) {
+            (false, _) => value + 1,
+            (true, Overflow::Wrap) => self.add(value, 1),
+            (true, Overflow::Saturate) => max,
+        }
+    }
+
+    pub fn dec(&self, value: u32) -> u32 {
+        match (value, self.overflow) {
+            (0, Overflow::Wrap) => self.cell_width.max(),
+            (0, Overflow::Saturate) => 0,
+            (value, _) if value > self.cell_width.max() => self.add(value, -1),
+            (value, _) => value - 1,
+        }
+    }
+
+    /// `delta` times [`InterpreterOptions::inc`] or [`InterpreterOptions::dec`] at once
+    pub fn add(&self, value: u32, delta: i64) -> u32 {
+        let max = i64::from(self.cell_width.max());
+        let value = i64::from(value) + delta;
+        match self.overflow {
+            Overflow::Wrap => value.rem_euclid(max + 1) as u32,
+            Overflow::Saturate => value.clamp(0, max) as u32,
+        }
+    }
+
+    /// `value` in the range of the cell width, wrapped or clamped like by `+` and `-`
+    pub fn fit(&self, value: u32) -> u32 {
+        self.add(value, 0)
+    }
+
+    /// Value of the current cell after reading past the end of the input
+    pub fn eof(&self, value: u32) -> u32 {
+        match self.eof {
+            Eof::Zero => 0,
+            Eof::MinusOne => self.cell_width.max(),
+            Eof::Unchanged => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_range() {
+        let wrap = InterpreterOptions::default();
+        assert_eq!(wrap.inc(u32::MAX), 0);
+        assert_eq!(wrap.inc(0x1ff), 0);
+        assert_eq!(wrap.inc(0x1fe), 0xff);
+        assert_eq!(wrap.dec(0x100), 0xff);
+        assert_eq!(wrap.fit(0x1234), 0x34);
+
+        let saturate = InterpreterOptions {
+            overflow: Overflow::Saturate,
+            ..Default::default()
+        };
+        assert_eq!(saturate.inc(u32::MAX), 0xff);
+        assert_eq!(saturate.dec(u32::MAX), 0xff);
+        assert_eq!(saturate.fit(0x1234), 0xff);
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use crate::instruction::Instruction;
+
+/// Execution count and time of every instruction
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub counts: Vec<u64>,
+    pub time: Vec<Duration>,
+}
+
+/// A loop and everything executed inside of it, nested loops included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotSpot {
+    /// Index of the `[`
+    pub start: usize,
+    /// Index of the `]`
+    pub end: usize,
+    pub count: u64,
+    pub time: Duration,
+}
+
+impl HotSpot {
+    /// Source of the loop, shortened to at most `max_len` chars
+    pub fn code(&self, instructions: &[Instruction], max_len: usize) -> String {
+        let len = self.end + 1 - self.start;
+        let code = instructions[self.start..=self.end]
+            .iter()
+            .map(Instruction::symbol);
+        if len <= max_len {
+            code.collect()
+        } else {
+            code.take(max_len.saturating_sub(1)).chain(['…']).collect()
+        }
+    }
+}
+
+impl Profile {
+    pub fn new(len: usize) -> Self {
+        Self {
+            counts: vec![0; len],
+            time: vec![Duration::ZERO; len],
+        }
+    }
+
+    pub fn record(&mut self, idx: usize, elapsed: Duration) {
+        self.counts[idx] += 1;
+        self.time[idx] += elapsed;
+    }
+
+    pub fn total_count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.time.iter().sum()
+    }
+
+    /// Loops which ran at least once, most executed instructions first
+    pub fn hot_spots(&self, instructions: &[Instruction]) -> Vec<HotSpot> {
+        let mut spots = instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(start, instruction)| match instruction {
+                // jumps point after the matching bracket
+                Instruction::JmpNext(after_end) => Some(start..*after_end),
+                _ => None,
+            })
+            .map(|range| HotSpot {
+                start: range.start,
+                end: range.end - 1,
+                count: self.counts[range.clone()].iter().sum(),
+                time: self.time[range].iter().sum(),
+            })
+            .filter(|spot| spot.count > 0)
+            .collect::<Vec<_>>();
+        spots.sort_by_key(|spot| std::cmp::Reverse(spot.count));
+        spots
+    }
+
+    /// Text table of the top `rows` hot spots, with a bar for each share
+    pub fn format_table(&self, instructions: &[Instruction], rows: usize) -> Vec<String> {
+        let total = self.total_count().max(1);
+        let mut lines = vec![format!(
+            "{:>7} {:>12} {:>10}  {:<20} {}",
+            "share", "count", "time", "", "loop"
+        )];
+        for spot in self.hot_spots(instructions).into_iter().take(rows) {
+            let share = spot.count as f64 / total as f64;
+            lines.push(format!(
+                "{:>6.2}% {:>12} {:>10.2?}  {:<20} {}..={} {}",
+                share * 100.0,
+                spot.count,
+                spot.time,
+                "█".repeat((share * 20.0).round() as usize),
+                spot.start,
+                spot.end,
+                spot.code(instructions, 40)
+            ));
+        }
+        lines
+    }
+}
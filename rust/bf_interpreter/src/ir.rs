@@ -0,0 +1,203 @@
+//! Optimized form of a program, runs of the same instruction and common idioms
+//! folded into single operations.
+
+use std::collections::VecDeque;
+
+use anyhow::bail;
+
+use crate::{
+    instruction::Instruction,
+    interpreter::options::{InterpreterOptions, Overflow, TapeEdge},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op {
+    /// Runs of `+` and `-`
+    Add(i64),
+    /// Runs of `>` and `<`
+    Move(isize),
+    /// `[-]`, `[+]` isn't folded as it never ends with saturating cells
+    Clear,
+    Print,
+    Read,
+    Loop(Vec<Op>),
+}
+
+/// Fold a parsed program, the jumps must be matched.
+/// A run only changes direction within one op where that can't be observed,
+/// `+-` could hit a saturated cell and `<>` could step off the tape in between.
+pub fn optimize(instructions: &[Instruction], options: &InterpreterOptions) -> Vec<Op> {
+    let merge_adds = options.overflow == Overflow::Wrap;
+    let merge_moves = options.tape_len.is_some() && options.tape_edge == TapeEdge::Wrap;
+
+    let mut stack = vec![vec![]];
+    for instruction in instructions {
+        let ops = stack.last_mut().unwrap();
+        match (instruction, ops.last_mut()) {
+            (Instruction::Inc, Some(Op::Add(n))) if merge_adds || *n > 0 => *n += 1,
+            (Instruction::Dec, Some(Op::Add(n))) if merge_adds || *n < 0 => *n -= 1,
+            (Instruction::PtrInc, Some(Op::Move(n))) if merge_moves || *n > 0 => *n += 1,
+            (Instruction::PtrDec, Some(Op::Move(n))) if merge_moves || *n < 0 => *n -= 1,
+            (Instruction::Inc, _) => ops.push(Op::Add(1)),
+            (Instruction::Dec, _) => ops.push(Op::Add(-1)),
+            (Instruction::PtrInc, _) => ops.push(Op::Move(1)),
+            (Instruction::PtrDec, _) => ops.push(Op::Move(-1)),
+            (Instruction::Prt, _) => ops.push(Op::Print),
+            (Instruction::Read, _) => ops.push(Op::Read),
+            (Instruction::JmpNext(_), _) => stack.push(vec![]),
+            (Instruction::JmpPrev(_), _) => {
+                let body = stack.pop().expect("jumps are matched");
+                let op = match body.as_slice() {
+                    [Op::Add(-1)] => Op::Clear,
+                    _ => Op::Loop(body),
+                };
+                stack.last_mut().expect("jumps are matched").push(op);
+            }
+        }
+        // `+-` and `><` cancel out
+        let ops = stack.last_mut().unwrap();
+        if matches!(ops.last(), Some(Op::Add(0) | Op::Move(0))) {
+            ops.pop();
+        }
+    }
+    stack.pop().expect("jumps are matched")
+}
+
+/// State of a program running as [`Op`]s
+#[derive(Debug, Default)]
+pub struct Machine {
+    pub memory: Vec<u32>,
+    pub memory_ptr: usize,
+    /// All of the input, the program sees EOF once it's used up
+    pub input: VecDeque<u8>,
+    pub output: Vec<u8>,
+    pub options: InterpreterOptions,
+}
+
+impl Machine {
+    pub fn new(input: &[u8], options: InterpreterOptions) -> Self {
+        Self {
+            memory: vec![0],
+            input: input.iter().copied().collect(),
+            options,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn add(&mut self, delta: i64) {
+        let cell = &mut self.memory[self.memory_ptr];
+        *cell = self.options.add(*cell, delta);
+    }
+
+    #[inline]
+    pub fn move_ptr(&mut self, offset: isize) -> anyhow::Result<()> {
+        let ptr = self.memory_ptr as isize + offset;
+        let ptr = match (self.options.tape_len, self.options.tape_edge) {
+            (Some(len), TapeEdge::Wrap) => ptr.rem_euclid(len.max(1) as isize),
+            (Some(len), TapeEdge::Error) if ptr >= len as isize => {
+                bail!("moved past the end of the tape")
+            }
+            _ if ptr < 0 => bail!("moved past the start of the tape"),
+            _ => ptr,
+        } as usize;
+
+        if ptr >= self.memory.len() {
+            self.memory.resize(ptr + 1, 0);
+        }
+        self.memory_ptr = ptr;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn read(&mut self) {
+        let cell = &mut self.memory[self.memory_ptr];
+        *cell = match self.input.pop_front() {
+            Some(value) => value.into(),
+            None => self.options.eof(*cell),
+        };
+    }
+
+    /// Interpret the ops directly
+    pub fn run(&mut self, ops: &[Op]) -> anyhow::Result<()> {
+        for op in ops {
+            match op {
+                Op::Add(delta) => self.add(*delta),
+                Op::Move(offset) => self.move_ptr(*offset)?,
+                Op::Clear => self.memory[self.memory_ptr] = 0,
+                Op::Print => self.output.push(self.memory[self.memory_ptr] as u8),
+                Op::Read => self.read(),
+                Op::Loop(body) => {
+                    while self.memory[self.memory_ptr] != 0 {
+                        self.run(body)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+type Compiled = Box<dyn Fn(&mut Machine) -> anyhow::Result<()>>;
+
+/// Ops turned into a chain of closures, so nothing is matched on at run time
+pub struct Program(Compiled);
+
+impl std::fmt::Debug for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Program").finish_non_exhaustive()
+    }
+}
+
+impl Program {
+    pub fn compile(ops: &[Op]) -> Self {
+        Self(compile_block(ops))
+    }
+
+    pub fn run(&self, machine: &mut Machine) -> anyhow::Result<()> {
+        (self.0)(machine)
+    }
+}
+
+fn compile_block(ops: &[Op]) -> Compiled {
+    let ops = ops.iter().map(compile_op).collect::<Vec<_>>();
+    Box::new(move |machine| {
+        for op in &ops {
+            op(machine)?;
+        }
+        Ok(())
+    })
+}
+
+fn compile_op(op: &Op) -> Compiled {
+    match *op {
+        Op::Add(delta) => Box::new(move |machine| {
+            machine.add(delta);
+            Ok(())
+        }),
+        Op::Move(offset) => Box::new(move |machine| machine.move_ptr(offset)),
+        Op::Clear => Box::new(|machine| {
+            machine.memory[machine.memory_ptr] = 0;
+            Ok(())
+        }),
+        Op::Print => Box::new(|machine| {
+            machine
+                .output
+                .push(machine.memory[machine.memory_ptr] as u8);
+            Ok(())
+        }),
+        Op::Read => Box::new(|machine| {
+            machine.read();
+            Ok(())
+        }),
+        Op::Loop(ref body) => {
+            let body = compile_block(body);
+            Box::new(move |machine| {
+                while machine.memory[machine.memory_ptr] != 0 {
+                    body(machine)?;
+                }
+                Ok(())
+            })
+        }
+    }
+}
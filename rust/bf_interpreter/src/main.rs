@@ -1,14 +1,83 @@
 #![warn(missing_debug_implementations)]
 
+use std::path::PathBuf;
+
 use anyhow::Context as _;
+use bench::BenchArgs;
+use clap::{Parser, Subcommand};
+use compile::CompileArgs;
+use interpreter::options::InterpreterOptions;
+use run::RunArgs;
 use visualizer::Visualizer;
 
+pub mod bench;
+pub mod compile;
 pub mod instruction;
 pub mod interpreter;
+pub mod ir;
+pub mod run;
 pub mod visualizer;
 
+#[derive(Debug, Parser)]
+#[command(
+    about = "Brainfuck interpreter with a terminal visualizer",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Program to load into the visualizer
+    program: Option<PathBuf>,
+    /// File of slash-commands to run in the visualizer on startup, one per line
+    #[arg(long, value_name = "PATH")]
+    script: Option<PathBuf>,
+
+    #[command(flatten)]
+    options: InterpreterOptions,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run a program to completion without the visualizer
+    Run(RunArgs),
+    /// Translate a program into a standalone Rust program
+    Compile(CompileArgs),
+    /// Time the interpreter against the optimized and compiled forms of programs
+    Bench(BenchArgs),
+}
+
 fn main() -> anyhow::Result<()> {
-    let mut visualizer = Visualizer::init().context("failed to initialize visualizer")?;
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Run(args)) => run::run(&args),
+        Some(Command::Compile(args)) => compile::compile(&args),
+        Some(Command::Bench(args)) => bench::bench(&args),
+        None => run_visualizer(cli.options, cli.program, cli.script),
+    }
+}
+
+fn run_visualizer(
+    options: InterpreterOptions,
+    program: Option<PathBuf>,
+    script: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    // read before taking over the terminal, so errors are printed normally
+    let script = script
+        .map(|path| {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read script {}", path.display()))
+        })
+        .transpose()?;
+
+    let mut visualizer = Visualizer::init(options).context("failed to initialize visualizer")?;
+    if let Some(program) = program {
+        visualizer.load_file(&program);
+    }
+    if let Some(script) = script {
+        visualizer.run_script(&script);
+    }
 
     loop {
         let result = visualizer.tick();
@@ -0,0 +1,106 @@
+use std::{
+    io::{stdin, stdout, BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context};
+use clap::Args;
+
+use crate::interpreter::{options::InterpreterOptions, Interpreter};
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// Program to run, text after a `/end` line is used as input first
+    pub program: PathBuf,
+    /// Read input from a file instead of stdin
+    #[arg(long)]
+    pub input: Option<PathBuf>,
+    /// Fail if the program doesn't halt within N instructions
+    #[arg(long, value_name = "N")]
+    pub max_steps: Option<u64>,
+    /// Print the loops which ran the most to stderr when done
+    #[arg(long)]
+    pub profile: bool,
+    #[command(flatten)]
+    pub options: InterpreterOptions,
+}
+
+/// Run a program to completion without the visualizer
+pub fn run(args: &RunArgs) -> anyhow::Result<()> {
+    let mut interpreter = Interpreter::from_file(&args.program)
+        .with_context(|| format!("failed to load {}", args.program.display()))?;
+    interpreter.options = args.options;
+    interpreter.set_profiling(args.profile);
+
+    let input: Box<dyn BufRead> = match &args.input {
+        Some(path) => Box::new(BufReader::new(
+            std::fs::File::open(path)
+                .with_context(|| format!("failed to open {}", path.display()))?,
+        )),
+        None => Box::new(stdin().lock()),
+    };
+
+    let result = execute(&mut interpreter, input, stdout().lock(), args.max_steps);
+    if let Some(profile) = &interpreter.profile {
+        eprintln!();
+        for line in profile.format_table(&interpreter.instructions, 20) {
+            eprintln!("{line}");
+        }
+        eprintln!(
+            "{} instructions in {:.2?}",
+            profile.total_count(),
+            profile.total_time()
+        );
+    }
+    result?;
+    Ok(())
+}
+
+/// Run until the program halts, reading input a line at a time when it runs out
+/// and writing output as it's produced. Returns the number of instructions executed.
+pub fn execute(
+    interpreter: &mut Interpreter,
+    mut input: impl BufRead,
+    mut output: impl Write,
+    max_steps: Option<u64>,
+) -> anyhow::Result<u64> {
+    let mut steps = 0;
+    let mut written = 0;
+    loop {
+        if max_steps.is_some_and(|max_steps| steps >= max_steps) {
+            output.flush().context("failed to write output")?;
+            bail!("program didn't halt within {steps} steps");
+        }
+
+        let halted = interpreter
+            .tick()
+            .with_context(|| format!("failed at instruction {}", interpreter.instruction_ptr))?;
+        if interpreter.output.len() > written {
+            output
+                .write_all(&interpreter.output[written..])
+                .context("failed to write output")?;
+            written = interpreter.output.len();
+        }
+        if halted {
+            break;
+        }
+
+        if interpreter.waitting_input {
+            output.flush().context("failed to write output")?;
+
+            let mut line = vec![];
+            input
+                .read_until(b'\n', &mut line)
+                .context("failed to read input")?;
+            if line.is_empty() {
+                interpreter.input_closed = true;
+            }
+            interpreter.input_buf.extend(line);
+        } else {
+            steps += 1;
+        }
+    }
+
+    output.flush().context("failed to write output")?;
+    Ok(steps)
+}
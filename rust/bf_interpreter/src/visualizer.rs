@@ -1,12 +1,14 @@
 use std::{
+    cell::Cell,
+    collections::BTreeSet,
     io::{stdout, Stdout},
     path::Path,
     str::FromStr,
     time::{Duration, Instant},
-    usize,
 };
 
 use anyhow::Context;
+use clap::ValueEnum;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -16,28 +18,86 @@ use functional_utils::FunctionalUtils;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Style, Styled, Stylize},
+    style::{Color, Style, Styled, Stylize},
     text::{Line, Span},
     widgets::{Block, Padding, Paragraph},
     Frame, Terminal,
 };
 
-use self::interpreter_state::InterpreterState;
-use crate::interpreter::Interpreter;
+use self::{browser::Browser, interpreter_state::InterpreterState};
+use crate::interpreter::{
+    heatmap::Heatmap, options::InterpreterOptions, profile::Profile, Interpreter,
+};
+pub mod browser;
 pub mod interpreter_state;
+pub mod output;
+
+/// Execution time per frame at [`Speed::Max`]
+const FRAME_BUDGET: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    /// Instructions per frame
+    Fixed(u64),
+    /// As many instructions as fit in [`FRAME_BUDGET`]
+    Max,
+}
 
 #[derive(Debug)]
 pub struct Visualizer {
     terminal: Option<Terminal<CrosstermBackend<Stdout>>>,
 
     interpreter: Option<(Interpreter, InterpreterState)>,
-    speed: u64,
+    options: InterpreterOptions,
+    speed: Speed,
+    /// Instructions per frame at [`Speed::Max`], adjusted to the time the last frame took
+    batch: u64,
+    /// Renders per second while running, frames in between only execute
+    turbo: Option<u32>,
+    /// Record the history of loaded programs, `/history off` skips it to run faster
+    history: bool,
+    /// Count memory accesses of loaded programs, `/heatmap off` skips it to run faster
+    heatmap: bool,
+    last_render: Option<Instant>,
+    /// Last error, shown next to the command input
+    message: Option<String>,
+    /// Open file browser, takes over the keyboard while open
+    browser: Option<Browser>,
+    /// Instruction indices which pause a running program
+    breakpoints: BTreeSet<usize>,
+    /// Lines the output pane is scrolled up from the end, clamped while rendering
+    output_scroll: Cell<usize>,
+    /// Height of the output pane when it was last rendered
+    output_page: Cell<usize>,
 
     input_buffer: String,
 }
 
 impl Visualizer {
-    pub fn init() -> anyhow::Result<Self> {
+    /// Without a terminal, for driving the visualizer through commands only
+    pub fn new(options: InterpreterOptions) -> Self {
+        Self {
+            terminal: None,
+
+            interpreter: None,
+            options,
+            speed: Speed::Fixed(1),
+            batch: 1024,
+            turbo: None,
+            history: true,
+            heatmap: true,
+            last_render: None,
+            message: None,
+            browser: None,
+            breakpoints: BTreeSet::new(),
+            output_scroll: Cell::new(0),
+            output_page: Cell::new(1),
+
+            input_buffer: String::new(),
+        }
+    }
+
+    pub fn init(options: InterpreterOptions) -> anyhow::Result<Self> {
         enable_raw_mode().context("failed to enable raw mode")?;
         stdout()
             .execute(EnterAlternateScreen)
@@ -47,11 +107,7 @@ impl Visualizer {
             terminal: Terminal::new(CrosstermBackend::new(stdout()))
                 .context("failed to create terminal")?
                 .some(),
-
-            interpreter: None,
-            speed: 1,
-
-            input_buffer: String::new(),
+            ..Self::new(options)
         }
         .into_ok()
     }
@@ -70,24 +126,94 @@ impl Visualizer {
             return Ok(true);
         }
 
-        let mut terminal = self.terminal.take().unwrap();
-        terminal
-            .draw(|frame| self.render(frame))
-            .context("failed to draw")?;
-        self.terminal = Some(terminal);
+        let running = self
+            .interpreter
+            .as_ref()
+            .is_some_and(|(_, state)| state.is_running());
+        let render = match self.turbo {
+            Some(per_second) if running => self
+                .last_render
+                .is_none_or(|last| last.elapsed() >= Duration::from_secs(1) / per_second.max(1)),
+            _ => true,
+        };
+        if let (Some(mut terminal), true) = (self.terminal.take(), render) {
+            terminal
+                .draw(|frame| self.render(frame))
+                .context("failed to draw")?;
+            self.terminal = Some(terminal);
+            self.last_render = Some(Instant::now());
+        }
+
+        self.advance();
 
-        match &mut self.interpreter {
-            Some((i, state @ InterpreterState::Running)) => {
-                for _ in 0..self.speed {
-                    if i.tick() {
+        Ok(false)
+    }
+
+    /// Run one frame worth of instructions of a running program
+    pub fn advance(&mut self) {
+        // turbo runs at max speed, rendering is what it limits
+        let (batch, adaptive) = match (self.speed, self.turbo) {
+            (Speed::Fixed(speed), None) => (speed, false),
+            _ => (self.batch, true),
+        };
+
+        if let Some((i, state @ InterpreterState::Running)) = &mut self.interpreter {
+            let start = Instant::now();
+            let mut executed = 0;
+            while executed < batch {
+                executed += 1;
+                match i.tick() {
+                    Ok(false) => {}
+                    Ok(true) => {
                         *state = InterpreterState::Paused;
+                        break;
                     }
+                    Err(err) => {
+                        *state = InterpreterState::Paused;
+                        self.message = Some(format!("{err} at instruction {}", i.instruction_ptr));
+                        break;
+                    }
+                }
+                // checked after the tick, so resuming on a breakpoint moves past it
+                if self.breakpoints.contains(&i.instruction_ptr) {
+                    *state = InterpreterState::Paused;
+                    break;
+                }
+                if i.waitting_input {
+                    break;
                 }
             }
-            _ => {}
+
+            // only a full batch says how long a batch takes
+            if adaptive && executed == batch {
+                let elapsed = start.elapsed().max(Duration::from_micros(1));
+                let scale = FRAME_BUDGET.as_secs_f64() / elapsed.as_secs_f64();
+                // changes at most 2x per frame so one slow frame doesn't collapse the batch
+                self.batch = ((batch as f64 * scale.clamp(0.5, 2.0)) as u64).max(1);
+            }
         }
+    }
 
-        Ok(false)
+    /// Load a program from a file, errors are shown like the ones of `/load_file`
+    pub fn load_file(&mut self, path: &Path) {
+        self.load(Interpreter::from_file(path));
+    }
+
+    /// Submit every line like it was typed into the command input.
+    /// Blank lines and lines starting with `#` are skipped, stops at the first error.
+    pub fn run_script(&mut self, script: &str) {
+        for (idx, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            self.submit(line);
+            if let Some(message) = &self.message {
+                self.message = Some(format!("script line {}: {message}", idx + 1));
+                return;
+            }
+        }
     }
 
     fn render(&self, frame: &mut Frame) {
@@ -96,10 +222,51 @@ impl Visualizer {
             .constraints([Constraint::Fill(1), Constraint::Length(1)])
             .split(frame.size());
 
-        self.render_interpreter(frame, layout[0]);
+        match &self.browser {
+            Some(browser) => self.render_browser(frame, layout[0], browser),
+            None => self.render_interpreter(frame, layout[0]),
+        }
         self.render_command_input(frame, layout[1]);
     }
 
+    fn render_browser(&self, frame: &mut Frame, rect: Rect, browser: &Browser) {
+        let matches = browser.matches();
+        let height = (rect.height as usize).saturating_sub(3);
+        // keep the selection in view
+        let skip = (browser.selected + 1).saturating_sub(height);
+
+        let mut lines = vec![Line::from(vec![
+            "filter: ".dim(),
+            Span::from(browser.filter.as_str()),
+        ])];
+        lines.extend(
+            matches
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .take(height)
+                .map(|(idx, path)| {
+                    let line = Line::from(path.display().to_string());
+                    if idx == browser.selected {
+                        line.reversed()
+                    } else {
+                        line
+                    }
+                }),
+        );
+        if matches.is_empty() {
+            lines.push(Line::from("no matching .bf files").dim());
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(format!(
+                "{} (enter to load, esc to close)",
+                browser.dir.display()
+            ))),
+            rect,
+        );
+    }
+
     fn render_interpreter(&self, frame: &mut Frame, rect: Rect) {
         match self.interpreter.as_ref() {
             None => frame.render_widget(
@@ -117,72 +284,171 @@ impl Visualizer {
         frame: &mut Frame,
         rect: Rect,
         interpreter: &Interpreter,
-        running: InterpreterState,
+        _running: InterpreterState,
     ) {
-        // region warpping output
-        let output = String::from_utf8_lossy(&interpreter.output);
-        let mut line_buf = String::new();
-        let mut output_lines = vec![];
-
-        for ch in output.chars() {
-            match ch {
-                '\n' => {
-                    output_lines.push(line_buf.clone());
-                    line_buf.clear()
-                }
-                _ => {
-                    line_buf.push(ch);
-
-                    if line_buf.len() >= rect.width as usize {
-                        output_lines.push(line_buf.clone());
-                        line_buf.clear()
-                    }
-                }
-            }
-        }
-
-        if !line_buf.is_empty() {
-            output_lines.push(line_buf);
-        }
-        // endregion
-
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
+                Constraint::Length(1),
+                Constraint::Length(3),
                 Constraint::Fill(1),
-                Constraint::Length(10.min((rect.height / 2).into())),
+                Constraint::Length(10.min(rect.height / 2)),
             ])
             .split(rect);
-        // TODO: memory and instructions
+        self.render_interpreter_status(frame, layout[0], interpreter);
+        if let Some(heatmap) = &interpreter.heatmap {
+            self.render_heatmap(frame, layout[1], interpreter, heatmap);
+        }
+        // TODO: instructions
+        if let Some(profile) = &interpreter.profile {
+            self.render_profile(frame, layout[2], interpreter, profile);
+        }
+
+        self.render_interpreter_output(frame, layout[3], interpreter);
+    }
 
-        self.render_interpreter_output(frame, layout[1], output_lines);
+    fn render_interpreter_status(&self, frame: &mut Frame, rect: Rect, interpreter: &Interpreter) {
+        let mut status = format!(
+            "ip {}  ptr {}  cell {}",
+            interpreter.instruction_ptr,
+            interpreter.memory_ptr,
+            interpreter
+                .memory
+                .get(interpreter.memory_ptr)
+                .copied()
+                .unwrap_or(0)
+        );
+        if let Some(history) = &interpreter.history {
+            status = format!("step {}  {status}", history.step);
+        }
+        if !self.breakpoints.is_empty() {
+            let breakpoints = self
+                .breakpoints
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>();
+            status = format!("{status}  breakpoints {}", breakpoints.join(","));
+        }
+        frame.render_widget(Paragraph::new(status).dim(), rect);
     }
 
-    fn render_interpreter_output(&self, frame: &mut Frame, rect: Rect, output: Vec<String>) {
-        let output_len = output.len();
-        output
+    /// One row of cells around the pointer, brighter the more they were accessed
+    fn render_heatmap(
+        &self,
+        frame: &mut Frame,
+        rect: Rect,
+        interpreter: &Interpreter,
+        heatmap: &Heatmap,
+    ) {
+        let width = (rect.width as usize).saturating_sub(2);
+        let ptr = interpreter.memory_ptr;
+        let len = interpreter.memory.len().max(ptr + 1);
+        // keep the pointer in view, from the start of the tape if it fits
+        let start = ptr.saturating_sub(width / 2).min(len.saturating_sub(width));
+        let end = (start + width).min(len);
+
+        let max = heatmap.max_accesses();
+        let cells = (start..end)
+            .map(|cell| {
+                let heat = heatmap.heat(cell, max);
+                let color = Color::Rgb((heat * 255.0) as u8, (heat * 96.0) as u8, 0);
+                let span = Span::from(if cell == ptr { "^" } else { " " }).bg(color);
+                if cell == ptr {
+                    span.white().bold()
+                } else {
+                    span
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let title = format!("memory heat {start}..{end}, max {max} accesses");
+        frame.render_widget(
+            Paragraph::new(Line::from(cells)).block(Block::bordered().title(title)),
+            rect,
+        );
+    }
+
+    fn render_profile(
+        &self,
+        frame: &mut Frame,
+        rect: Rect,
+        interpreter: &Interpreter,
+        profile: &Profile,
+    ) {
+        let mut lines = profile
+            .format_table(
+                &interpreter.instructions,
+                (rect.height as usize).saturating_sub(3),
+            )
+            .into_iter()
+            .map(Line::from)
+            .collect::<Vec<_>>();
+        lines.push(Line::from(format!(
+            "{} instructions in {:.2?}",
+            profile.total_count(),
+            profile.total_time()
+        )));
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("profile")),
+            rect,
+        );
+    }
+
+    fn render_interpreter_output(&self, frame: &mut Frame, rect: Rect, interpreter: &Interpreter) {
+        let output = output::wrap_lines(
+            &String::from_utf8_lossy(&interpreter.output),
+            rect.width as usize,
+        );
+        let mut height = rect.height as usize;
+        self.output_page.set(height.max(1));
+
+        let scroll = self
+            .output_scroll
+            .get()
+            .min(output.len().saturating_sub(height));
+        self.output_scroll.set(scroll);
+        if scroll > 0 {
+            // last row tells how to get back to the end
+            height = height.saturating_sub(1);
+        }
+
+        let mut lines = output
             .into_iter()
-            .skip(output_len.saturating_sub(rect.height as usize))
-            .map(|line| Line::from(line))
-            .collect::<Vec<_>>()
-            .then(|lines| frame.render_widget(Paragraph::new(lines), rect));
+            .rev()
+            .skip(scroll)
+            .take(height)
+            .rev()
+            .collect::<Vec<_>>();
+        if scroll > 0 {
+            lines.push(
+                Line::from(format!(
+                    "{scroll} more lines below, page down or end to follow the output"
+                ))
+                .dim(),
+            );
+        }
+        frame.render_widget(Paragraph::new(lines), rect);
+    }
+
+    /// The running program is blocked on `,` with nothing left to read
+    fn is_waiting_for_input(&self) -> bool {
+        self.interpreter.as_ref().is_some_and(|(i, state)| {
+            i.waitting_input && i.input_buf.is_empty() && state.is_running()
+        })
     }
 
     fn render_command_input(&self, frame: &mut Frame, rect: Rect) {
-        let indicator_style = if let Some(true) = self
-            .interpreter
-            .as_ref()
-            .map(|(i, state)| i.waitting_input && state.is_running())
-        {
-            Style::default().blue()
+        let indicator = if self.is_waiting_for_input() {
+            "input>".blue()
         } else {
-            Style::default()
+            ">".set_style(Style::default())
         };
 
         let extra_len = self
             .input_buffer
             .len()
-            .saturating_sub((rect.width as usize).saturating_sub(2));
+            .saturating_sub((rect.width as usize).saturating_sub(indicator.content.len() + 1));
 
         let input_buf = if extra_len > 0 {
             Span::from(
@@ -195,23 +461,79 @@ impl Visualizer {
             Span::from(&self.input_buffer)
         };
 
-        let line = Line::from(vec![">".set_style(indicator_style), input_buf]);
+        let mut line = Line::from(vec![indicator, input_buf]);
+        if let (Some(message), true) = (&self.message, self.input_buffer.is_empty()) {
+            line.spans.push(Span::from(message.as_str()).red());
+        } else if self.input_buffer.is_empty() && self.is_waiting_for_input() {
+            line.spans.push(
+                Span::from("the program is waiting, enter sends a line, ctrl-d closes the input")
+                    .dim(),
+            );
+        }
         frame.render_widget(Paragraph::new(line), rect);
     }
 
     /// Return: should exit
     fn handle_event(&mut self) -> anyhow::Result<bool> {
-        while event::poll(Duration::from_millis(1)).context("failed to pool event")? {
+        // frames between renders shouldn't wait for events,
+        // an idle visualizer waits so it doesn't redraw nonstop
+        let running = self
+            .interpreter
+            .as_ref()
+            .is_some_and(|(_, state)| state.is_running());
+        let timeout = if self.turbo.is_some() && running {
+            Duration::ZERO
+        } else {
+            Duration::from_millis(1)
+        };
+        while event::poll(timeout).context("failed to pool event")? {
             let event = event::read().context("failed to read event")?;
             match event {
                 Event::Key(key) => {
                     if key.kind == KeyEventKind::Press {
                         if key.modifiers == KeyModifiers::CONTROL {
-                            if key.code == KeyCode::Char('c') {
-                                return Ok(true);
+                            match key.code {
+                                KeyCode::Char('c') => return Ok(true),
+                                KeyCode::Char('d') => {
+                                    if let Some((interpreter, _)) = &mut self.interpreter {
+                                        interpreter.input_closed = true;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        } else if let Some(browser) = &mut self.browser {
+                            match key.code {
+                                KeyCode::Esc => self.browser = None,
+                                KeyCode::Up => browser.move_selection(-1),
+                                KeyCode::Down => browser.move_selection(1),
+                                KeyCode::Backspace => {
+                                    let mut filter = browser.filter.clone();
+                                    filter.pop();
+                                    browser.set_filter(filter);
+                                }
+                                KeyCode::Char(ch) => {
+                                    browser.set_filter(format!("{}{ch}", browser.filter));
+                                }
+                                KeyCode::Enter => {
+                                    if let Some(path) = browser.selected_path() {
+                                        self.browser = None;
+                                        self.load(Interpreter::from_file(&path));
+                                    }
+                                }
+                                _ => {}
                             }
                         } else {
+                            let scroll = self.output_scroll.get();
+                            let page = self.output_page.get();
                             match key.code {
+                                KeyCode::PageUp => {
+                                    self.output_scroll.set(scroll.saturating_add(page))
+                                }
+                                KeyCode::PageDown => {
+                                    self.output_scroll.set(scroll.saturating_sub(page))
+                                }
+                                KeyCode::Home => self.output_scroll.set(usize::MAX),
+                                KeyCode::End => self.output_scroll.set(0),
                                 KeyCode::Backspace => {
                                     self.input_buffer.pop();
                                 }
@@ -234,8 +556,13 @@ impl Visualizer {
     }
 
     fn handle_input(&mut self) {
-        let buffer: Box<str> = Box::from(self.input_buffer.as_str());
-        self.input_buffer.clear();
+        let buffer = std::mem::take(&mut self.input_buffer);
+        self.submit(&buffer);
+    }
+
+    /// A command if it starts with `/`, input for the program otherwise
+    pub fn submit(&mut self, buffer: &str) {
+        self.message = None;
 
         if let Some(command) = buffer.strip_prefix('/') {
             let mut command = command.split(' ');
@@ -246,14 +573,55 @@ impl Visualizer {
                 // TODO:
             }
         } else {
+            let waiting = self.is_waiting_for_input();
             if let Some((interpreter, _)) = self.interpreter.as_mut() {
                 interpreter.input_buf.extend(buffer.bytes());
+                if waiting {
+                    // answering a prompt, like a terminal would
+                    interpreter.input_buf.push_back(b'\n');
+                }
             } else {
                 // TODO:
             }
         }
     }
 
+    fn load(&mut self, interpreter: anyhow::Result<Interpreter>) {
+        match interpreter {
+            Ok(mut interpreter) => {
+                interpreter.options = self.options;
+                interpreter.set_history(self.history);
+                interpreter.set_heatmap(self.heatmap);
+                self.interpreter = Some((interpreter, InterpreterState::Paused));
+                // indices of the previous program
+                self.breakpoints.clear();
+            }
+            Err(err) => self.message = Some(format!("failed to load: {err:#}")),
+        }
+    }
+
+    /// Names and values like the command line flags, `/set tape_len none` makes the tape growable
+    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let options = &mut self.options;
+        match key {
+            "cell_width" => options.cell_width = ValueEnum::from_str(value, true)?,
+            "overflow" => options.overflow = ValueEnum::from_str(value, true)?,
+            "tape_edge" => options.tape_edge = ValueEnum::from_str(value, true)?,
+            "eof" => options.eof = ValueEnum::from_str(value, true)?,
+            "tape_len" => {
+                options.tape_len = match value {
+                    "none" => None,
+                    len => Some(
+                        len.parse()
+                            .map_err(|err| format!("invalid length: {err}"))?,
+                    ),
+                }
+            }
+            _ => return Err(format!("unknown option {key}")),
+        }
+        Ok(())
+    }
+
     fn handle_command<'item>(
         &mut self,
         name: &'item str,
@@ -267,14 +635,7 @@ impl Visualizer {
                     return;
                 }
 
-                match Interpreter::from_str(&code) {
-                    Ok(interpreter) => {
-                        self.interpreter = Some((interpreter, InterpreterState::Paused))
-                    }
-                    Err(_err) => {
-                        // TODO:
-                    }
-                }
+                self.load(Interpreter::from_str(&code));
             }
             "load_file" => {
                 let path = args.collect::<Vec<_>>().join(" ");
@@ -283,13 +644,30 @@ impl Visualizer {
                     return;
                 }
 
-                match Interpreter::from_file(Path::new(&path)) {
-                    Ok(interpreter) => {
-                        self.interpreter = Some((interpreter, InterpreterState::Paused))
-                    }
-                    Err(_err) => {
-                        // TODO:
-                    }
+                self.load(Interpreter::from_file(Path::new(&path)));
+            }
+            "browse" => {
+                let dir = args.collect::<Vec<_>>().join(" ");
+                let dir = if dir.is_empty() { "." } else { &dir };
+                match Browser::open(Path::new(dir)) {
+                    Ok(browser) => self.browser = Some(browser),
+                    Err(err) => self.message = Some(format!("failed to browse: {err:#}")),
+                }
+            }
+            "save_output" => {
+                let path = args.collect::<Vec<_>>().join(" ");
+                let Some((interpreter, _)) = &self.interpreter else {
+                    self.message = Some("no program loaded".to_string());
+                    return;
+                };
+                if path.is_empty() {
+                    self.message = Some("usage: /save_output <path>".to_string());
+                    return;
+                }
+
+                // raw bytes, `\r` and escape sequences included
+                if let Err(err) = std::fs::write(&path, &interpreter.output) {
+                    self.message = Some(format!("failed to save output: {err}"));
                 }
             }
             "run" => {
@@ -302,16 +680,186 @@ impl Visualizer {
                     *running = InterpreterState::Paused;
                 }
             }
-            "speed" => {
-                if let Some(speed) = args.next() {
-                    if let Ok(speed) = speed.parse::<u64>() {
-                        self.speed = speed
+            "step" => {
+                let steps = args
+                    .next()
+                    .and_then(|it| it.parse::<u64>().ok())
+                    .unwrap_or(1);
+                if let Some((interpreter, running)) = &mut self.interpreter {
+                    *running = InterpreterState::Paused;
+                    for _ in 0..steps {
+                        match interpreter.tick() {
+                            Ok(false) if !interpreter.waitting_input => {}
+                            Ok(_) => break,
+                            Err(err) => {
+                                self.message = Some(format!(
+                                    "{err} at instruction {}",
+                                    interpreter.instruction_ptr
+                                ));
+                                break;
+                            }
+                        }
                     }
                 }
             }
-            _ => {
-                // TODO:
+            "back" => {
+                let steps = args
+                    .next()
+                    .and_then(|it| it.parse::<u64>().ok())
+                    .unwrap_or(1);
+                if let Some((interpreter, running)) = &mut self.interpreter {
+                    *running = InterpreterState::Paused;
+                    match interpreter.back(steps) {
+                        Ok(undone) if undone < steps => {
+                            self.message = Some(format!("only {undone} steps of history"))
+                        }
+                        Ok(_) => {}
+                        Err(err) => self.message = Some(format!("failed to step back: {err}")),
+                    }
+                }
+            }
+            "profile" => {
+                if let Some((interpreter, _)) = &mut self.interpreter {
+                    let enabled = match args.next() {
+                        Some("on") => true,
+                        Some("off") => false,
+                        _ => interpreter.profile.is_none(),
+                    };
+                    interpreter.set_profiling(enabled);
+                }
+            }
+            "history" => {
+                self.history = match args.next() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => !self.history,
+                };
+                if let Some((interpreter, _)) = &mut self.interpreter {
+                    interpreter.set_history(self.history);
+                }
+            }
+            "heatmap" => {
+                self.heatmap = match args.next() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => !self.heatmap,
+                };
+                if let Some((interpreter, _)) = &mut self.interpreter {
+                    interpreter.set_heatmap(self.heatmap);
+                }
             }
+            "resetstats" => {
+                if let Some((interpreter, _)) = &mut self.interpreter {
+                    interpreter.set_heatmap(self.heatmap);
+                    if interpreter.profile.is_some() {
+                        interpreter.set_profiling(true);
+                    }
+                }
+            }
+            "set" => {
+                let (Some(key), Some(value)) = (args.next(), args.next()) else {
+                    self.message = Some("usage: /set <option> <value>".to_string());
+                    return;
+                };
+                match self.set_option(key, value) {
+                    Ok(()) => {
+                        if let Some((interpreter, _)) = &mut self.interpreter {
+                            interpreter.options = self.options;
+                            // a narrower cell width leaves cells out of its range
+                            for cell in &mut interpreter.memory {
+                                *cell = self.options.fit(*cell);
+                            }
+                        }
+                    }
+                    Err(err) => self.message = Some(err),
+                }
+            }
+            "break" => {
+                let Some(idx) = args.next().and_then(|it| it.parse::<usize>().ok()) else {
+                    self.message = Some("usage: /break <instruction index>".to_string());
+                    return;
+                };
+                // toggles
+                if !self.breakpoints.remove(&idx) {
+                    self.breakpoints.insert(idx);
+                }
+            }
+            "speed" => match args.next().map(|it| (it, it.parse::<u64>())) {
+                Some(("max", _)) => self.speed = Speed::Max,
+                Some((_, Ok(speed))) => self.speed = Speed::Fixed(speed),
+                _ => self.message = Some("usage: /speed <instructions per frame|max>".to_string()),
+            },
+            "turbo" => match args.next() {
+                None => self.turbo = Some(4),
+                Some("off") => self.turbo = None,
+                Some(per_second) => match per_second.parse::<u32>() {
+                    Ok(per_second) if per_second > 0 => self.turbo = Some(per_second),
+                    _ => self.message = Some("usage: /turbo [renders per second|off]".to_string()),
+                },
+            },
+            _ => self.message = Some(format!("unknown command /{name}")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded(visualizer: &Visualizer) -> (&Interpreter, InterpreterState) {
+        let (interpreter, state) = visualizer.interpreter.as_ref().expect("no program loaded");
+        (interpreter, *state)
+    }
+
+    #[test]
+    fn test_script() {
+        let mut visualizer = Visualizer::new(InterpreterOptions::default());
+        visualizer.run_script(
+            "
+            # copies 3 into the next cell
+            /load +++[>+<-]>.
+            /speed 100
+            /break 5
+            /run
+            ",
+        );
+        assert_eq!(visualizer.message, None);
+
+        // pauses before running the breakpoint's instruction
+        visualizer.advance();
+        let (interpreter, state) = loaded(&visualizer);
+        assert!(state.is_paused());
+        assert_eq!(interpreter.instruction_ptr, 5);
+        assert_eq!(interpreter.history.as_ref().unwrap().step, 5);
+
+        visualizer.run_script("/step 2");
+        let (interpreter, _) = loaded(&visualizer);
+        assert_eq!(interpreter.instruction_ptr, 7);
+        assert_eq!((interpreter.memory_ptr, interpreter.memory[1]), (0, 1));
+
+        visualizer.run_script("/back 3");
+        let (interpreter, _) = loaded(&visualizer);
+        assert_eq!(interpreter.instruction_ptr, 4);
+        assert_eq!(interpreter.history.as_ref().unwrap().step, 4);
+        assert_eq!(interpreter.memory.get(1).copied().unwrap_or(0), 0);
+
+        // `/break` toggles, without the breakpoint it runs to the end
+        visualizer.run_script("/break 5\n/run");
+        visualizer.advance();
+        let (interpreter, state) = loaded(&visualizer);
+        assert!(state.is_paused());
+        assert_eq!(interpreter.output, [3]);
+    }
+
+    #[test]
+    fn test_script_error() {
+        let mut visualizer = Visualizer::new(InterpreterOptions::default());
+        visualizer.run_script("/load +\n\n/bogus\n/step");
+        assert_eq!(
+            visualizer.message.as_deref(),
+            Some("script line 3: unknown command /bogus")
+        );
+        // stops at the failing line
+        assert_eq!(loaded(&visualizer).0.instruction_ptr, 0);
+    }
+}
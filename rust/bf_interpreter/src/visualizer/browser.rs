@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// List of the `.bf` files under a directory, filtered as the user types
+#[derive(Debug)]
+pub struct Browser {
+    pub dir: PathBuf,
+    /// Relative to `dir`, sorted
+    files: Vec<PathBuf>,
+    pub filter: String,
+    /// Index into [`Browser::matches`]
+    pub selected: usize,
+}
+
+impl Browser {
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        let mut files = vec![];
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let entries = std::fs::read_dir(&current)
+                .with_context(|| format!("failed to read {}", current.display()))?;
+            for entry in entries {
+                let path = entry.context("failed to read directory entry")?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.extension().is_some_and(|it| it == "bf") {
+                    files.push(path.strip_prefix(dir)?.to_path_buf());
+                }
+            }
+        }
+        files.sort();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            files,
+            filter: String::new(),
+            selected: 0,
+        })
+    }
+
+    /// Files matching the filter, best match first
+    pub fn matches(&self) -> Vec<&Path> {
+        let mut matches = self
+            .files
+            .iter()
+            .filter_map(|path| {
+                fuzzy_score(&self.filter, &path.to_string_lossy()).map(|score| (score, path))
+            })
+            .collect::<Vec<_>>();
+        // stable, equal scores stay sorted by path
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches
+            .into_iter()
+            .map(|(_, path)| path.as_path())
+            .collect()
+    }
+
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.matches()
+            .get(self.selected)
+            .map(|path| self.dir.join(path))
+    }
+
+    pub fn move_selection(&mut self, offset: isize) {
+        let len = self.matches().len();
+        self.selected = self
+            .selected
+            .saturating_add_signed(offset)
+            .min(len.saturating_sub(1));
+    }
+
+    pub fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.selected = 0;
+    }
+}
+
+/// `None` unless the chars of `pattern` appear in order in `text`, ignoring case.
+/// Consecutive matches and matches at the start of a word score higher.
+fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text = text.chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match = None;
+    for pattern_ch in pattern.chars().flat_map(char::to_lowercase) {
+        let offset = text[pos..]
+            .iter()
+            .position(|ch| ch.to_lowercase().eq([pattern_ch]))?;
+        let idx = pos + offset;
+
+        score += 1;
+        if last_match.is_some_and(|last| last + 1 == idx) {
+            score += 5;
+        }
+        if idx == 0 || !text[idx - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last_match = Some(idx);
+        pos = idx + 1;
+    }
+
+    // shorter paths first among otherwise equal matches
+    Some(score * 1000 - text.len() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subsequence() {
+        assert!(fuzzy_score("hlo", "hello.bf").is_some());
+        assert!(fuzzy_score("HeL", "hello.bf").is_some());
+        assert!(fuzzy_score("", "hello.bf").is_some());
+        assert_eq!(fuzzy_score("ohl", "hello.bf"), None);
+        assert_eq!(fuzzy_score("hellox", "hello.bf"), None);
+        assert_eq!(fuzzy_score("a", ""), None);
+    }
+
+    #[test]
+    fn test_ranking() {
+        let score = |text| fuzzy_score("rot", text).unwrap();
+        // contiguous beats scattered
+        assert!(score("xrotx.bf") > score("rxoxt.bf"));
+        // word starts beat the middle of a word
+        assert!(score("tests/rot13.bf") > score("tests/carrot.bf"));
+        // shorter first when nothing else differs
+        assert!(score("rot.bf") > score("rot13.bf"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let browser = Browser {
+            dir: PathBuf::from("programs"),
+            files: ["hello.bf", "r/o/t.bf", "rot13.bf", "squares.bf"]
+                .map(PathBuf::from)
+                .to_vec(),
+            filter: "rot".to_string(),
+            selected: 0,
+        };
+        assert_eq!(
+            browser.matches(),
+            [Path::new("rot13.bf"), Path::new("r/o/t.bf")]
+        );
+        assert_eq!(
+            browser.selected_path(),
+            Some(PathBuf::from("programs/rot13.bf"))
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthChar;
+
+/// One terminal column, the second column of a wide char has an empty symbol
+type Cell = (String, Style);
+
+/// Split program output into lines at most `width` columns wide, like a terminal would show it.
+/// `\r` goes back to the start of the line so following chars overwrite it,
+/// SGR escape sequences style the following chars, other escape sequences are dropped.
+pub fn wrap_lines(output: &str, width: usize) -> Vec<Line<'static>> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    let mut line = Vec::<Cell>::new();
+    let mut col = 0usize;
+    let mut style = Style::default();
+
+    let mut chars = output.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\n' => {
+                lines.push(to_line(std::mem::take(&mut line)));
+                col = 0;
+            }
+            '\r' => col = 0,
+            '\x1b' => {
+                // CSI sequences end with a byte in `@..=~`, other sequences end with
+                // the first byte after ` ..=/`, like `ESC ( B`
+                match chars.next() {
+                    Some('[') => {}
+                    Some(' '..='/') => {
+                        chars.by_ref().find(|ch| !(' '..='/').contains(ch));
+                        continue;
+                    }
+                    _ => continue,
+                }
+                let mut params = String::new();
+                for ch in chars.by_ref() {
+                    if ('@'..='~').contains(&ch) {
+                        if ch == 'm' {
+                            style = apply_sgr(style, &params);
+                        }
+                        break;
+                    }
+                    params.push(ch);
+                }
+            }
+            _ => match ch.width() {
+                None => {}
+                // combining marks stay with the char before them
+                Some(0) => {
+                    if let Some((symbol, _)) = col.checked_sub(1).and_then(|it| line.get_mut(it)) {
+                        symbol.push(ch);
+                    }
+                }
+                Some(ch_width) => {
+                    if col + ch_width > width {
+                        lines.push(to_line(std::mem::take(&mut line)));
+                        col = 0;
+                    }
+                    put(&mut line, col, ch, ch_width, style);
+                    col += ch_width;
+                }
+            },
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(to_line(line));
+    }
+    lines
+}
+
+/// Write `ch` at `col`, blanking what's left of wide chars it partly covers
+fn put(line: &mut Vec<Cell>, col: usize, ch: char, ch_width: usize, style: Style) {
+    let end = col + ch_width;
+    if line.len() < end {
+        line.resize(end, (" ".to_string(), Style::default()));
+    }
+    if line[col].0.is_empty() {
+        line[col - 1].0 = " ".to_string();
+    }
+    if line.get(end).is_some_and(|(symbol, _)| symbol.is_empty()) {
+        line[end].0 = " ".to_string();
+    }
+
+    line[col] = (ch.to_string(), style);
+    for cell in &mut line[col + 1..end] {
+        *cell = (String::new(), style);
+    }
+}
+
+/// Join the columns into spans of the same style
+fn to_line(cells: Vec<Cell>) -> Line<'static> {
+    let mut spans = Vec::<Span>::new();
+    for (symbol, style) in cells {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(&symbol),
+            _ => spans.push(Span::styled(symbol, style)),
+        }
+    }
+    Line::from(spans)
+}
+
+/// Style after the SGR sequence `ESC [ {params} m`
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut params = params
+        .split([';', ':'])
+        .map(|param| param.parse::<u8>().unwrap_or(0));
+
+    while let Some(param) = params.next() {
+        style = match param {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(param - 30)),
+            38 => match extended_color(&mut params) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed(param - 40)),
+            48 => match extended_color(&mut params) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed(param - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(param - 100 + 8)),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// `5;n` or `2;r;g;b` after a 38 or 48
+fn extended_color(params: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match params.next()? {
+        5 => Some(Color::Indexed(params.next()?)),
+        2 => Some(Color::Rgb(params.next()?, params.next()?, params.next()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            text(&wrap_lines("abcdefg\nhi\n\nj", 3)),
+            ["abc", "def", "g", "hi", "", "j"]
+        );
+        assert_eq!(text(&wrap_lines("abc\n", 3)), ["abc"]);
+        assert_eq!(text(&wrap_lines("", 3)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_carriage_return() {
+        assert_eq!(
+            text(&wrap_lines("loading 10%\rloading 100%\n", 20)),
+            ["loading 100%"]
+        );
+        assert_eq!(text(&wrap_lines("abcdef\rx", 20)), ["xbcdef"]);
+        // `\r` only returns to the start of the wrapped row
+        assert_eq!(text(&wrap_lines("abcde\rx", 3)), ["abc", "xe"]);
+    }
+
+    #[test]
+    fn test_wide_chars() {
+        assert_eq!(text(&wrap_lines("a中文b", 4)), ["a中", "文b"]);
+        assert_eq!(text(&wrap_lines("中文", 3)), ["中", "文"]);
+        // overwriting half of a wide char blanks the other half
+        assert_eq!(text(&wrap_lines("中文\rx", 4)), ["x 文"]);
+        assert_eq!(text(&wrap_lines("a中\rab", 4)), ["ab "]);
+        assert_eq!(text(&wrap_lines("e\u{301}x", 4)), ["e\u{301}x"]);
+    }
+
+    #[test]
+    fn test_escapes() {
+        let lines = wrap_lines("\x1b[1;31mred\x1b[0m plain\x1b[2K\x1b(B", 4);
+        assert_eq!(text(&lines), ["red ", "plai", "n"]);
+
+        let red = Style::default()
+            .add_modifier(Modifier::BOLD)
+            .fg(Color::Indexed(1));
+        assert_eq!(lines[0].spans, [Span::styled("red", red), Span::raw(" ")]);
+
+        let lines = wrap_lines("\x1b[38;5;208ma\x1b[48;2;1;2;3mb\x1b[39;49mc", 10);
+        assert_eq!(
+            lines[0].spans,
+            [
+                Span::styled("a", Style::default().fg(Color::Indexed(208))),
+                Span::styled(
+                    "b",
+                    Style::default()
+                        .fg(Color::Indexed(208))
+                        .bg(Color::Rgb(1, 2, 3))
+                ),
+                Span::styled("c", Style::default().fg(Color::Reset).bg(Color::Reset)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Standard programs run with the headless `run` subcommand, and with `bench`,
+//! which fails if the optimized and compiled forms disagree with the interpreter.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+/// Path relative to the crate
+fn program(path: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+fn bf(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bf_interpreter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the interpreter");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .expect("failed to write input");
+    child
+        .wait_with_output()
+        .expect("failed to run the interpreter")
+}
+
+/// Output of a program which must halt within `max_steps`
+fn run(path: &str, max_steps: u64, args: &[&str], input: &str) -> String {
+    let path = program(path);
+    let max_steps = max_steps.to_string();
+    let mut run_args = vec!["run", path.to_str().unwrap(), "--max-steps", &max_steps];
+    run_args.extend(args);
+
+    let output = bf(&run_args, input);
+    assert!(
+        output.status.success(),
+        "{} failed: {}",
+        path.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output isn't utf-8")
+}
+
+#[test]
+fn test_hello() {
+    assert_eq!(run("programs/hello.bf", 1_000, &[], ""), "Hello World!\n");
+}
+
+#[test]
+fn test_squares() {
+    let expected = (0..=100)
+        .map(|n| format!("{}\n", n * n))
+        .collect::<String>();
+    assert_eq!(run("programs/squares.bf", 10_000_000, &[], ""), expected);
+}
+
+#[test]
+fn test_rot13() {
+    let input = "Hello, World!\nabc xyz ABC XYZ 0123\n";
+    let expected = "Uryyb, Jbeyq!\nnop klm NOP KLM 0123\n";
+    for eof in ["unchanged", "-1"] {
+        let eof = format!("--eof={eof}");
+        assert_eq!(
+            run("tests/programs/rot13.bf", 100_000, &[&eof], input),
+            expected
+        );
+    }
+}
+
+#[test]
+fn test_cell_size() {
+    for width in ["8", "16", "32"] {
+        assert_eq!(
+            run(
+                "tests/programs/cell_size.bf",
+                1_000_000,
+                &["--cell-width", width],
+                ""
+            ),
+            format!("{width} bit cells\n")
+        );
+    }
+}
+
+#[test]
+fn test_quine() {
+    let source = std::fs::read_to_string(program("tests/programs/quine.bf")).unwrap();
+    assert_eq!(run("tests/programs/quine.bf", 1_000_000, &[], ""), source);
+}
+
+#[test]
+fn test_nested_loops() {
+    assert_eq!(
+        run("tests/programs/nested_loops.bf", 1_000_000, &[], ""),
+        "A\n"
+    );
+}
+
+#[test]
+fn test_step_limit() {
+    let path = program("tests/programs/nested_loops.bf");
+    let output = bf(&["run", path.to_str().unwrap(), "--max-steps", "1000"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("didn't halt within 1000 steps"));
+}
+
+#[test]
+fn test_optimizer_agrees() {
+    // bench reads the input from the program, after a `/end` line
+    let rot13 = std::env::temp_dir().join("bf_interpreter_test_rot13.bf");
+    let code = std::fs::read_to_string(program("tests/programs/rot13.bf")).unwrap();
+    std::fs::write(&rot13, format!("{code}/end\nHello, World!\n")).unwrap();
+
+    let mut args = vec!["bench", "--eof", "unchanged", rot13.to_str().unwrap()];
+    let paths = [
+        "programs/hello.bf",
+        "programs/squares.bf",
+        "tests/programs/cell_size.bf",
+        "tests/programs/quine.bf",
+        "tests/programs/nested_loops.bf",
+    ]
+    .map(program);
+    args.extend(paths.iter().map(|path| path.to_str().unwrap()));
+
+    let output = bf(&args, "");
+    std::fs::remove_file(&rot13).ok();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_optimizer_agrees_saturate() {
+    // `-` right after a saturating run of `+`, and `<` after `>`
+    let saturate = std::env::temp_dir().join("bf_interpreter_test_saturate.bf");
+    let code = format!("{}-.>>+<-<.[-]-+.>.", "+".repeat(256));
+    std::fs::write(&saturate, code).unwrap();
+
+    let output = bf(
+        &["run", saturate.to_str().unwrap(), "--overflow", "saturate"],
+        "",
+    );
+    assert_eq!(output.stdout, [0xfe, 0xfe, 0x01, 0x00]);
+
+    let hello = program("programs/hello.bf");
+    let args = [
+        "bench",
+        "--overflow",
+        "saturate",
+        saturate.to_str().unwrap(),
+        hello.to_str().unwrap(),
+    ];
+    let output = bf(&args, "");
+    std::fs::remove_file(&saturate).ok();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,22 @@
+prints the width of a cell in bits
+
+calculate 256 and test if it's zero
+++++++++[>++++++++<-]>[<++++>-]
++<[>-<
+    not zero so multiply by 256 again to get 65536
+    [>++++<-]>[<++++++++>-]<[>++++++++<-]
+    +>[>
+        print 32
+        ++++++++++[>+++++<-]>+.-.[-]<
+    <[-]<->] <[>>
+        print 16
+        +++++++[>+++++++<-]>.+++++.[-]<
+<<-]] >[>
+    print 8
+    ++++++++[>+++++++<-]>.[-]<
+<-]<
+print " bit cells" and a newline
++++++++++++[>+++>+++++++++>+++++++++>+<<<<-]>-.>-.+++++++.+++++++++++.<.
+>>.++.+++++++..<-.>>-.
+clean up the used cells
+[[-]<]
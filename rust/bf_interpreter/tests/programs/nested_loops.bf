@@ -0,0 +1,7 @@
+five loops of ten nested in each other add one to the last cell 100000 times
+then prints the cell with 95 subtracted and a newline
+8 bit cells give 160 and print an A
+
+++++++++++[>++++++++++[>++++++++++[>++++++++++[>++++++++++[>+<-]<-]<-]<-]<-]
+>>>>>-----------------------------------------------------------------------
+------------------------.[-]++++++++++.
@@ -0,0 +1,65 @@
+use std::time::Instant;
+
+use clap::Args;
+
+use crate::{
+    board::{Board, EdgeMode},
+    engine::{Engine, EngineKind},
+    gpu::GpuBoard,
+    hashlife::HashLife,
+};
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Width and height of the board in cells
+    #[arg(long, default_value_t = 4096)]
+    pub size: usize,
+    /// Number of generations to run
+    #[arg(long, default_value_t = 1000)]
+    pub gens: u64,
+    /// Seed of the initial random soup
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Density of the initial random soup
+    #[arg(long, default_value_t = 0.5)]
+    pub density: f64,
+    /// How neighbors outside of the board are treated
+    #[arg(long, value_enum, default_value_t = EdgeMode::Dead)]
+    pub edge_mode: EdgeMode,
+    /// Simulation engine to measure
+    #[arg(long, value_enum, default_value_t = EngineKind::Dense)]
+    pub engine: EngineKind,
+}
+
+pub fn bench(args: &BenchArgs) -> anyhow::Result<()> {
+    let size = args.size.max(1);
+
+    let mut soup = Board::new(size, size);
+    soup.edge_mode = args.edge_mode;
+    soup.rand(args.seed, args.density);
+    let mut board: Box<dyn Engine> = match args.engine {
+        EngineKind::Dense => Box::new(soup),
+        EngineKind::HashLife => Box::new(HashLife::from_board(&soup)),
+        EngineKind::Gpu => Box::new(GpuBoard::from_board(&soup)?),
+    };
+
+    // warm up caches and the thread pool
+    board.step(args.gens.min(10));
+
+    let start = Instant::now();
+    board.step(args.gens);
+    let elapsed = start.elapsed();
+
+    let secs = elapsed.as_secs_f64();
+    let gens_per_sec = args.gens as f64 / secs;
+    let cells_per_sec = gens_per_sec * (size * size) as f64;
+    println!(
+        "{size}x{size}, {} generations in {elapsed:.2?}, population: {}",
+        args.gens,
+        board.population()
+    );
+    println!("generations/sec: {gens_per_sec: >12.2}");
+    println!("cell updates/sec: {:>11.2}M", cells_per_sec / 1_000_000.0);
+
+    Ok(())
+}
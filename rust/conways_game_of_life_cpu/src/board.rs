@@ -0,0 +1,458 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use clap::ValueEnum;
+use image::{DynamicImage, ImageBuffer, Luma};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+
+use crate::pattern::Pattern;
+
+/// How neighbors outside of the board are treated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EdgeMode {
+    /// Cells outside of the board are always dead.
+    #[default]
+    Dead,
+    /// The board wraps around, left/right and top/bottom edges are adjacent.
+    Wrap,
+    /// Cells outside of the board mirror the nearest edge cell.
+    Mirror,
+}
+
+impl EdgeMode {
+    pub fn next(self) -> Self {
+        match self {
+            EdgeMode::Dead => EdgeMode::Wrap,
+            EdgeMode::Wrap => EdgeMode::Mirror,
+            EdgeMode::Mirror => EdgeMode::Dead,
+        }
+    }
+
+    /// Resolve `pos + offset` into a coordinate in `0..len`,
+    /// `None` means the neighbor is dead.
+    pub fn resolve(self, pos: usize, offset: isize, len: usize) -> Option<usize> {
+        match (pos.checked_add_signed(offset), self) {
+            (Some(pos), _) if pos < len => Some(pos),
+            (_, EdgeMode::Dead) => None,
+            (_, EdgeMode::Wrap) => Some((pos as isize + offset).rem_euclid(len as isize) as usize),
+            (_, EdgeMode::Mirror) => Some(pos),
+        }
+    }
+}
+
+/// Random fill used when there's no pattern to load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Soup {
+    pub seed: u64,
+    /// Probability of a cell starting alive
+    pub density: f64,
+}
+
+impl Default for Soup {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            density: 0.5,
+        }
+    }
+}
+
+const WORD_BITS: usize = u64::BITS as usize;
+/// Tiles are one word wide and `TILE_HEIGHT` rows tall
+const TILE_HEIGHT: usize = 64;
+
+/// Bit-packed board, each row is stored as `words_per_row` words,
+/// cell `x` lives in bit `x % 64` of word `x / 64`.
+/// Padding bits after the last cell of a row are always zero.
+#[derive(Debug, Clone)]
+pub struct Board {
+    data: Vec<u64>,
+    data_out: Vec<u64>,
+
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    /// Per tile, whether any of its cells changed in the last update.
+    /// Tiles with no change in their 3x3 tile neighborhood are skipped,
+    /// their cells in `data_out` are still equal to `data`
+    changed: Vec<bool>,
+    /// Edge mode of the last update, changing it invalidates `changed`
+    changed_edge_mode: EdgeMode,
+    generation: u64,
+    births: u64,
+    deaths: u64,
+
+    pub edge_mode: EdgeMode,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+
+        Self {
+            data: vec![0; words_per_row * height],
+            data_out: vec![0; words_per_row * height],
+
+            width,
+            height,
+            words_per_row,
+            changed: vec![true; words_per_row * height.div_ceil(TILE_HEIGHT)],
+            changed_edge_mode: EdgeMode::default(),
+            generation: 0,
+            births: 0,
+            deaths: 0,
+
+            edge_mode: EdgeMode::default(),
+        }
+    }
+
+    /// Board with `pattern` centered on it, or random `soup` if there's none.
+    pub fn from_pattern(
+        width: usize,
+        height: usize,
+        pattern: Option<&Pattern>,
+        soup: Soup,
+    ) -> Self {
+        let mut this = Self::new(width, height);
+        if let Some(pattern) = pattern {
+            this.place(
+                pattern,
+                width.saturating_sub(pattern.width) / 2,
+                height.saturating_sub(pattern.height) / 2,
+            );
+        } else {
+            this.rand(soup.seed, soup.density);
+        }
+        this
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of updates since the board was created
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Number of alive cells
+    pub fn population(&self) -> u64 {
+        self.data.iter().map(|word| word.count_ones() as u64).sum()
+    }
+
+    /// Cells born in the last update
+    pub fn births(&self) -> u64 {
+        self.births
+    }
+
+    /// Cells died in the last update
+    pub fn deaths(&self) -> u64 {
+        self.deaths
+    }
+
+    /// Hash of the cell state, used to detect repeating boards
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.width.hash(&mut hasher);
+        self.data.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn rand(&mut self, seed: u64, probability: f64) {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.set(x, y, rng.gen_bool(probability));
+            }
+        }
+    }
+
+    /// Copy `pattern` onto the board with its top left corner at `(x, y)`,
+    /// cells falling outside of the board are dropped.
+    pub fn place(&mut self, pattern: &Pattern, x: usize, y: usize) {
+        for pattern_y in 0..pattern.height {
+            for pattern_x in 0..pattern.width {
+                let (x, y) = (x + pattern_x, y + pattern_y);
+                if x < self.width && y < self.height {
+                    self.set(x, y, pattern.get(pattern_x, pattern_y));
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let (idx, bit) = self.coord_to_idx(x, y);
+        self.data[idx] >> bit & 1 == 1
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        let (idx, bit) = self.coord_to_idx(x, y);
+        self.changed[y / TILE_HEIGHT * self.words_per_row + x / WORD_BITS] = true;
+        if value {
+            self.data[idx] |= 1 << bit;
+        } else {
+            self.data[idx] &= !(1 << bit);
+        }
+    }
+
+    /// Word index and bit offset of a cell
+    fn coord_to_idx(&self, x: usize, y: usize) -> (usize, usize) {
+        debug_assert!(x < self.width && y < self.height);
+        (self.words_per_row * y + x / WORD_BITS, x % WORD_BITS)
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.data[self.words_per_row * y..self.words_per_row * (y + 1)]
+    }
+
+    /// Mask of the valid cells in the last word of a row
+    fn last_word_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Words of a row shifted by one cell, with the cells outside of the board
+    /// filled according to the edge mode.
+    /// Returns `(west, east)`, where `west` holds the left neighbor of every cell
+    /// and `east` holds the right neighbor.
+    fn shifted(&self, row: &[u64], idx: usize) -> (u64, u64) {
+        let edge_cell = |x: Option<usize>| match x {
+            Some(x) => row[x / WORD_BITS] >> (x % WORD_BITS) & 1,
+            None => 0,
+        };
+
+        let word = row[idx];
+
+        let west_carry = if idx > 0 {
+            row[idx - 1] >> (WORD_BITS - 1)
+        } else {
+            edge_cell(self.edge_mode.resolve(0, -1, self.width))
+        };
+        let west = word << 1 | west_carry;
+
+        let mut east = word >> 1;
+        if idx + 1 < row.len() {
+            east |= row[idx + 1] << (WORD_BITS - 1);
+        } else {
+            let last_bit = (self.width - 1) % WORD_BITS;
+            let edge = edge_cell(self.edge_mode.resolve(self.width - 1, 1, self.width));
+            east |= edge << last_bit;
+        }
+
+        (west, east)
+    }
+
+    /// Tiles to recompute, the ones with a changed tile in their neighborhood
+    fn active_tiles(&self) -> Vec<bool> {
+        let (tiles_x, tiles_y) = (self.words_per_row, self.height.div_ceil(TILE_HEIGHT));
+        let changed = |x: usize, y: usize, x_off: isize, y_off: isize| match (
+            self.edge_mode.resolve(x, x_off, tiles_x),
+            self.edge_mode.resolve(y, y_off, tiles_y),
+        ) {
+            (Some(x), Some(y)) => self.changed[y * tiles_x + x],
+            _ => false,
+        };
+
+        (0..tiles_y)
+            .flat_map(|y| (0..tiles_x).map(move |x| (x, y)))
+            .map(|(x, y)| (-1..=1).any(|y_off| (-1..=1).any(|x_off| changed(x, y, x_off, y_off))))
+            .collect()
+    }
+
+    /// Bit-sliced neighbor count, each bit position is an independent cell.
+    #[inline]
+    fn next_word(neighbors: [u64; 8], alive: u64) -> u64 {
+        let (mut ones, mut twos, mut four_or_more) = (0_u64, 0_u64, 0_u64);
+        for neighbor in neighbors {
+            let carry_ones = ones & neighbor;
+            ones ^= neighbor;
+            let carry_twos = twos & carry_ones;
+            twos ^= carry_ones;
+            four_or_more |= carry_twos;
+        }
+
+        // 2 neighbors: survive, 3 neighbors: survive or birth
+        !four_or_more & twos & (ones | alive)
+    }
+
+    /// Compute the next generation into the back buffer, then swap the buffers.
+    /// Rows are split into bands of tiles updated in parallel,
+    /// tiles whose neighborhood didn't change in the last update are skipped.
+    pub fn update(&mut self) {
+        self.generation += 1;
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        if self.edge_mode != self.changed_edge_mode {
+            self.changed.fill(true);
+            self.changed_edge_mode = self.edge_mode;
+        }
+        let active = self.active_tiles();
+        let mut changed = vec![false; self.changed.len()];
+
+        let mut data_out = std::mem::take(&mut self.data_out);
+        let zero_row = vec![0; self.words_per_row];
+        let last_word_mask = self.last_word_mask();
+
+        let (births, deaths) = data_out
+            .par_chunks_mut(self.words_per_row * TILE_HEIGHT)
+            .zip(changed.par_chunks_mut(self.words_per_row))
+            .zip(active.par_chunks(self.words_per_row))
+            .enumerate()
+            .map(|(tile_y, ((band, changed), active))| {
+                let (mut births, mut deaths) = (0, 0);
+                for (row, out) in band.chunks_mut(self.words_per_row).enumerate() {
+                    let y = tile_y * TILE_HEIGHT + row;
+                    let neighbor_row = |offset| match self.edge_mode.resolve(y, offset, self.height)
+                    {
+                        Some(y) => self.row(y),
+                        None => &zero_row,
+                    };
+                    let up = neighbor_row(-1);
+                    let mid = self.row(y);
+                    let down = neighbor_row(1);
+
+                    for (idx, out) in out.iter_mut().enumerate() {
+                        if !active[idx] {
+                            continue;
+                        }
+
+                        let (up_west, up_east) = self.shifted(up, idx);
+                        let (mid_west, mid_east) = self.shifted(mid, idx);
+                        let (down_west, down_east) = self.shifted(down, idx);
+
+                        let mut new = Self::next_word(
+                            [
+                                up_west, up[idx], up_east, mid_west, mid_east, down_west,
+                                down[idx], down_east,
+                            ],
+                            mid[idx],
+                        );
+                        if idx + 1 == self.words_per_row {
+                            new &= last_word_mask;
+                        }
+
+                        let old = mid[idx];
+                        *out = new;
+                        changed[idx] |= new != old;
+                        births += (new & !old).count_ones() as u64;
+                        deaths += (old & !new).count_ones() as u64;
+                    }
+                }
+                (births, deaths)
+            })
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+        self.births = births;
+        self.deaths = deaths;
+        self.changed = changed;
+
+        self.data_out = std::mem::replace(&mut self.data, data_out);
+    }
+
+    pub fn to_img(&self) -> DynamicImage {
+        let img =
+            ImageBuffer::<Luma<u8>, _>::from_fn(self.width as u32, self.height as u32, |x, y| {
+                Luma([if self.get(x as usize, y as usize) {
+                    255
+                } else {
+                    0
+                }])
+            });
+
+        DynamicImage::ImageLuma8(img)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_neighbors(board: &Board, x: usize, y: usize) -> usize {
+        let mut count = 0;
+        for y_off in -1..=1 {
+            for x_off in -1..=1 {
+                if (x_off, y_off) == (0, 0) {
+                    continue;
+                }
+                if let (Some(x), Some(y)) = (
+                    board.edge_mode.resolve(x, x_off, board.width),
+                    board.edge_mode.resolve(y, y_off, board.height),
+                ) {
+                    count += board.get(x, y) as usize;
+                }
+            }
+        }
+        count
+    }
+
+    fn naive_update(board: &Board) -> Board {
+        let mut next = board.clone();
+        for y in 0..board.height {
+            for x in 0..board.width {
+                let alive = matches!(
+                    (board.get(x, y), count_neighbors(board, x, y)),
+                    (true, 2) | (_, 3)
+                );
+                next.set(x, y, alive);
+            }
+        }
+        next
+    }
+
+    #[test]
+    fn test_update_matches_naive() {
+        for edge_mode in [EdgeMode::Dead, EdgeMode::Wrap, EdgeMode::Mirror] {
+            for (width, height) in [(1, 1), (3, 5), (63, 7), (64, 9), (65, 4), (130, 17)] {
+                let mut board = Board::new(width, height);
+                board.edge_mode = edge_mode;
+                board.rand(width as u64 * 31 + height as u64, 0.4);
+
+                for _ in 0..8 {
+                    let expected = naive_update(&board);
+                    board.update();
+                    assert_eq!(board.data, expected.data, "{edge_mode:?} {width}x{height}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiled_update_matches_naive() {
+        for edge_mode in [EdgeMode::Dead, EdgeMode::Wrap, EdgeMode::Mirror] {
+            let mut board = Board::new(200, 150);
+            board.edge_mode = edge_mode;
+            // a glider crossing tile borders, with a static block and a blinker
+            for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+                board.set(x + 58, y + 58, true);
+            }
+            for (x, y) in [(130, 100), (131, 100), (130, 101), (131, 101)] {
+                board.set(x, y, true);
+            }
+            for x in 197..200 {
+                board.set(x, 149, true);
+            }
+
+            for generation in 0..120 {
+                match generation {
+                    40 => board.set(10, 140, true),
+                    80 => board.edge_mode = edge_mode.next(),
+                    _ => {}
+                }
+
+                let expected = naive_update(&board);
+                board.update();
+                assert_eq!(board.data, expected.data, "{edge_mode:?} {generation}");
+            }
+        }
+    }
+}
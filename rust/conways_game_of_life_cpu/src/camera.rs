@@ -0,0 +1,73 @@
+/// Maps window pixels to board cells, with integer zoom and panning.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// Window pixels per cell
+    pub zoom: u32,
+    /// Board coordinate shown at the top left corner of the window
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Camera {
+    pub const MAX_ZOOM: u32 = 64;
+
+    /// Largest zoom which still fits the whole board, centered in the window.
+    pub fn fit(board: (usize, usize), window: (u32, u32)) -> Self {
+        let zoom = (window.0 as usize / board.0.max(1))
+            .min(window.1 as usize / board.1.max(1))
+            .clamp(1, Self::MAX_ZOOM as usize) as u32;
+
+        let mut this = Self {
+            zoom,
+            x: 0.0,
+            y: 0.0,
+        };
+        this.center_on((board.0 as f64 / 2.0, board.1 as f64 / 2.0), window);
+        this
+    }
+
+    pub fn center_on(&mut self, point: (f64, f64), window: (u32, u32)) {
+        let zoom = self.zoom as f64;
+        self.x = point.0 - window.0 as f64 / zoom / 2.0;
+        self.y = point.1 - window.1 as f64 / zoom / 2.0;
+    }
+
+    pub fn screen_to_board(&self, screen: (f64, f64)) -> (f64, f64) {
+        let zoom = self.zoom as f64;
+        (self.x + screen.0 / zoom, self.y + screen.1 / zoom)
+    }
+
+    /// Board cell under the window pixel, `None` if outside of `0..width`/`0..height`.
+    pub fn cell_at(
+        &self,
+        screen: (f64, f64),
+        width: usize,
+        height: usize,
+    ) -> Option<(usize, usize)> {
+        let (x, y) = self.screen_to_board(screen);
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        (x < width && y < height).then_some((x, y))
+    }
+
+    /// Change zoom while keeping the board point under `anchor` in place.
+    pub fn zoom_at(&mut self, anchor: (f64, f64), zoom: u32) {
+        let zoom = zoom.clamp(1, Self::MAX_ZOOM);
+        let (x, y) = self.screen_to_board(anchor);
+
+        self.zoom = zoom;
+        let zoom = zoom as f64;
+        self.x = x - anchor.0 / zoom;
+        self.y = y - anchor.1 / zoom;
+    }
+
+    /// Move by a distance in window pixels.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let zoom = self.zoom as f64;
+        self.x += dx / zoom;
+        self.y += dy / zoom;
+    }
+}
@@ -0,0 +1,134 @@
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+
+use crate::engine::Engine;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// Alive cells are white, dead cells are black
+    #[default]
+    Binary,
+    /// Alive cells are colored by how many generations they've been alive
+    Age,
+    /// Dead cells fade out over a few generations after dying
+    Heat,
+}
+
+impl ColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::Binary => ColorMode::Age,
+            ColorMode::Age => ColorMode::Heat,
+            ColorMode::Heat => ColorMode::Binary,
+        }
+    }
+}
+
+/// Heat lost per generation after a cell died
+const HEAT_DECAY: u8 = 12;
+
+/// Per cell age and recent death tracking, used for coloring.
+#[derive(Debug, Clone)]
+pub struct CellHistory {
+    width: usize,
+    /// Generations alive, 0 for dead cells
+    age: Vec<u16>,
+    /// 255 for alive cells, decays after death
+    heat: Vec<u8>,
+}
+
+impl CellHistory {
+    pub fn new(board: &dyn Engine) -> Self {
+        let mut this = Self {
+            width: board.width(),
+            age: vec![0; board.width() * board.height()],
+            heat: vec![0; board.width() * board.height()],
+        };
+        this.update(board);
+        this
+    }
+
+    /// Record the current generation of `board`, call once per update.
+    pub fn update(&mut self, board: &dyn Engine) {
+        if self.width != board.width() || self.age.len() != board.width() * board.height() {
+            *self = Self::new(board);
+            return;
+        }
+
+        let width = self.width.max(1);
+        self.age
+            .par_chunks_mut(width)
+            .zip(self.heat.par_chunks_mut(width))
+            .enumerate()
+            .for_each(|(y, (age, heat))| {
+                for x in 0..age.len() {
+                    if board.get(x, y) {
+                        age[x] = age[x].saturating_add(1);
+                        heat[x] = u8::MAX;
+                    } else {
+                        age[x] = 0;
+                        heat[x] = heat[x].saturating_sub(HEAT_DECAY);
+                    }
+                }
+            });
+    }
+
+    pub fn color(&self, mode: ColorMode, board: &dyn Engine, x: usize, y: usize) -> [u8; 3] {
+        let idx = y * self.width + x;
+        match mode {
+            ColorMode::Binary => {
+                if board.get(x, y) {
+                    [255, 255, 255]
+                } else {
+                    [0, 0, 0]
+                }
+            }
+            ColorMode::Age => match self.age.get(idx) {
+                Some(&age) if age > 0 => {
+                    // log scale, reaches the end of the gradient after ~1000 generations
+                    let t = (age as f32).log2() / 10.0;
+                    gradient(&AGE_GRADIENT, t)
+                }
+                _ => [0, 0, 0],
+            },
+            ColorMode::Heat => {
+                let heat = self.heat.get(idx).copied().unwrap_or(0);
+                gradient(&HEAT_GRADIENT, heat as f32 / u8::MAX as f32)
+            }
+        }
+    }
+
+    pub fn to_img(&self, mode: ColorMode, board: &dyn Engine) -> RgbImage {
+        RgbImage::from_fn(board.width() as u32, board.height() as u32, |x, y| {
+            Rgb(self.color(mode, board, x as usize, y as usize))
+        })
+    }
+}
+
+const AGE_GRADIENT: [[u8; 3]; 5] = [
+    [255, 255, 255],
+    [255, 230, 80],
+    [255, 100, 30],
+    [180, 30, 120],
+    [40, 40, 200],
+];
+
+const HEAT_GRADIENT: [[u8; 3]; 4] = [[0, 0, 0], [90, 0, 20], [230, 80, 0], [255, 255, 200]];
+
+/// Linear interpolation between evenly spaced color stops, `t` in `0..=1`.
+fn gradient(stops: &[[u8; 3]], t: f32) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let idx = (t as usize).min(stops.len() - 2);
+    let t = t - idx as f32;
+
+    let (a, b) = (stops[idx], stops[idx + 1]);
+    [0, 1, 2].map(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * t).round() as u8)
+}
+
+pub fn to_argb(rgb: [u8; 3]) -> u32 {
+    u32::from_be_bytes([0, rgb[0], rgb[1], rgb[2]])
+}
@@ -0,0 +1,128 @@
+use std::fmt::Debug;
+
+use clap::ValueEnum;
+
+use crate::{
+    board::{Board, EdgeMode, Soup},
+    gpu::GpuBoard,
+    hashlife::HashLife,
+    pattern::Pattern,
+};
+
+/// A Game of Life simulation, viewed through a `width` x `height` window
+/// with `(0, 0)` at the top left.
+pub trait Engine: Debug + Send + Sync {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+
+    /// Number of generations simulated since the engine was created
+    fn generation(&self) -> u64;
+    /// Number of alive cells
+    fn population(&self) -> u64;
+    /// Cells `(born, died)` in the last update, `None` if the engine doesn't track them
+    fn activity(&self) -> Option<(u64, u64)>;
+    /// Hash of the cell state, equal states hash equal regardless of history
+    fn state_hash(&self) -> u64;
+
+    /// `None` if the universe is unbounded and there is no edge
+    fn edge_mode(&self) -> Option<EdgeMode>;
+    /// Ignored by unbounded engines
+    fn set_edge_mode(&mut self, edge_mode: EdgeMode);
+
+    fn get(&self, x: usize, y: usize) -> bool;
+    fn set(&mut self, x: usize, y: usize, value: bool);
+
+    /// Advance a single generation
+    fn update(&mut self);
+
+    /// Advance `generations` generations at once,
+    /// engines which can skip ahead faster than one update at a time override this.
+    fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.update();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EngineKind {
+    /// Bit-packed array, fast for dense boards of moderate size
+    #[default]
+    Dense,
+    /// Gosper's HashLife on a quadtree, for huge sparse and repetitive patterns.
+    /// The universe is unbounded, the board size only sets the initial view
+    #[value(name = "hashlife")]
+    HashLife,
+    /// Compute shader on the GPU, for large dense boards.
+    /// Fails to start without a GPU adapter
+    Gpu,
+}
+
+impl EngineKind {
+    /// Engine with `pattern` centered on the board, or random `soup` if there's none.
+    /// Only [`EngineKind::Gpu`] can fail.
+    pub fn build(
+        self,
+        width: usize,
+        height: usize,
+        pattern: Option<&Pattern>,
+        soup: Soup,
+        edge_mode: EdgeMode,
+    ) -> anyhow::Result<Box<dyn Engine>> {
+        let mut engine: Box<dyn Engine> = match self {
+            EngineKind::Dense => Box::new(Board::from_pattern(width, height, pattern, soup)),
+            EngineKind::HashLife => Box::new(HashLife::from_pattern(width, height, pattern, soup)),
+            EngineKind::Gpu => Box::new(GpuBoard::from_board(&Board::from_pattern(
+                width, height, pattern, soup,
+            ))?),
+        };
+        engine.set_edge_mode(edge_mode);
+        Ok(engine)
+    }
+}
+
+impl Engine for Board {
+    fn width(&self) -> usize {
+        Board::width(self)
+    }
+
+    fn height(&self) -> usize {
+        Board::height(self)
+    }
+
+    fn generation(&self) -> u64 {
+        Board::generation(self)
+    }
+
+    fn population(&self) -> u64 {
+        Board::population(self)
+    }
+
+    fn activity(&self) -> Option<(u64, u64)> {
+        Some((self.births(), self.deaths()))
+    }
+
+    fn state_hash(&self) -> u64 {
+        Board::state_hash(self)
+    }
+
+    fn edge_mode(&self) -> Option<EdgeMode> {
+        Some(self.edge_mode)
+    }
+
+    fn set_edge_mode(&mut self, edge_mode: EdgeMode) {
+        self.edge_mode = edge_mode;
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        Board::get(self, x, y)
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: bool) {
+        Board::set(self, x, y, value)
+    }
+
+    fn update(&mut self) {
+        Board::update(self)
+    }
+}
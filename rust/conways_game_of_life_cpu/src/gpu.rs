@@ -0,0 +1,342 @@
+use std::{
+    future::Future,
+    hash::{DefaultHasher, Hash, Hasher},
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use anyhow::{anyhow, Context as _};
+use bytemuck::{cast_slice, Pod, Zeroable};
+use wgpu::{
+    util::{BufferInitDescriptor, DeviceExt},
+    BindGroup, BindGroupDescriptor, BindGroupEntry, Buffer, BufferAddress, BufferDescriptor,
+    BufferUsages, CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline,
+    ComputePipelineDescriptor, Device, DeviceDescriptor, Maintain, MapMode, Queue,
+    RequestAdapterOptions, ShaderModuleDescriptor, ShaderSource,
+};
+
+use crate::{
+    board::{Board, EdgeMode},
+    engine::Engine,
+};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Uniforms of `gpu.wgsl`
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+struct Param {
+    width: u32,
+    height: u32,
+    edge_mode: u32,
+    _padding: u32,
+}
+
+/// Board updated by a compute shader, one `u32` per cell in two buffers
+/// swapping roles every generation.
+/// The cells are read back after every [`Engine::step`], so drawing and
+/// stats work like with the CPU engines, a multi generation step only
+/// reads back once.
+#[derive(Debug)]
+pub struct GpuBoard {
+    device: Device,
+    queue: Queue,
+    pipeline: ComputePipeline,
+    param_buffer: Buffer,
+    /// Reading from the buffer of the same index
+    bind_groups: [BindGroup; 2],
+    cell_buffers: [Buffer; 2],
+    activity_buffer: Buffer,
+    /// Cells followed by the activity
+    readback_buffer: Buffer,
+    /// Index of the buffer with the latest cells
+    parity: usize,
+
+    /// Copy of the latest cells
+    cells: Vec<u32>,
+    /// Cells were set on the host since the last upload
+    dirty: bool,
+    width: usize,
+    height: usize,
+    generation: u64,
+    births: u64,
+    deaths: u64,
+
+    pub edge_mode: EdgeMode,
+}
+
+impl GpuBoard {
+    /// Copy of `board` on the default adapter, fails without a GPU
+    pub fn from_board(board: &Board) -> anyhow::Result<Self> {
+        let (width, height) = (board.width(), board.height());
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| board.get(x, y) as u32))
+            .collect::<Vec<_>>();
+
+        let instance = wgpu::Instance::default();
+        let adapter = block_on(instance.request_adapter(&RequestAdapterOptions::default()))
+            .ok_or(anyhow!("no GPU adapter available"))?;
+        let (device, queue) = block_on(adapter.request_device(
+            &DeviceDescriptor {
+                label: Some("game of life device"),
+                required_limits: adapter.limits(),
+                ..Default::default()
+            },
+            None,
+        ))
+        .context("failed to request device")?;
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("gpu.wgsl"),
+            source: ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("update pipeline"),
+            layout: None,
+            module: &shader,
+            entry_point: "update",
+            compilation_options: Default::default(),
+        });
+
+        let param_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("param buffer"),
+            size: size_of::<Param>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // storage bindings can't be empty
+        let contents = if cells.is_empty() { &[0][..] } else { &cells };
+        let cell_buffers = ["cell buffer a", "cell buffer b"].map(|label| {
+            device.create_buffer_init(&BufferInitDescriptor {
+                label: Some(label),
+                contents: cast_slice(contents),
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            })
+        });
+        let activity_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("activity buffer"),
+            size: 2 * 4,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("readback buffer"),
+            size: (cells.len() as BufferAddress + 2) * 4,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = pipeline.get_bind_group_layout(0);
+        let [a, b] = &cell_buffers;
+        let bind_groups = [[a, b], [b, a]].map(|[input, output]| {
+            let buffers = [&param_buffer, input, output, &activity_buffer];
+            let entries = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>();
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("update bind group"),
+                layout: &layout,
+                entries: &entries,
+            })
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            param_buffer,
+            bind_groups,
+            cell_buffers,
+            activity_buffer,
+            readback_buffer,
+            parity: 0,
+
+            cells,
+            dirty: false,
+            width,
+            height,
+            generation: board.generation(),
+            births: board.births(),
+            deaths: board.deaths(),
+
+            edge_mode: board.edge_mode,
+        })
+    }
+
+    /// Run `generations` updates in one submission, then read the cells back
+    fn run(&mut self, generations: u64) {
+        self.generation += generations;
+        if generations == 0 || self.cells.is_empty() {
+            return;
+        }
+
+        if std::mem::take(&mut self.dirty) {
+            self.queue
+                .write_buffer(&self.cell_buffers[self.parity], 0, cast_slice(&self.cells));
+        }
+        let param = Param {
+            width: self.width as u32,
+            height: self.height as u32,
+            edge_mode: self.edge_mode as u32,
+            _padding: 0,
+        };
+        self.queue
+            .write_buffer(&self.param_buffer, 0, cast_slice(&[param]));
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("update command encoder"),
+            });
+        let workgroups = [self.width, self.height].map(|len| (len as u32).div_ceil(WORKGROUP_SIZE));
+        for generation in 0..generations {
+            // only the last generation's activity is kept
+            if generation + 1 == generations {
+                encoder.clear_buffer(&self.activity_buffer, 0, None);
+            }
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("update pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.parity], &[]);
+            pass.dispatch_workgroups(workgroups[0], workgroups[1], 1);
+            drop(pass);
+            self.parity ^= 1;
+        }
+
+        let cells_size = (self.cells.len() * 4) as BufferAddress;
+        encoder.copy_buffer_to_buffer(
+            &self.cell_buffers[self.parity],
+            0,
+            &self.readback_buffer,
+            0,
+            cells_size,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.activity_buffer,
+            0,
+            &self.readback_buffer,
+            cells_size,
+            2 * 4,
+        );
+        self.queue.submit([encoder.finish()]);
+
+        let slice = self.readback_buffer.slice(..);
+        slice.map_async(MapMode::Read, |result| {
+            result.expect("failed to map readback buffer")
+        });
+        self.device.poll(Maintain::Wait);
+        {
+            let view = slice.get_mapped_range();
+            let (cells, activity) = cast_slice::<_, u32>(&view).split_at(self.cells.len());
+            self.cells.copy_from_slice(cells);
+            self.births = activity[0] as u64;
+            self.deaths = activity[1] as u64;
+        }
+        self.readback_buffer.unmap();
+    }
+}
+
+/// wgpu's futures are ready right away on native, no executor needed
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::yield_now();
+    }
+}
+
+impl Engine for GpuBoard {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn population(&self) -> u64 {
+        self.cells.iter().map(|&cell| cell as u64).sum()
+    }
+
+    fn activity(&self) -> Option<(u64, u64)> {
+        Some((self.births, self.deaths))
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.width.hash(&mut hasher);
+        self.cells.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn edge_mode(&self) -> Option<EdgeMode> {
+        Some(self.edge_mode)
+    }
+
+    fn set_edge_mode(&mut self, edge_mode: EdgeMode) {
+        self.edge_mode = edge_mode;
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[y * self.width + x] == 1
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: bool) {
+        self.cells[y * self.width + x] = value as u32;
+        self.dirty = true;
+    }
+
+    fn update(&mut self) {
+        self.run(1);
+    }
+
+    fn step(&mut self, generations: u64) {
+        self.run(generations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_board() {
+        for edge_mode in [EdgeMode::Dead, EdgeMode::Wrap, EdgeMode::Mirror] {
+            let mut board = Board::new(67, 45);
+            board.edge_mode = edge_mode;
+            board.rand(7, 0.4);
+
+            let Ok(mut gpu) = GpuBoard::from_board(&board) else {
+                eprintln!("no GPU adapter, skipped");
+                return;
+            };
+            for (i, generations) in [1, 1, 5, 20].into_iter().enumerate() {
+                // edits are uploaded before the next update
+                board.set(i, 0, true);
+                gpu.set(i, 0, true);
+                board.step(generations);
+                gpu.step(generations);
+                for y in 0..board.height() {
+                    for x in 0..board.width() {
+                        assert_eq!(gpu.get(x, y), board.get(x, y), "{edge_mode:?} {x},{y}");
+                    }
+                }
+                assert_eq!(gpu.activity(), Engine::activity(&board));
+            }
+        }
+    }
+}
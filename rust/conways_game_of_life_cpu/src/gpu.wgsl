@@ -0,0 +1,79 @@
+// one u32 per cell, 0 dead and 1 alive
+
+struct Param {
+    width: u32,
+    height: u32,
+    // same as `EdgeMode`, 0 dead, 1 wrap, 2 mirror
+    edge_mode: u32,
+}
+
+@group(0) @binding(0) var<uniform> param: Param;
+@group(0) @binding(1) var<storage, read> cells_in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> cells_out: array<u32>;
+// births and deaths of the last update
+@group(0) @binding(3) var<storage, read_write> activity: array<atomic<u32>, 2>;
+
+var<workgroup> births: atomic<u32>;
+var<workgroup> deaths: atomic<u32>;
+
+// `pos` moved into `0..len`, -1 if the neighbor is dead
+fn resolve(pos: i32, len: i32) -> i32 {
+    if pos >= 0 && pos < len {
+        return pos;
+    }
+    switch param.edge_mode {
+        case 0u: {
+            return -1;
+        }
+        case 1u: {
+            // neighbors are at most one cell outside
+            return select(pos - len, pos + len, pos < 0);
+        }
+        default: {
+            return clamp(pos, 0, len - 1);
+        }
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn update(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_index) local_idx: u32,
+) {
+    let width = i32(param.width);
+    let height = i32(param.height);
+    let x = i32(global_id.x);
+    let y = i32(global_id.y);
+
+    // no early return, every invocation has to reach the barrier
+    if x < width && y < height {
+        var neighbors = 0u;
+        for (var dy = -1; dy <= 1; dy++) {
+            for (var dx = -1; dx <= 1; dx++) {
+                let nx = resolve(x + dx, width);
+                let ny = resolve(y + dy, height);
+                if (dx != 0 || dy != 0) && nx >= 0 && ny >= 0 {
+                    neighbors += cells_in[ny * width + nx];
+                }
+            }
+        }
+
+        let idx = y * width + x;
+        let alive = cells_in[idx];
+        let next = select(0u, 1u, neighbors == 3u || (alive == 1u && neighbors == 2u));
+        cells_out[idx] = next;
+
+        if next > alive {
+            atomicAdd(&births, 1u);
+        } else if next < alive {
+            atomicAdd(&deaths, 1u);
+        }
+    }
+
+    workgroupBarrier();
+    if local_idx == 0u {
+        atomicAdd(&activity[0], atomicLoad(&births));
+        atomicAdd(&activity[1], atomicLoad(&deaths));
+    }
+}
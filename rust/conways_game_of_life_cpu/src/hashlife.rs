@@ -0,0 +1,537 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{
+    board::{Board, EdgeMode, Soup},
+    engine::Engine,
+    pattern::Pattern,
+};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Smallest root level, keeps the padding logic away from the leaves
+const MIN_ROOT_LEVEL: u8 = 3;
+/// Compact the node arena once it grows past this many nodes
+const GC_THRESHOLD: usize = 1 << 22;
+
+/// Quadtree node, a square of `2^level` cells.
+/// Level 0 nodes are the two leaves [`DEAD`] and [`ALIVE`].
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// `[nw, ne, sw, se]`
+    children: [NodeId; 4],
+    level: u8,
+    population: u64,
+    /// Structural hash, equal trees hash equal even across garbage collections
+    hash: u64,
+}
+
+/// Gosper's HashLife, every distinct subtree is stored once and the
+/// future of each one is memoized, so repetitive patterns can be advanced
+/// by huge power of two steps in roughly constant time.
+///
+/// The universe is unbounded, the root is centered on `(0, 0)` and grows
+/// as needed, `width`/`height` only describe the region shown as the board.
+#[derive(Debug)]
+pub struct HashLife {
+    nodes: Vec<Node>,
+    interned: HashMap<[NodeId; 4], NodeId>,
+    /// `(node, log2 of generations)` -> center of the node after that many generations
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// Empty node of every level
+    empty: Vec<NodeId>,
+    root: NodeId,
+
+    width: usize,
+    height: usize,
+    generation: u64,
+}
+
+impl HashLife {
+    pub fn new(width: usize, height: usize) -> Self {
+        let leaf = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+            hash: population,
+        };
+
+        let mut this = Self {
+            nodes: vec![leaf(0), leaf(1)],
+            interned: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+
+            width,
+            height,
+            generation: 0,
+        };
+        this.root = this.empty(MIN_ROOT_LEVEL);
+        this
+    }
+
+    /// Universe with `pattern` centered on the board, or random `soup` if there's none.
+    pub fn from_pattern(
+        width: usize,
+        height: usize,
+        pattern: Option<&Pattern>,
+        soup: Soup,
+    ) -> Self {
+        match pattern {
+            Some(pattern) => {
+                let mut this = Self::new(width, height);
+                let x = width.saturating_sub(pattern.width) / 2;
+                let y = height.saturating_sub(pattern.height) / 2;
+                for pattern_y in 0..pattern.height {
+                    for pattern_x in 0..pattern.width {
+                        if pattern.get(pattern_x, pattern_y) {
+                            this.set_cell((x + pattern_x) as i64, (y + pattern_y) as i64, true);
+                        }
+                    }
+                }
+                this
+            }
+            None => Self::from_board(&Board::from_pattern(width, height, None, soup)),
+        }
+    }
+
+    pub fn from_board(board: &Board) -> Self {
+        let mut this = Self::new(board.width(), board.height());
+
+        let size = board.width().max(board.height()) as i64;
+        while this.half() < size {
+            this.expand();
+        }
+
+        let level = this.level(this.root);
+        let half = this.half();
+        this.root = this.build(board, level, -half, -half);
+        this
+    }
+
+    /// Build the node covering `2^level` cells from `(x, y)` out of a dense board
+    fn build(&mut self, board: &Board, level: u8, x: i64, y: i64) -> NodeId {
+        let size = 1_i64 << level;
+        let (width, height) = (board.width() as i64, board.height() as i64);
+        if x + size <= 0 || y + size <= 0 || x >= width || y >= height {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if board.get(x as usize, y as usize) {
+                ALIVE
+            } else {
+                DEAD
+            };
+        }
+
+        let half = size / 2;
+        let children = [(0, 0), (half, 0), (0, half), (half, half)]
+            .map(|(dx, dy)| self.build(board, level - 1, x + dx, y + dy));
+        self.join(children)
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    fn level(&self, id: NodeId) -> u8 {
+        self.node(id).level
+    }
+
+    fn population_of(&self, id: NodeId) -> u64 {
+        self.node(id).population
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.node(id).children
+    }
+
+    /// Half of the root size, the root covers `-half..half` on both axes
+    fn half(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn node_hash(level: u8, children: [u64; 4]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (level, children).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The canonical node with these children
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.interned.get(&children) {
+            return id;
+        }
+
+        let level = self.level(children[0]) + 1;
+        let node = Node {
+            children,
+            level,
+            population: children.iter().map(|&it| self.population_of(it)).sum(),
+            hash: Self::node_hash(level, children.map(|it| self.node(it).hash)),
+        };
+
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.interned.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let last = *self.empty.last().unwrap();
+            let next = self.join([last; 4]);
+            self.empty.push(next);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Double the root size, keeping the content centered
+    fn expand(&mut self) {
+        let [nw, ne, sw, se] = self.children(self.root);
+        let e = self.empty(self.level(self.root) - 1);
+
+        let children = [
+            self.join([e, e, e, nw]),
+            self.join([e, e, ne, e]),
+            self.join([e, sw, e, e]),
+            self.join([se, e, e, e]),
+        ];
+        self.root = self.join(children);
+    }
+
+    /// 4x4 grid of the grandchildren of a node, indexed `[y][x]`
+    fn grandchildren(&self, id: NodeId) -> [[NodeId; 4]; 4] {
+        let [nw, ne, sw, se] = self.children(id).map(|it| self.children(it));
+        [
+            [nw[0], nw[1], ne[0], ne[1]],
+            [nw[2], nw[3], ne[2], ne[3]],
+            [sw[0], sw[1], se[0], se[1]],
+            [sw[2], sw[3], se[2], se[3]],
+        ]
+    }
+
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let grid = self.grandchildren(id);
+        self.join([grid[1][1], grid[1][2], grid[2][1], grid[2][2]])
+    }
+
+    /// Whether the content of the root lies in its central 1/4 x 1/4,
+    /// so it can't reach past the center half within `root size / 8` generations
+    fn is_padded(&self) -> bool {
+        let [nw, ne, sw, se] = self.children(self.root).map(|it| self.children(it));
+        let inner = [
+            self.children(nw[3])[3],
+            self.children(ne[2])[2],
+            self.children(sw[1])[1],
+            self.children(se[0])[0],
+        ]
+        .iter()
+        .map(|&it| self.population_of(it))
+        .sum::<u64>();
+        inner == self.population_of(self.root)
+    }
+
+    /// Next generation of the center 2x2 of a 4x4 node
+    fn base_case(&mut self, id: NodeId) -> NodeId {
+        let grid = self.grandchildren(id).map(|row| row.map(|it| it == ALIVE));
+        let next = |x: usize, y: usize| {
+            let count = grid[y - 1..=y + 1]
+                .iter()
+                .flat_map(|row| &row[x - 1..=x + 1])
+                .filter(|&&alive| alive)
+                .count()
+                - grid[y][x] as usize;
+            if matches!((grid[y][x], count), (true, 2) | (_, 3)) {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+
+        let children = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(children)
+    }
+
+    /// Center of the node advanced by `2^step_log2` generations,
+    /// `step_log2` must be at most `level - 2`.
+    fn successor(&mut self, id: NodeId, step_log2: u8) -> NodeId {
+        let level = self.level(id);
+        debug_assert!(level >= 2 && step_log2 <= level - 2);
+
+        if self.population_of(id) == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, step_log2)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.base_case(id)
+        } else {
+            let grid = self.grandchildren(id);
+            let full_speed = step_log2 == level - 2;
+
+            // nine overlapping sub nodes, advanced by half of the step at full speed
+            let mut nine = [[DEAD; 3]; 3];
+            for y in 0..3 {
+                for x in 0..3 {
+                    let sub = self.join([
+                        grid[y][x],
+                        grid[y][x + 1],
+                        grid[y + 1][x],
+                        grid[y + 1][x + 1],
+                    ]);
+                    nine[y][x] = if full_speed {
+                        self.successor(sub, level - 3)
+                    } else {
+                        self.centre(sub)
+                    };
+                }
+            }
+
+            let second_step = if full_speed { level - 3 } else { step_log2 };
+            let mut children = [DEAD; 4];
+            for (idx, (x, y)) in [(0, 0), (1, 0), (0, 1), (1, 1)].into_iter().enumerate() {
+                let sub = self.join([
+                    nine[y][x],
+                    nine[y][x + 1],
+                    nine[y + 1][x],
+                    nine[y + 1][x + 1],
+                ]);
+                children[idx] = self.successor(sub, second_step);
+            }
+            self.join(children)
+        };
+
+        self.results.insert((id, step_log2), result);
+        result
+    }
+
+    /// Advance the whole universe by `2^step_log2` generations
+    fn advance_pow2(&mut self, step_log2: u8) {
+        while self.level(self.root) < step_log2 + 3 || !self.is_padded() {
+            self.expand();
+        }
+        self.root = self.successor(self.root, step_log2);
+        self.generation += 1 << step_log2;
+
+        if self.nodes.len() > GC_THRESHOLD {
+            self.collect_garbage();
+        }
+    }
+
+    /// Rebuild the arena with only the nodes reachable from the root
+    fn collect_garbage(&mut self) {
+        let mut new = Self::new(self.width, self.height);
+        let mut mapping = HashMap::new();
+        new.root = new.copy_from(self, self.root, &mut mapping);
+        new.generation = self.generation;
+        *self = new;
+    }
+
+    fn copy_from(
+        &mut self,
+        other: &HashLife,
+        id: NodeId,
+        mapping: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&new) = mapping.get(&id) {
+            return new;
+        }
+
+        let children = other
+            .children(id)
+            .map(|child| self.copy_from(other, child, mapping));
+        let new = self.join(children);
+        mapping.insert(id, new);
+        new
+    }
+
+    /// Cell in universe coordinates
+    pub fn get_cell(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        if x < -half || y < -half || x >= half || y >= half {
+            return false;
+        }
+
+        let (mut x, mut y) = ((x + half) as u64, (y + half) as u64);
+        let mut node = self.root;
+        let mut level = self.level(node);
+        while level > 0 {
+            if self.population_of(node) == 0 {
+                return false;
+            }
+            level -= 1;
+            let half = 1 << level;
+            let idx = (y >= half) as usize * 2 + (x >= half) as usize;
+            (x, y) = (x & (half - 1), y & (half - 1));
+            node = self.children(node)[idx];
+        }
+        node == ALIVE
+    }
+
+    /// Set a cell in universe coordinates, growing the universe if needed
+    pub fn set_cell(&mut self, x: i64, y: i64, value: bool) {
+        while x < -self.half() || y < -self.half() || x >= self.half() || y >= self.half() {
+            self.expand();
+        }
+
+        let half = self.half();
+        let level = self.level(self.root);
+        self.root = self.set_rec(
+            self.root,
+            level,
+            (x + half) as u64,
+            (y + half) as u64,
+            value,
+        );
+    }
+
+    fn set_rec(&mut self, node: NodeId, level: u8, x: u64, y: u64, value: bool) -> NodeId {
+        if level == 0 {
+            return if value { ALIVE } else { DEAD };
+        }
+
+        let half = 1 << (level - 1);
+        let idx = (y >= half) as usize * 2 + (x >= half) as usize;
+        let mut children = self.children(node);
+        children[idx] = self.set_rec(
+            children[idx],
+            level - 1,
+            x & (half - 1),
+            y & (half - 1),
+            value,
+        );
+        self.join(children)
+    }
+}
+
+impl Engine for HashLife {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn population(&self) -> u64 {
+        self.population_of(self.root)
+    }
+
+    fn activity(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    fn state_hash(&self) -> u64 {
+        // shrink to the smallest centered square holding every cell,
+        // so roots padded to different sizes hash the same
+        let population = self.population_of(self.root);
+        let mut level = self.level(self.root);
+        let mut children = self.children(self.root);
+        while level > MIN_ROOT_LEVEL {
+            let centre = [
+                self.children(children[0])[3],
+                self.children(children[1])[2],
+                self.children(children[2])[1],
+                self.children(children[3])[0],
+            ];
+            if centre.iter().map(|&it| self.population_of(it)).sum::<u64>() != population {
+                break;
+            }
+            children = centre;
+            level -= 1;
+        }
+
+        Self::node_hash(level, children.map(|it| self.node(it).hash))
+    }
+
+    fn edge_mode(&self) -> Option<EdgeMode> {
+        None
+    }
+
+    fn set_edge_mode(&mut self, _edge_mode: EdgeMode) {}
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.get_cell(x as i64, y as i64)
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: bool) {
+        self.set_cell(x as i64, y as i64, value)
+    }
+
+    fn update(&mut self) {
+        self.advance_pow2(0);
+    }
+
+    fn step(&mut self, generations: u64) {
+        for step_log2 in 0..u64::BITS as u8 {
+            if generations >> step_log2 & 1 == 1 {
+                self.advance_pow2(step_log2);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_dense() {
+        // soup in the middle, far enough from the dead edges of the dense board
+        let mut soup = Board::new(20, 20);
+        soup.rand(7, 0.4);
+        let mut board = Board::new(100, 100);
+        for y in 0..20 {
+            for x in 0..20 {
+                board.set(x + 40, y + 40, soup.get(x, y));
+            }
+        }
+        let mut life = HashLife::from_board(&board);
+
+        for generations in [1, 1, 2, 3, 8, 21] {
+            for _ in 0..generations {
+                board.update();
+            }
+            life.step(generations);
+            assert_eq!(life.generation(), board.generation());
+
+            assert_eq!(life.population(), board.population());
+            for y in 0..board.height() {
+                for x in 0..board.width() {
+                    assert_eq!(Engine::get(&life, x, y), board.get(x, y), "{x},{y}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_glider_moves() {
+        let glider = Pattern::parse_cells(".O\n..O\nOOO").unwrap();
+        let mut life = HashLife::from_pattern(3, 3, Some(&glider), Soup::default());
+        let hash = life.state_hash();
+
+        life.step(1 << 20);
+        assert_eq!(life.population(), 5);
+        // a glider travels one cell diagonally every 4 generations
+        let offset = (1 << 18) as i64;
+        assert!(life.get_cell(offset + 1, offset + 2));
+        assert!(life.get_cell(offset + 2, offset + 2));
+        assert_ne!(life.state_hash(), hash);
+    }
+}
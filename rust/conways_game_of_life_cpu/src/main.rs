@@ -1,155 +1,207 @@
 #![warn(missing_debug_implementations)]
 
-use std::{io::Cursor, num::NonZeroU32, path::Path, sync::Arc, time::Instant};
+use std::{
+    num::NonZeroU32,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use image::{DynamicImage, ImageBuffer, ImageFormat, Luma};
-use rand::{rngs::SmallRng, Rng, SeedableRng};
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use anyhow::Context as _;
+use clap::{Args, Parser, Subcommand};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
 use softbuffer::{Context, Surface};
-use tokio::task::JoinSet;
 use winit::{
     application::ApplicationHandler,
     dpi::{LogicalSize, PhysicalSize},
-    event::WindowEvent,
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
     event_loop::{ActiveEventLoop, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
     window::{Window, WindowId},
 };
 
-#[derive(Debug, Clone)]
-struct Board {
-    pub data: Vec<bool>,
-    data_out: Vec<bool>,
+use crate::{
+    bench::BenchArgs,
+    board::{Board, EdgeMode, Soup},
+    camera::Camera,
+    color::{CellHistory, ColorMode},
+    engine::{Engine, EngineKind},
+    pattern::Pattern,
+    record::RecordArgs,
+    search::SearchArgs,
+    stats::{StatsArgs, StatsTracker},
+};
 
-    width: usize,
-    height: usize,
-}
+pub mod bench;
+pub mod board;
+pub mod camera;
+pub mod color;
+pub mod engine;
+pub mod gpu;
+pub mod hashlife;
+pub mod pattern;
+pub mod record;
+pub mod search;
+pub mod stats;
 
-impl Board {
-    pub fn new(width: usize, height: usize) -> Self {
-        Self {
-            data: vec![false; width * height],
-            data_out: vec![false; width * height],
+struct AppState {
+    pub window: Arc<Window>,
+    pub surface: Surface<Arc<Window>, Arc<Window>>,
 
-            width,
-            height,
-        }
-    }
+    pub board: Box<dyn Engine>,
+    pub engine: EngineKind,
+    pub board_size: (usize, usize),
+    pub pattern: Option<Pattern>,
+    /// Random fill used without a pattern
+    pub soup: Soup,
+    pub camera: Camera,
+    pub color_mode: ColorMode,
+    /// Only tracked while a color mode other than [`ColorMode::Binary`] is active
+    pub history: Option<CellHistory>,
+    pub stats: StatsTracker,
+    /// Pause once the board becomes static or oscillates
+    pub stop_on_stable: bool,
+
+    pub paused: bool,
+    pub step_requested: bool,
+    /// Target generations per second, `None` means one generation per redraw
+    pub tps: Option<f64>,
+    /// Generations owed to the target rate, carried across frames
+    step_budget: f64,
+    last_step: Instant,
+    /// Last cursor position in window pixels
+    pub cursor: Option<(f64, f64)>,
+    /// Value being painted while a mouse button is held
+    pub painting: Option<bool>,
+    pub panning: bool,
 
-    pub fn rand(&mut self, seed: u64, probability: f64) {
-        let mut rng = SmallRng::seed_from_u64(seed);
-        for ele in self.data.iter_mut() {
-            *ele = rng.gen_bool(probability);
-        }
-    }
+    print_interval_secs: f64,
+    last_print: Instant,
+    last_count: u64,
+}
 
-    pub fn get(&self, x: usize, y: usize) -> bool {
-        self.data[self.coord_to_idx(x, y)]
+impl AppState {
+    pub fn reset_board(&mut self) {
+        let (width, height) = self.board_size;
+        let edge_mode = self.board.edge_mode().unwrap_or_default();
+        let build = |engine: EngineKind| {
+            engine.build(width, height, self.pattern.as_ref(), self.soup, edge_mode)
+        };
+        self.board = build(self.engine).unwrap_or_else(|err| {
+            println!(
+                "failed to build {:?} engine, using dense: {err:?}",
+                self.engine
+            );
+            self.engine = EngineKind::Dense;
+            build(EngineKind::Dense).expect("dense engine can't fail")
+        });
+        self.reset_history();
+        self.stats.reset();
+        self.stats.observe(self.board.as_ref());
     }
 
-    #[allow(unused)]
-    pub fn set(&mut self, x: usize, y: usize, value: bool) {
-        let idx = self.coord_to_idx(x, y);
-        self.data_out[idx] = value;
+    fn reset_history(&mut self) {
+        let (width, height) = self.board_size;
+        self.history = match self.color_mode {
+            ColorMode::Binary => None,
+            _ if width.saturating_mul(height) > MAX_HISTORY_CELLS => {
+                println!("board too large for {:?} colors", self.color_mode);
+                None
+            }
+            _ => Some(CellHistory::new(self.board.as_ref())),
+        };
     }
 
-    fn coord_to_idx(&self, x: usize, y: usize) -> usize {
-        debug_assert_eq!(self.data.len(), self.width * self.height);
-        let idx = self.width * y + x;
-        debug_assert!(idx < self.data.len());
-        idx
+    pub fn cycle_color_mode(&mut self) {
+        self.color_mode = self.color_mode.next();
+        self.reset_history();
+        println!("color mode: {:?}", self.color_mode);
     }
 
-    fn idx_to_coord(&self, idx: usize) -> (usize, usize) {
-        debug_assert!(idx < self.data.len());
-        (idx % self.width, idx / self.width)
-    }
+    /// Advance a single generation
+    fn advance(&mut self) {
+        self.board.update();
+        if let Some(history) = self.history.as_mut() {
+            history.update(self.board.as_ref());
+        }
 
-    pub fn count_neighbors(&self, x: usize, y: usize) -> usize {
-        const OFFSETS: [(isize, isize); 8] = [
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            (-1, 0),
-            (1, 0),
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-        ];
-
-        OFFSETS
-            .into_iter()
-            .filter(|&(x_off, y_off)| {
-                match (x.checked_add_signed(x_off), y.checked_add_signed(y_off)) {
-                    (Some(x), Some(y)) if x < self.width && y < self.height => self.get(x, y),
-                    _ => false,
-                }
-            })
-            .count()
+        let stats = self.stats.observe(self.board.as_ref());
+        if self.stop_on_stable && stats.is_stable() && !self.paused {
+            self.paused = true;
+            println!(
+                "stabilized at generation {} ({}), paused",
+                stats.generation,
+                stats.describe_period()
+            );
+        }
     }
 
-    pub fn update(&mut self) {
-        self.data = self
-            .data
-            .par_iter()
-            .enumerate()
-            .map(|(idx, &v)| {
-                let (x, y) = self.idx_to_coord(idx);
-                match self.count_neighbors(x, y) {
-                    2 => v,
-                    3 => true,
-                    _ => false,
-                }
-            })
-            .collect();
-
-        // for y in 0..self.height {
-        //     for x in 0..self.width {
-        //         let v = self.get(x, y);
-        //         let start = Instant::now();
-        //         let v = match self.count_neighbors(x, y) {
-        //             2 => v,
-        //             3 => true,
-        //             _ => false,
-        //         };
-        //         dbg!(start.elapsed());
-        //         self.set(x, y, v);
-        //     }
-        // }
-        //
-        // std::mem::swap(&mut self.data, &mut self.data_out);
-    }
+    /// Advance the board according to the target rate, spending at most
+    /// [`FRAME_STEP_TIME`] per frame so the window stays responsive.
+    pub fn step(&mut self) {
+        let elapsed = self.last_step.elapsed().as_secs_f64();
+        self.last_step = Instant::now();
 
-    pub fn to_img(&self) -> DynamicImage {
-        let img = ImageBuffer::<Luma<u8>, _>::from_vec(
-            self.width as u32,
-            self.height as u32,
-            self.data
-                .iter()
-                .map(|&v| if v { 255_u8 } else { 0 })
-                .collect::<Vec<_>>(),
-        )
-        .expect("failed to create img");
+        if self.paused {
+            self.step_budget = 0.0;
+            if std::mem::take(&mut self.step_requested) {
+                self.advance();
+            }
+            return;
+        }
 
-        DynamicImage::ImageLuma8(img)
-    }
-}
+        let Some(tps) = self.tps else {
+            self.advance();
+            return;
+        };
 
-struct AppState {
-    pub window: Arc<Window>,
-    pub surface: Surface<Arc<Window>, Arc<Window>>,
+        self.step_budget += elapsed * tps;
+        let start = Instant::now();
+        while self.step_budget >= 1.0 && !self.paused && start.elapsed() < FRAME_STEP_TIME {
+            self.advance();
+            self.step_budget -= 1.0;
+        }
+        // can't keep up, drop the backlog instead of spiraling
+        self.step_budget = self.step_budget.min(1.0);
+    }
 
-    pub board: Board,
+    /// Advance many generations at once, which [`EngineKind::HashLife`] does
+    /// far faster than one at a time.
+    /// Skipped generations aren't part of the stats or color history.
+    pub fn skip_ahead(&mut self, generations: u64) {
+        let start = Instant::now();
+        self.board.step(generations);
+        println!(
+            "skipped to generation {} in {:.2?}",
+            self.board.generation(),
+            start.elapsed()
+        );
+
+        self.reset_history();
+        self.stats.reset();
+        self.stats.observe(self.board.as_ref());
+    }
 
-    print_interval_secs: f64,
-    last_print: Instant,
-    last_count: u64,
-}
+    pub fn change_speed(&mut self, faster: bool) {
+        let tps = self.tps.unwrap_or(MAX_TPS);
+        let tps = if faster { tps * 2.0 } else { tps / 2.0 };
+        self.tps = (tps < MAX_TPS).then_some(tps.max(MIN_TPS));
+        match self.tps {
+            Some(tps) => println!("tps: {tps}"),
+            None => println!("tps: unlimited"),
+        }
+    }
 
-impl AppState {
-    pub fn update_board_to_size(&mut self) {
+    pub fn window_size(&self) -> (u32, u32) {
         let PhysicalSize { width, height } = self.window.inner_size();
-        self.board = Board::new(width as usize, height as usize);
-        self.board.rand(0, 0.5);
+        (width, height)
+    }
+
+    pub fn on_resize(&mut self) {
+        let (width, height) = self.window_size();
         self.surface
             .resize(
                 NonZeroU32::new(width.max(1)).unwrap(),
@@ -158,16 +210,72 @@ impl AppState {
             .expect("failed to resize surface");
     }
 
+    pub fn fit_camera(&mut self) {
+        self.camera = Camera::fit(self.board_size, self.window_size());
+    }
+
+    /// Paint cells along the line from the last cursor position to `to`,
+    /// so fast drags don't leave gaps.
+    pub fn paint_to(&mut self, to: (f64, f64)) {
+        let Some(value) = self.painting else {
+            return;
+        };
+        let from = self.cursor.unwrap_or(to);
+        // edited boards aren't a continuation of the recorded history
+        self.stats.reset();
+
+        let steps = (to.0 - from.0)
+            .abs()
+            .max((to.1 - from.1).abs())
+            .ceil()
+            .max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let x = from.0 + (to.0 - from.0) * t;
+            let y = from.1 + (to.1 - from.1) * t;
+
+            if let Some((x, y)) =
+                self.camera
+                    .cell_at((x, y), self.board.width(), self.board.height())
+            {
+                self.board.set(x, y, value);
+            }
+        }
+    }
+
     pub fn draw(&mut self) {
+        let (width, height) = self.window_size();
         let mut buffer = self
             .surface
             .buffer_mut()
             .expect("failed to get draw buffer");
 
-        for (idx, v) in self.board.data.iter().enumerate() {
-            buffer[idx] = if *v { 0x00FFFFFF } else { 0 };
+        if (width * height) as usize != buffer.len() {
+            eprintln!("window dimension and buffer size didn't match, skipping draw");
+            return;
         }
 
+        let board = self.board.as_ref();
+        let camera = self.camera;
+        let color_mode = self.color_mode;
+        let history = self.history.as_ref();
+        buffer
+            .par_chunks_mut(width.max(1) as usize)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, px) in row.iter_mut().enumerate() {
+                    let cell = camera.cell_at((x as f64, y as f64), board.width(), board.height());
+                    *px = match (cell, history) {
+                        (Some((x, y)), Some(history)) => {
+                            color::to_argb(history.color(color_mode, board, x, y))
+                        }
+                        (Some((x, y)), None) if board.get(x, y) => 0x00FFFFFF,
+                        (Some(_), None) => 0,
+                        (None, _) => 0x00202020,
+                    };
+                }
+            });
+
         buffer.present().expect("failed to present draw result");
     }
 
@@ -177,7 +285,26 @@ impl AppState {
         if elapsed >= self.print_interval_secs {
             let fps = self.last_count as f64 / elapsed;
             let frametime = 1000.0 / fps;
-            println!("fps: {fps: >8.2}, frametime: {frametime: >6.2}ms");
+            // keep the csv on stdout parseable
+            if !self.stats.csv_to_stdout() {
+                println!(
+                    "fps: {fps: >8.2}, frametime: {frametime: >6.2}ms, generation: {: >8}, population: {: >8}",
+                    self.board.generation(),
+                    self.board.population()
+                );
+            }
+            if let Some(stats) = self.stats.last {
+                let activity = match (stats.births, stats.deaths) {
+                    (Some(births), Some(deaths)) => format!(" | +{births} -{deaths}"),
+                    _ => String::new(),
+                };
+                self.window.set_title(&format!(
+                    "Conway's Game of Life - gen {} | pop {}{activity} | {}",
+                    stats.generation,
+                    stats.population,
+                    stats.describe_period()
+                ));
+            }
 
             self.last_print = Instant::now();
             self.last_count = 0;
@@ -185,7 +312,129 @@ impl AppState {
     }
 }
 
+/// Window pixels moved per arrow key press
+const PAN_STEP: f64 = 50.0;
+
+/// Generations skipped per press of the skip key
+const SKIP_GENERATIONS: u64 = 1024;
+/// Larger boards only support binary colors
+const MAX_HISTORY_CELLS: usize = 1 << 26;
+
+/// Max time spent on simulation per frame
+const FRAME_STEP_TIME: Duration = Duration::from_millis(30);
+const MIN_TPS: f64 = 0.25;
+/// Rates at or above this run one generation per redraw instead
+const MAX_TPS: f64 = 16384.0;
+
+#[derive(Debug, Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    world: WorldArgs,
+    #[command(flatten)]
+    stats: StatsArgs,
+
+    /// Generations per second, 0 runs one generation per redraw
+    #[arg(long, default_value_t = 60.0)]
+    tps: f64,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run without a window and write the frames to a video or gif
+    Record {
+        #[command(flatten)]
+        world: WorldArgs,
+        #[command(flatten)]
+        record: RecordArgs,
+        #[command(flatten)]
+        stats: StatsArgs,
+    },
+    /// Run without a window and print the simulation throughput
+    Bench(BenchArgs),
+    /// Run many random soups without a window and report the long-lived ones
+    Search(SearchArgs),
+}
+
+#[derive(Debug, Args)]
+struct WorldArgs {
+    /// Pattern file to load (`.rle` or plaintext `.cells`), random soup if omitted
+    pattern: Option<PathBuf>,
+
+    /// Board width in cells
+    #[arg(long, default_value_t = 1000)]
+    width: usize,
+    /// Board height in cells
+    #[arg(long, default_value_t = 1000)]
+    height: usize,
+
+    /// How neighbors outside of the board are treated
+    #[arg(long, value_enum, default_value_t = EdgeMode::Dead)]
+    edge_mode: EdgeMode,
+
+    /// Seed of the random soup, picked at random if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Density of the random soup
+    #[arg(long, default_value_t = 0.5)]
+    density: f64,
+
+    /// Simulation engine
+    #[arg(long, value_enum, default_value_t = EngineKind::Dense)]
+    engine: EngineKind,
+}
+
+impl WorldArgs {
+    fn size(&self) -> (usize, usize) {
+        (self.width.max(1), self.height.max(1))
+    }
+
+    fn load_pattern(&self) -> anyhow::Result<Option<Pattern>> {
+        self.pattern
+            .as_deref()
+            .map(Pattern::from_file)
+            .transpose()
+            .context("failed to load pattern")
+    }
+
+    /// Prints the seed when it's picked at random, so the soup can be reproduced
+    fn soup(&self) -> Soup {
+        let seed = self.seed.unwrap_or_else(|| {
+            let seed = rand::random();
+            if self.pattern.is_none() {
+                println!("seed: {seed}");
+            }
+            seed
+        });
+
+        Soup {
+            seed,
+            density: self.density,
+        }
+    }
+
+    fn build_board(&self) -> anyhow::Result<Box<dyn Engine>> {
+        let (width, height) = self.size();
+        self.engine.build(
+            width,
+            height,
+            self.load_pattern()?.as_ref(),
+            self.soup(),
+            self.edge_mode,
+        )
+    }
+}
+
 struct GameOfLife {
+    board_size: (usize, usize),
+    edge_mode: EdgeMode,
+    engine: EngineKind,
+    tps: Option<f64>,
+    pattern: Option<Pattern>,
+    soup: Soup,
+    stats: StatsArgs,
     state: Option<AppState>,
 }
 
@@ -204,13 +453,34 @@ impl ApplicationHandler for GameOfLife {
             window,
             surface,
 
-            board: Board::new(1, 1),
+            board: Box::new(Board::new(1, 1)),
+            engine: self.engine,
+            board_size: self.board_size,
+            pattern: self.pattern.clone(),
+            soup: self.soup,
+            camera: Camera::fit((1, 1), (1, 1)),
+            color_mode: ColorMode::default(),
+            history: None,
+            stats: StatsTracker::from_args(&self.stats).expect("failed to open stats output"),
+            stop_on_stable: self.stats.stop_on_stable,
+
+            paused: false,
+            step_requested: false,
+            tps: self.tps,
+            step_budget: 0.0,
+            last_step: Instant::now(),
+            cursor: None,
+            painting: None,
+            panning: false,
 
             print_interval_secs: 0.1,
             last_print: Instant::now(),
             last_count: 0,
         };
-        state.update_board_to_size();
+        state.board.set_edge_mode(self.edge_mode);
+        state.reset_board();
+        state.on_resize();
+        state.fit_camera();
 
         self.state = Some(state);
     }
@@ -224,14 +494,126 @@ impl ApplicationHandler for GameOfLife {
         if let Some(state) = self.state.as_mut() {
             match event {
                 WindowEvent::Resized(_) => {
-                    state.update_board_to_size();
+                    state.on_resize();
                 }
                 WindowEvent::RedrawRequested => {
-                    state.board.update();
+                    state.step();
                     state.draw();
                     state.update_fps_counter();
                     state.window.request_redraw();
                 }
+                WindowEvent::KeyboardInput { event, .. } => {
+                    if event.state != ElementState::Released {
+                        return;
+                    }
+                    match event.physical_key {
+                        PhysicalKey::Code(KeyCode::KeyE) => match state.board.edge_mode() {
+                            Some(edge_mode) => {
+                                state.board.set_edge_mode(edge_mode.next());
+                                println!("edge mode: {:?}", edge_mode.next());
+                            }
+                            None => println!("edge mode: unbounded"),
+                        },
+                        PhysicalKey::Code(KeyCode::KeyC) => {
+                            state.cycle_color_mode();
+                        }
+                        PhysicalKey::Code(KeyCode::Space) => {
+                            state.paused = !state.paused;
+                            println!("paused: {}", state.paused);
+                        }
+                        PhysicalKey::Code(KeyCode::KeyN) => {
+                            state.step_requested = true;
+                        }
+                        PhysicalKey::Code(KeyCode::KeyR) => {
+                            state.soup.seed = state.soup.seed.wrapping_add(1);
+                            if state.pattern.is_none() {
+                                println!("seed: {}", state.soup.seed);
+                            }
+                            state.reset_board();
+                        }
+                        PhysicalKey::Code(KeyCode::KeyJ) => {
+                            state.skip_ahead(SKIP_GENERATIONS);
+                        }
+                        PhysicalKey::Code(KeyCode::Equal | KeyCode::NumpadAdd) => {
+                            state.change_speed(true);
+                        }
+                        PhysicalKey::Code(KeyCode::Minus | KeyCode::NumpadSubtract) => {
+                            state.change_speed(false);
+                        }
+                        PhysicalKey::Code(KeyCode::KeyF) => {
+                            state.fit_camera();
+                        }
+                        PhysicalKey::Code(KeyCode::ArrowLeft) => state.camera.pan(-PAN_STEP, 0.0),
+                        PhysicalKey::Code(KeyCode::ArrowRight) => state.camera.pan(PAN_STEP, 0.0),
+                        PhysicalKey::Code(KeyCode::ArrowUp) => state.camera.pan(0.0, -PAN_STEP),
+                        PhysicalKey::Code(KeyCode::ArrowDown) => state.camera.pan(0.0, PAN_STEP),
+                        _ => {}
+                    }
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    let position = (position.x, position.y);
+                    if let (true, Some(cursor)) = (state.panning, state.cursor) {
+                        state
+                            .camera
+                            .pan(cursor.0 - position.0, cursor.1 - position.1);
+                    }
+                    state.paint_to(position);
+                    state.cursor = Some(position);
+                }
+                WindowEvent::CursorLeft { .. } => {
+                    state.cursor = None;
+                }
+                WindowEvent::MouseInput {
+                    state: button_state,
+                    button,
+                    ..
+                } => {
+                    let value = match button {
+                        MouseButton::Left => true,
+                        MouseButton::Right => false,
+                        MouseButton::Middle => {
+                            state.panning = button_state.is_pressed();
+                            return;
+                        }
+                        _ => return,
+                    };
+
+                    if button_state.is_pressed() {
+                        state.painting = Some(value);
+                        if let Some(cursor) = state.cursor {
+                            state.paint_to(cursor);
+                        }
+                    } else if state.painting == Some(value) {
+                        state.painting = None;
+                    }
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let lines = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y as f64,
+                        MouseScrollDelta::PixelDelta(position) => position.y / 50.0,
+                    };
+                    let anchor = state.cursor.unwrap_or_else(|| {
+                        let (width, height) = state.window_size();
+                        (width as f64 / 2.0, height as f64 / 2.0)
+                    });
+                    let zoom = if lines > 0.0 {
+                        state.camera.zoom.saturating_mul(2)
+                    } else if lines < 0.0 {
+                        state.camera.zoom / 2
+                    } else {
+                        return;
+                    };
+                    state.camera.zoom_at(anchor, zoom);
+                }
+                WindowEvent::DroppedFile(path) => match Pattern::from_file(&path) {
+                    Ok(pattern) => {
+                        state.pattern = Some(pattern);
+                        state.reset_board();
+                    }
+                    Err(err) => {
+                        eprintln!("failed to load pattern {}: {err:?}", path.display());
+                    }
+                },
                 WindowEvent::CloseRequested => {
                     self.state = None;
                     event_loop.exit();
@@ -242,77 +624,41 @@ impl ApplicationHandler for GameOfLife {
     }
 }
 
-#[tokio::main]
-async fn main() {
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Record {
+            world,
+            record,
+            stats,
+        }) => {
+            let mut board = world.build_board()?;
+            record::record(board.as_mut(), &record, &stats).context("failed to record")
+        }
+        Some(Command::Bench(args)) => bench::bench(&args).context("failed to bench"),
+        Some(Command::Search(args)) => search::search(&args).context("failed to search soups"),
+        None => run_window(cli.world, cli.stats, cli.tps),
+    }
+}
+
+fn run_window(world: WorldArgs, stats: StatsArgs, tps: f64) -> anyhow::Result<()> {
     let event_loop = EventLoop::new().expect("failed to create event loop");
 
-    let mut app = GameOfLife { state: None };
+    let mut app = GameOfLife {
+        board_size: world.size(),
+        edge_mode: world.edge_mode,
+        engine: world.engine,
+        tps: (tps > 0.0).then_some(tps),
+        pattern: world.load_pattern()?,
+        soup: world.soup(),
+        stats,
+        state: None,
+    };
 
     event_loop
         .run_app(&mut app)
         .expect("failed to run application");
 
-    // run_gen_image().await;
-}
-
-#[allow(unused)]
-async fn run_gen_image() {
-    let output = Path::new("./output");
-
-    println!("init");
-    let mut board = Board::new(1920, 1080);
-    board.rand(0, 0.8);
-
-    println!("start");
-    let mut join_set = JoinSet::new();
-    let print_interval = 0.2;
-    let mut last_print = Instant::now();
-    let mut last_count = 0_u64;
-    for i in 0..5000 {
-        let start = Instant::now();
-
-        board.update();
-        let update = start.elapsed();
-
-        let img = board.to_img();
-        let convert = start.elapsed() - update;
-
-        {
-            let mut writer = Cursor::new(Vec::<u8>::new());
-            img.write_to(&mut writer, ImageFormat::Png)
-                .expect("failed to encode image");
-
-            let path = output.join(format!("{i:0>5}.png"));
-            let fut = async {
-                tokio::fs::write(path, writer.into_inner())
-                    .await
-                    .expect("failed to write image");
-            };
-            join_set.spawn(fut);
-
-            // if join_set.len() > 50 {
-            //     while join_set.len() > 10 && join_set.join_next().await.is_some() {}
-            // }
-        }
-        #[allow(unused)]
-        let save = start.elapsed() - update - convert;
-
-        // dbg!(start.elapsed());
-        dbg!(update);
-        dbg!(convert);
-        dbg!(save);
-
-        last_count += 1;
-        let elapsed = last_print.elapsed().as_secs_f64();
-        if elapsed >= print_interval {
-            let fps = last_count as f64 / elapsed;
-            let frametime = 1000.0 / fps;
-            println!("fps: {fps: >8.2}, frametime: {frametime: >6.2}ms");
-
-            last_print = Instant::now();
-            last_count = 0;
-        }
-    }
-
-    while join_set.join_next().await.is_some() {}
+    Ok(())
 }
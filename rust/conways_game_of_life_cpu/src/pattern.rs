@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+
+/// A rectangular block of cells, loaded from a Life pattern file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<bool>,
+}
+
+impl Pattern {
+    /// Load a pattern, `.rle` files are parsed as RLE, anything else as plaintext `.cells`.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).context("failed to read pattern file")?;
+
+        let is_rle = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("rle"));
+        if is_rle {
+            Self::parse_rle(&content)
+        } else {
+            Self::parse_cells(&content)
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[self.width * y + x]
+    }
+
+    fn from_rows(rows: Vec<Vec<bool>>, min_width: usize) -> Self {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0).max(min_width);
+        let height = rows.len();
+
+        let mut cells = Vec::with_capacity(width * height);
+        for mut row in rows {
+            row.resize(width, false);
+            cells.extend(row);
+        }
+
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    // https://conwaylife.com/wiki/Plaintext
+    pub fn parse_cells(s: &str) -> anyhow::Result<Self> {
+        let mut rows = vec![];
+
+        for (line_idx, line) in s.lines().enumerate() {
+            if line.starts_with('!') {
+                continue;
+            }
+
+            let row = line
+                .trim_end()
+                .chars()
+                .map(|ch| match ch {
+                    '.' => Ok(false),
+                    'O' | '*' => Ok(true),
+                    _ => bail!("unexpected character {ch:?} at line {}", line_idx + 1),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            rows.push(row);
+        }
+
+        Ok(Self::from_rows(rows, 0))
+    }
+
+    // https://conwaylife.com/wiki/Run_Length_Encoded
+    pub fn parse_rle(s: &str) -> anyhow::Result<Self> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let header = lines.next().context("missing header line")?;
+        let mut width = 0;
+        for item in header.split(',') {
+            let Some((key, value)) = item.split_once('=') else {
+                bail!("invalid header item {item:?}");
+            };
+            let value = value.trim();
+            match key.trim() {
+                "x" => width = value.parse().context("invalid pattern width")?,
+                "y" => {
+                    value.parse::<usize>().context("invalid pattern height")?;
+                }
+                "rule"
+                    if !value.eq_ignore_ascii_case("B3/S23")
+                        && !value.eq_ignore_ascii_case("23/3") =>
+                {
+                    bail!("unsupported rule {value:?}");
+                }
+                _ => {}
+            }
+        }
+
+        let mut rows = vec![];
+        let mut row = vec![];
+        let mut count = None::<usize>;
+
+        'outer: for line in lines {
+            for ch in line.chars() {
+                match ch {
+                    '0'..='9' => {
+                        let digit = ch as usize - '0' as usize;
+                        count = Some(count.unwrap_or(0) * 10 + digit);
+                    }
+                    'b' | '.' => {
+                        row.extend(std::iter::repeat_n(false, count.take().unwrap_or(1)));
+                    }
+                    '$' => {
+                        rows.push(std::mem::take(&mut row));
+                        for _ in 1..count.take().unwrap_or(1) {
+                            rows.push(vec![]);
+                        }
+                    }
+                    '!' => break 'outer,
+                    ch if ch.is_ascii_alphabetic() => {
+                        row.extend(std::iter::repeat_n(true, count.take().unwrap_or(1)));
+                    }
+                    ch if ch.is_whitespace() => {}
+                    _ => bail!("unexpected character {ch:?}"),
+                }
+            }
+        }
+        rows.push(row);
+
+        Ok(Self::from_rows(rows, width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: [bool; 9] = [false, true, false, false, false, true, true, true, true];
+
+    #[test]
+    fn test_parse_rle() {
+        let pattern = Pattern::parse_rle("#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!")
+            .expect("failed to parse");
+
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.cells, GLIDER);
+    }
+
+    #[test]
+    fn test_parse_rle_multiline_and_blank_rows() {
+        let pattern = Pattern::parse_rle("x = 2, y = 4\no\nb$2$\n2o!").expect("failed to parse");
+
+        assert_eq!((pattern.width, pattern.height), (2, 4));
+        assert_eq!(
+            pattern.cells,
+            [true, false, false, false, false, false, true, true]
+        );
+    }
+
+    #[test]
+    fn test_parse_cells() {
+        let pattern =
+            Pattern::parse_cells("!Name: Glider\n.O\n..O\nOOO\n").expect("failed to parse");
+
+        assert_eq!((pattern.width, pattern.height), (3, 3));
+        assert_eq!(pattern.cells, GLIDER);
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    time::Instant,
+};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, Rgba, RgbaImage,
+};
+
+use crate::{
+    color::{CellHistory, ColorMode},
+    engine::Engine,
+    stats::{StatsArgs, StatsTracker},
+};
+
+#[derive(Debug, Args)]
+pub struct RecordArgs {
+    /// Output file, `.gif` is encoded directly, anything else is piped to ffmpeg
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Number of frames to record
+    #[arg(long, default_value_t = 300)]
+    pub frames: u64,
+    /// Generations per recorded frame
+    #[arg(long, default_value_t = 1)]
+    pub steps: u64,
+    /// Output pixels per cell
+    #[arg(long, default_value_t = 1)]
+    pub scale: u32,
+    /// Frame rate of the output
+    #[arg(long, default_value_t = 30)]
+    pub fps: u32,
+    /// How cells are colored
+    #[arg(long, value_enum, default_value_t = ColorMode::Binary)]
+    pub color_mode: ColorMode,
+}
+
+enum FrameSink {
+    Gif(GifEncoder<File>, Delay),
+    Ffmpeg(Child, ChildStdin),
+}
+
+impl FrameSink {
+    fn open(path: &Path, width: u32, height: u32, fps: u32) -> anyhow::Result<Self> {
+        let is_gif = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+
+        if is_gif {
+            let file = File::create(path).context("failed to create output file")?;
+            let mut encoder = GifEncoder::new(file);
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .context("failed to set gif repeat")?;
+
+            Ok(Self::Gif(encoder, Delay::from_numer_denom_ms(1000, fps)))
+        } else {
+            let mut child = Command::new("ffmpeg")
+                .args(["-loglevel", "error", "-y"])
+                .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+                .args(["-s", &format!("{width}x{height}")])
+                .args(["-r", &fps.to_string()])
+                .args(["-i", "-", "-pix_fmt", "yuv420p"])
+                .arg(path)
+                .stdin(Stdio::piped())
+                .spawn()
+                .context("failed to spawn ffmpeg")?;
+            let stdin = child.stdin.take().context("failed to open ffmpeg stdin")?;
+
+            Ok(Self::Ffmpeg(child, stdin))
+        }
+    }
+
+    fn write(&mut self, rgb: &[u8], width: u32, height: u32) -> anyhow::Result<()> {
+        match self {
+            FrameSink::Gif(encoder, delay) => {
+                let img = RgbaImage::from_fn(width, height, |x, y| {
+                    let idx = (y * width + x) as usize * 3;
+                    Rgba([rgb[idx], rgb[idx + 1], rgb[idx + 2], 255])
+                });
+                encoder
+                    .encode_frame(Frame::from_parts(img, 0, 0, *delay))
+                    .context("failed to encode gif frame")
+            }
+            FrameSink::Ffmpeg(_, stdin) => stdin
+                .write_all(rgb)
+                .context("failed to write frame to ffmpeg"),
+        }
+    }
+
+    fn finish(self) -> anyhow::Result<()> {
+        match self {
+            FrameSink::Gif(..) => Ok(()),
+            FrameSink::Ffmpeg(mut child, stdin) => {
+                drop(stdin);
+                let status = child.wait().context("failed to wait for ffmpeg")?;
+                if !status.success() {
+                    bail!("ffmpeg exited with {status}");
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Render the board into a packed rgb buffer, `scale` pixels per cell.
+fn render_rgb(
+    board: &dyn Engine,
+    history: &CellHistory,
+    color_mode: ColorMode,
+    scale: u32,
+    buf: &mut Vec<u8>,
+) {
+    let scale = scale as usize;
+    let row_len = board.width() * scale * 3;
+
+    buf.clear();
+    buf.reserve(row_len * board.height() * scale);
+    for y in 0..board.height() {
+        let row_start = buf.len();
+        for x in 0..board.width() {
+            let rgb = history.color(color_mode, board, x, y);
+            for _ in 0..scale {
+                buf.extend_from_slice(&rgb);
+            }
+        }
+        for _ in 1..scale {
+            buf.extend_from_within(row_start..row_start + row_len);
+        }
+    }
+}
+
+pub fn record(
+    board: &mut dyn Engine,
+    args: &RecordArgs,
+    stats_args: &StatsArgs,
+) -> anyhow::Result<()> {
+    let scale = args.scale.max(1);
+    let width = board.width() as u32 * scale;
+    let height = board.height() as u32 * scale;
+
+    let mut sink = FrameSink::open(&args.output, width, height, args.fps.max(1))?;
+    let mut buf = vec![];
+    let mut history = CellHistory::new(board);
+    let mut stats = StatsTracker::from_args(stats_args)?;
+    stats.observe(board);
+
+    let start = Instant::now();
+    let mut frames = 0;
+    'record: while frames < args.frames {
+        render_rgb(board, &history, args.color_mode, scale, &mut buf);
+        sink.write(&buf, width, height)?;
+        frames += 1;
+
+        for _ in 0..args.steps {
+            board.update();
+            if args.color_mode != ColorMode::Binary {
+                history.update(board);
+            }
+
+            let stats = stats.observe(board);
+            if stats_args.stop_on_stable && stats.is_stable() {
+                eprintln!(
+                    "stabilized at generation {} ({})",
+                    stats.generation,
+                    stats.describe_period()
+                );
+                break 'record;
+            }
+        }
+
+        if frames % 100 == 0 {
+            eprintln!("recorded {} / {} frames", frames, args.frames);
+        }
+    }
+    sink.finish()?;
+
+    eprintln!(
+        "recorded {} frames to {} in {:.2?}",
+        frames,
+        args.output.display(),
+        start.elapsed()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use anyhow::Context;
+use clap::Args;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    board::{Board, EdgeMode, Soup},
+    stats::StatsTracker,
+};
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Number of seeds to try
+    #[arg(long, default_value_t = 1000)]
+    pub seeds: u64,
+    /// First seed, the search covers `start_seed..start_seed + seeds`
+    #[arg(long, default_value_t = 0)]
+    pub start_seed: u64,
+    /// Density of the random soups
+    #[arg(long, default_value_t = 0.5)]
+    pub density: f64,
+    /// Width and height of the board in cells
+    #[arg(long, default_value_t = 64)]
+    pub size: usize,
+    /// How neighbors outside of the board are treated
+    #[arg(long, value_enum, default_value_t = EdgeMode::Wrap)]
+    pub edge_mode: EdgeMode,
+    /// Give up on a soup after this many generations
+    #[arg(long, default_value_t = 20000)]
+    pub max_gens: u64,
+    /// Report soups living at least this many generations before stabilizing
+    #[arg(long, default_value_t = 2000)]
+    pub min_lifespan: u64,
+    /// Report file, csv sorted by lifespan
+    #[arg(short, long, default_value = "soups.csv")]
+    pub output: PathBuf,
+}
+
+/// Outcome of running a single soup
+#[derive(Debug, Clone, Copy)]
+struct SoupResult {
+    seed: u64,
+    /// Generation the soup first repeated at, or `max_gens` if it never did
+    lifespan: u64,
+    period: Option<u64>,
+    population: u64,
+}
+
+fn run_soup(args: &SearchArgs, seed: u64) -> SoupResult {
+    let soup = Soup {
+        seed,
+        density: args.density,
+    };
+    let size = args.size.max(1);
+    let mut board = Board::from_pattern(size, size, None, soup);
+    board.edge_mode = args.edge_mode;
+
+    let mut stats = StatsTracker::new(StatsTracker::DEFAULT_MAX_PERIOD);
+    let mut last = stats.observe(&board);
+    while board.generation() < args.max_gens && !last.is_stable() {
+        board.update();
+        last = stats.observe(&board);
+    }
+
+    SoupResult {
+        seed,
+        // the repeat is detected one period after the state first appeared
+        lifespan: last.generation - last.period.unwrap_or(0),
+        period: last.period,
+        population: last.population,
+    }
+}
+
+/// Run many random soups headlessly and report the long-lived ones.
+pub fn search(args: &SearchArgs) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let done = AtomicU64::new(0);
+
+    let mut interesting = (args.start_seed..args.start_seed.saturating_add(args.seeds))
+        .into_par_iter()
+        .map(|seed| {
+            let result = run_soup(args, seed);
+
+            let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if done.is_multiple_of(100) {
+                eprintln!("searched {done} / {} soups", args.seeds);
+            }
+            result
+        })
+        .filter(|result| result.lifespan >= args.min_lifespan)
+        .collect::<Vec<_>>();
+    interesting.sort_by_key(|result| (std::cmp::Reverse(result.lifespan), result.seed));
+
+    let mut report =
+        BufWriter::new(File::create(&args.output).context("failed to create report file")?);
+    writeln!(report, "seed,lifespan,period,population").context("failed to write report")?;
+    for result in &interesting {
+        writeln!(
+            report,
+            "{},{},{},{}",
+            result.seed,
+            result.lifespan,
+            result.period.map(|it| it.to_string()).unwrap_or_default(),
+            result.population
+        )
+        .context("failed to write report")?;
+    }
+    report.flush().context("failed to write report")?;
+
+    println!(
+        "searched {} soups in {:.2?}, {} lived at least {} generations",
+        args.seeds,
+        start.elapsed(),
+        interesting.len(),
+        args.min_lifespan
+    );
+    if let Some(best) = interesting.first() {
+        println!(
+            "longest: seed {} with {} generations{}",
+            best.seed,
+            best.lifespan,
+            if best.period.is_none() {
+                " (still active)"
+            } else {
+                ""
+            }
+        );
+    }
+    println!("report written to {}", args.output.display());
+
+    Ok(())
+}
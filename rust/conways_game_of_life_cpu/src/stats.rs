@@ -0,0 +1,227 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use clap::Args;
+
+use crate::engine::Engine;
+
+#[derive(Debug, Clone, Args)]
+pub struct StatsArgs {
+    /// Write per generation statistics as csv, `-` for stdout
+    #[arg(long)]
+    pub stats: Option<PathBuf>,
+    /// Stop once the board becomes static or oscillates
+    #[arg(long)]
+    pub stop_on_stable: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub population: u64,
+    /// `None` if the engine doesn't track births and deaths
+    pub births: Option<u64>,
+    pub deaths: Option<u64>,
+    /// Period of the repeating state, 1 for still lifes, `None` while still evolving
+    pub period: Option<u64>,
+}
+
+impl GenerationStats {
+    pub const CSV_HEADER: &'static str = "generation,population,births,deaths,period";
+
+    pub fn is_stable(&self) -> bool {
+        self.period.is_some()
+    }
+
+    pub fn describe_period(&self) -> String {
+        match self.period {
+            None => "evolving".to_string(),
+            Some(_) if self.population == 0 => "dead".to_string(),
+            Some(1) => "still life".to_string(),
+            Some(period) => format!("period {period}"),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.generation,
+            self.population,
+            optional(self.births),
+            optional(self.deaths),
+            optional(self.period)
+        )
+    }
+}
+
+fn optional(value: Option<u64>) -> String {
+    value.map(|it| it.to_string()).unwrap_or_default()
+}
+
+/// Collects statistics every generation and detects stabilization by
+/// remembering the state hashes of the last `max_period` generations.
+pub struct StatsTracker {
+    max_period: usize,
+    recent: VecDeque<u64>,
+    /// state hash -> generation it was last seen
+    seen: HashMap<u64, u64>,
+    csv: Option<Box<dyn Write>>,
+    csv_to_stdout: bool,
+
+    pub last: Option<GenerationStats>,
+}
+
+impl Debug for StatsTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatsTracker")
+            .field("max_period", &self.max_period)
+            .field("last", &self.last)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StatsTracker {
+    pub const DEFAULT_MAX_PERIOD: usize = 64;
+
+    pub fn new(max_period: usize) -> Self {
+        Self {
+            max_period,
+            recent: VecDeque::with_capacity(max_period + 1),
+            seen: HashMap::with_capacity(max_period + 1),
+            csv: None,
+            csv_to_stdout: false,
+
+            last: None,
+        }
+    }
+
+    pub fn from_args(args: &StatsArgs) -> anyhow::Result<Self> {
+        let mut this = Self::new(Self::DEFAULT_MAX_PERIOD);
+
+        if let Some(path) = &args.stats {
+            this.csv_to_stdout = path.as_os_str() == "-";
+            let mut csv: Box<dyn Write> = if this.csv_to_stdout {
+                Box::new(std::io::stdout())
+            } else {
+                Box::new(BufWriter::new(
+                    File::create(path).context("failed to create stats file")?,
+                ))
+            };
+            writeln!(csv, "{}", GenerationStats::CSV_HEADER).context("failed to write stats")?;
+            this.csv = Some(csv);
+        }
+
+        Ok(this)
+    }
+
+    /// Whether the csv goes to stdout, other output should stay out of it
+    pub fn csv_to_stdout(&self) -> bool {
+        self.csv.is_some() && self.csv_to_stdout
+    }
+
+    /// Forget all history, call after the board was edited or replaced.
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.seen.clear();
+        self.last = None;
+    }
+
+    /// Record the current generation of `board`.
+    pub fn observe(&mut self, board: &dyn Engine) -> GenerationStats {
+        let generation = board.generation();
+        let hash = board.state_hash();
+
+        let period = self.seen.get(&hash).map(|&seen| generation - seen);
+
+        self.seen.insert(hash, generation);
+        self.recent.push_back(hash);
+        if self.recent.len() > self.max_period {
+            if let Some(old) = self.recent.pop_front() {
+                if self
+                    .seen
+                    .get(&old)
+                    .is_some_and(|&seen| generation - seen >= self.max_period as u64)
+                {
+                    self.seen.remove(&old);
+                }
+            }
+        }
+
+        let activity = board.activity();
+        let stats = GenerationStats {
+            generation,
+            population: board.population(),
+            births: activity.map(|(births, _)| births),
+            deaths: activity.map(|(_, deaths)| deaths),
+            period,
+        };
+
+        if let Some(csv) = self.csv.as_mut() {
+            if let Err(err) = writeln!(csv, "{}", stats.to_csv()) {
+                eprintln!("failed to write stats, disabling: {err}");
+                self.csv = None;
+            }
+        }
+
+        self.last = Some(stats);
+        stats
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(csv) = self.csv.as_mut() {
+            let _ = csv.flush();
+        }
+    }
+}
+
+impl Drop for StatsTracker {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::{Board, Soup},
+        pattern::Pattern,
+    };
+
+    fn run(cells: &str, gens: usize) -> GenerationStats {
+        let pattern = Pattern::parse_cells(cells).unwrap();
+        let mut board = Board::from_pattern(8, 8, Some(&pattern), Soup::default());
+        let mut tracker = StatsTracker::new(StatsTracker::DEFAULT_MAX_PERIOD);
+
+        let mut stats = tracker.observe(&board);
+        for _ in 0..gens {
+            board.update();
+            stats = tracker.observe(&board);
+        }
+        stats
+    }
+
+    #[test]
+    fn test_detects_still_life() {
+        let stats = run("OO\nOO", 1);
+        assert_eq!(stats.period, Some(1));
+        assert_eq!((stats.births, stats.deaths), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn test_detects_oscillator() {
+        let stats = run("OOO", 1);
+        assert_eq!(stats.period, None);
+        assert_eq!((stats.births, stats.deaths), (Some(2), Some(2)));
+        assert_eq!(stats.population, 3);
+
+        let stats = run("OOO", 2);
+        assert_eq!(stats.period, Some(2));
+    }
+}
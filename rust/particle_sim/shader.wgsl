@@ -4,6 +4,26 @@ struct Param {
     mouse_pos: vec2<f32>,
     boundary_collision_factor: u32,
     global_velocity_damping: u32,
+    repulsion_distance: f32,
+    repulsion_strength: f32,
+    gravity: vec2<f32>,
+    // 0 none, 1 wind, 2 attractor
+    force_field: u32,
+    field_strength: f32,
+    field_frequency: f32,
+    time: f32,
+    // 0 speed, 1 density, 2 cell
+    color_mode: u32,
+    color_scale: f32,
+    // 0 repulsion, 1 sph
+    model: u32,
+    rest_density: f32,
+    stiffness: f32,
+    viscosity: f32,
+    // size of the simulated area
+    boundary: vec2<f32>,
+    // fits the area into the window without stretching it
+    view_scale: vec2<f32>,
 }
 
 struct Point {
@@ -11,6 +31,14 @@ struct Point {
     velocity: vec2<f32>,
 }
 
+// kind: 0 none, 1 circle, 2 box
+struct Obstacle {
+    center: vec2<f32>,
+    // radius twice for a circle, half extents for a box
+    size: vec2<f32>,
+    kind: u32,
+}
+
 struct PointHashToIdx {
     index: u32,
     hash: u32,
@@ -34,6 +62,15 @@ var<storage, read_write> points_hash_data: array<PointHashToIdx>;
 @binding(3)
 var<storage, read_write> points_hash_index: array<u32>;
 
+@group(0)
+@binding(4)
+var<storage, read> obstacles: array<Obstacle>;
+
+// sph density of each point, or its neighbour count without sph
+@group(0)
+@binding(5)
+var<storage, read_write> points_density: array<f32>;
+
 struct VertexOut {
     @builtin(position)
     pos: vec4<f32>,
@@ -50,6 +87,7 @@ fn vs_main(
     @builtin(vertex_index) in_vertex_index: u32,
     @location(0) point_pos: vec2<f32>,
     @location(1) velocity: vec2<f32>,
+    @location(2) density: f32,
 ) -> VertexOut {
     var vertices = array(
         vec2(-1.0, 1.0),
@@ -60,32 +98,91 @@ fn vs_main(
         vec2(1.0, 1.0),
     );
 
-    let point_pos_clip = (point_pos * boundary_scaler - 0.5) * 2.0;
-    let vertex_pos = vertices[in_vertex_index] * point_radius * sqrt(2.0) + point_pos_clip;
-
-    let speed = length(velocity) / max_velocity_visual;
-    let red = speed;
-    let green = max(1 - speed, 0.0);
-    let color = vec4<f32>(red, green, 0.0, 1.0) ;
+    let vertex_pos = vertices[in_vertex_index] * point_size * sqrt(2.0) + point_pos;
+
+    var color = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    if param.color_mode == 1u {
+        let max_density = select(max_density_visual, param.rest_density * 2, param.model == 1u);
+        let heat = density / max_density * param.color_scale;
+        color = heat_color(heat);
+    } else if param.color_mode == 2u {
+        let hash = grid_id_to_hash_unbounded(vec2<i32>(point_pos / grid_size));
+        // scrambled, so neighbouring cells differ
+        color = hue_color(f32((hash * 2654435761u) >> 24u) / 256.0);
+    } else {
+        let speed = length(velocity) / max_velocity_visual * param.color_scale;
+        color = heat_color(speed);
+    }
 
     return VertexOut(
-        vec4<f32>(vertex_pos, 0, 1),
-        vertex_pos.xy,
-        point_pos_clip,
+        vec4<f32>(to_clip(vertex_pos), 0, 1),
+        vertex_pos,
+        point_pos,
         color,
     );
 }
 
+struct ObstacleOut {
+    @builtin(position)
+    pos: vec4<f32>,
+    // -1 to 1 across the obstacle
+    @location(0)
+    local: vec2<f32>,
+    @location(1) @interpolate(flat)
+    kind: u32,
+}
+
+@vertex
+fn vs_obstacle(
+    @builtin(vertex_index) in_vertex_index: u32,
+    @location(0) center: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) kind: u32,
+) -> ObstacleOut {
+    var vertices = array(
+        vec2(-1.0, 1.0),
+        vec2(-1.0, -1.0),
+        vec2(1.0, -1.0),
+        vec2(-1.0, 1.0),
+        vec2(1.0, -1.0),
+        vec2(1.0, 1.0),
+    );
+    let local = vertices[in_vertex_index];
+    let pos = to_clip(center + local * size);
+
+    return ObstacleOut(vec4<f32>(pos, 0, 1), local, kind);
+}
+
+@fragment
+fn fs_obstacle(info: ObstacleOut) -> @location(0) vec4<f32> {
+    if info.kind == 0u || (info.kind == 1u && length(info.local) > 1.0) {
+        discard;
+    }
+    return vec4<f32>(0.5, 0.5, 0.6, 1.0);
+}
+
+// green when cold to red when hot
+fn heat_color(heat: f32) -> vec4<f32> {
+    let red = heat;
+    let green = max(1 - heat, 0.0);
+    return vec4<f32>(red, green, 0.0, 1.0);
+}
+
+fn hue_color(hue: f32) -> vec4<f32> {
+    let rgb = clamp(abs(fract(hue + vec3(0.0, 2.0, 1.0) / 3.0) * 6.0 - 3.0) - 1.0, vec3(0.0), vec3(1.0));
+    return vec4<f32>(rgb, 1.0);
+}
+
 @fragment
 fn fs_main(
     info: VertexOut,
 ) -> @location(0) vec4<f32> {
     let dst = distance(info.v_pos, info.center);
-    let in_range = f32(dst < point_radius);
+    let in_range = f32(dst < point_size);
 
     let alpha = smoothstep(
-        point_radius,
-        point_radius - point_radius * edge_width,
+        point_size,
+        point_size - point_size * edge_width,
         dst
     );
 
@@ -95,29 +192,24 @@ fn fs_main(
 // percent
 const edge_width = 0.1;
 const max_velocity_visual = 2000f;
+const max_density_visual = 12f;
+// default of param.boundary
 const boundary_size = 80000.0;
-const boundary_x = boundary_size;
-const boundary_y = boundary_size; 
 const grid_size = 300.0;
 const point_size = 195.0;
-const a = 200f;
-const b = 50000f;
-const gravity = 250.0;
 const speed = 1.0;
+const tau = 6.283185307179586;
 
-const boundary_scaler = 1.0 / vec2<f32>(boundary_x, boundary_y);
-const point_radius = point_size * boundary_scaler.x;
-// const point_radius_squared = point_radius * point_radius;
 
 const mouse_radius = boundary_size / 10f;
 const mouse_strength = 20000f;
 
 const gravity_center_count = 1u;
 const gravity_centers = array<vec2<f32>, gravity_center_count>(
-    vec2<f32>(boundary_x / 2, boundary_y / 2),
-    // vec2<f32>(boundary_x / 2, boundary_y / 2 + 1250),
-    // vec2<f32>(boundary_x / 2 - 1250, boundary_y / 2 - 1250),
-    // vec2<f32>(boundary_x / 2 + 1250, boundary_y / 2 - 1250)
+    vec2<f32>(boundary_size / 2, boundary_size / 2),
+    // vec2<f32>(boundary_size / 2, boundary_size / 2 + 1250),
+    // vec2<f32>(boundary_size / 2 - 1250, boundary_size / 2 - 1250),
+    // vec2<f32>(boundary_size / 2 + 1250, boundary_size / 2 - 1250)
 );
 
 const grid_offset_count = 9u;
@@ -137,6 +229,10 @@ const grid_offsets = array<vec2<i32>, grid_offset_count>(
 @workgroup_size(1)
 fn cs_main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let idx = global_id.x + global_id.y * 65535 + global_id.z * 65535 * 65535;
+    // the dispatch is rounded up to whole rows of 65535 invocations
+    if idx >= arrayLength(&points) {
+        return;
+    }
     let time_delta = param.time_delta;
 
     var p = points[idx];
@@ -179,7 +275,7 @@ fn update(idx: u32, time_delta: f32) -> Point {
     var pos = p.pos;
     // p.pos += p.velocity * 1f / 1000f;
 
-    var acc = vec2<f32>(0.0, -gravity);
+    var acc = param.gravity + force_field(p.pos);
 
     // var gravity_centers = gravity_centers;
     // for (var i = 0u; i < gravity_center_count; i += 1u) {
@@ -188,7 +284,7 @@ fn update(idx: u32, time_delta: f32) -> Point {
     //     acc += to_center * gravity;
     // }
 
-    let mouse_pos = param.mouse_pos * vec2(boundary_x, boundary_y);
+    let mouse_pos = screen_to_world(param.mouse_pos);
     let to_mouse_distance_squared = distanse_squared(mouse_pos, p.pos);
     let mouse_in_range = to_mouse_distance_squared < pow(mouse_radius, 2f);
     if param.mouse_press > 0 && mouse_in_range {
@@ -203,7 +299,13 @@ fn update(idx: u32, time_delta: f32) -> Point {
         acc += (to_mouse * mouse_acc - p.velocity) * scaler;
     }
 
+    let sph = param.model == 1u;
+    let density = points_density[idx];
+    var pressure_acc = vec2(0f);
+    var viscosity_acc = vec2(0f);
+
     let grid_id = point_to_grid_id(p);
+    var neighbours = 0u;
     var grid_offsets = grid_offsets;
     for (var offset_idx = 0u; offset_idx < grid_offset_count; offset_idx += 1u) {
         let id = grid_id + grid_offsets[offset_idx];
@@ -223,8 +325,23 @@ fn update(idx: u32, time_delta: f32) -> Point {
                 continue;
             }
 
+            neighbours += 1u;
+
             let dst = distance(p.pos, other_p.pos);
-            let force = b * (pow(a / dst, 12f) - pow(a / dst, 6f));
+            if sph {
+                // kernels scaled to the neighbour range
+                let q = dst / grid_size;
+                let other_density = points_density[point_idx];
+                let dir = select(vec2(0f, 1f), (p.pos - other_p.pos) / dst, dst > 0.00001);
+
+                let shared_pressure = (pressure(density) + pressure(other_density)) / 2;
+                pressure_acc += dir * shared_pressure * pow(1 - q, 2f) / other_density;
+                viscosity_acc += (other_p.velocity - p.velocity) * (1 - q) / other_density;
+                continue;
+            }
+
+            let a = param.repulsion_distance;
+            let force = param.repulsion_strength * (pow(a / dst, 12f) - pow(a / dst, 6f));
 
             let repel_direction = normalize(p.pos - other_p.pos);
             let accl = repel_direction * force;
@@ -232,19 +349,25 @@ fn update(idx: u32, time_delta: f32) -> Point {
         }
     }
 
+    if sph {
+        acc += pressure_acc / density + viscosity_acc * param.viscosity;
+    } else {
+        points_density[idx] = f32(neighbours);
+    }
+
     p.velocity += acc * time_delta;
     // p.velocity = acc;
 
-    let x_out_up = (p.pos.x > boundary_x && p.velocity.x > 0);
-    let y_out_up = (p.pos.y > boundary_y && p.velocity.y > 0);
+    let x_out_up = (p.pos.x > param.boundary.x && p.velocity.x > 0);
+    let y_out_up = (p.pos.y > param.boundary.y && p.velocity.y > 0);
     let x_out_bottom = (p.pos.x < 0 && p.velocity.x < 0);
     let y_out_bottom = (p.pos.y < 0 && p.velocity.y < 0);
 
     // collide box
     let collide_x = x_out_up || x_out_bottom;
     let collide_y = y_out_up || y_out_bottom;
-    // let collide_x_pos = select(.0, boundary_x, x_out_up) + select(.0, .0, x_out_bottom);
-    // let collide_y_pos = select(.0, boundary_y, y_out_up) + select(.0, .0, y_out_bottom);
+    // let collide_x_pos = select(.0, param.boundary.x, x_out_up) + select(.0, .0, x_out_bottom);
+    // let collide_y_pos = select(.0, param.boundary.y, y_out_up) + select(.0, .0, y_out_bottom);
     // p.pos.x = select(p.pos.x, collide_x_pos, collide_x);
     // p.pos.y = select(p.pos.y, collide_y_pos, collide_y);
     p.velocity.x *= select(1.0, -1.0, collide_x);
@@ -257,19 +380,60 @@ fn update(idx: u32, time_delta: f32) -> Point {
     //     p.pos.x = 0.0;
     // }
     // if x_out_bottom {
-    //     p.pos.x = boundary_x;
+    //     p.pos.x = param.boundary.x;
     // }
     // if y_out_up {
     //     p.pos.y = 0.0;
     // }
     // if y_out_bottom {
-    //     p.pos.y = boundary_y;
+    //     p.pos.y = param.boundary.y;
     // }
 
     p.velocity *= f32(param.global_velocity_damping) * 0.0001;
 
     p.pos = pos + p.velocity * time_delta;
 
+    return collide_obstacles(p);
+}
+
+// push the point out of obstacles and bounce it off the surface
+fn collide_obstacles(point: Point) -> Point {
+    var p = point;
+    let restitution = f32(param.boundary_collision_factor) * 0.01;
+
+    for (var i = 0u; i < arrayLength(&obstacles); i += 1u) {
+        let obstacle = obstacles[i];
+        let offset = p.pos - obstacle.center;
+
+        var depth = 0f;
+        var normal = vec2(0f, 1f);
+        if obstacle.kind == 1u {
+            let dst = length(offset);
+            depth = obstacle.size.x - dst;
+            normal = select(normal, offset / dst, dst > 0.00001);
+        } else if obstacle.kind == 2u {
+            // out through the closest side
+            let overlap = obstacle.size - abs(offset);
+            let side = select(vec2(1f), vec2(-1f), offset < vec2(0f));
+            if overlap.x < overlap.y {
+                depth = overlap.x;
+                normal = vec2(side.x, 0f);
+            } else {
+                depth = overlap.y;
+                normal = vec2(0f, side.y);
+            }
+        }
+        if depth <= 0 {
+            continue;
+        }
+
+        p.pos += normal * depth;
+        let normal_speed = dot(p.velocity, normal);
+        if normal_speed < 0 {
+            p.velocity -= normal * normal_speed * (1 + restitution);
+        }
+    }
+
     return p;
 }
 
@@ -277,6 +441,9 @@ fn update(idx: u32, time_delta: f32) -> Point {
 @workgroup_size(1)
 fn calc_hash_data(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let idx = global_id.x + global_id.y * 65535 + global_id.z * 65535 * 65535;
+    if idx >= arrayLength(&points) {
+        return;
+    }
 
     let grid_id = point_to_grid_id(points[idx]);
     let hash = grid_id_to_hash(grid_id);
@@ -287,6 +454,9 @@ fn calc_hash_data(@builtin(global_invocation_id) global_id: vec3<u32>) {
 @workgroup_size(1)
 fn calc_hash_index(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let idx = global_id.x + global_id.y * 65535 + global_id.z * 65535 * 65535;
+    if idx >= arrayLength(&points) {
+        return;
+    }
 
     let cur = points_hash_data[idx].hash;
 
@@ -300,13 +470,90 @@ fn calc_hash_index(@builtin(global_invocation_id) global_id: vec3<u32>) {
     }
 }
 
+fn pressure(density: f32) -> f32 {
+    // no pull below the rest density, it makes points clump
+    return max(param.stiffness * (density - param.rest_density), 0f);
+}
+
+// sph density from the neighbours, before the forces need it
+@compute
+@workgroup_size(1)
+fn calc_density(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let idx = global_id.x + global_id.y * 65535 + global_id.z * 65535 * 65535;
+    if idx >= arrayLength(&points) {
+        return;
+    }
+    let p = points[idx];
+
+    // the point itself
+    var density = 1f;
+
+    let grid_id = point_to_grid_id(p);
+    var grid_offsets = grid_offsets;
+    for (var offset_idx = 0u; offset_idx < grid_offset_count; offset_idx += 1u) {
+        let id = grid_id + grid_offsets[offset_idx];
+        let hash = grid_id_to_hash(id);
+        let start_idx = points_hash_index[hash];
+
+        for (var hash_idx = start_idx; hash_idx < arrayLength(&points_hash_data); hash_idx += 1u) {
+            let point_hash = points_hash_data[hash_idx];
+            if point_hash.hash != hash {
+                break;
+            }
+
+            let point_idx = point_hash.index;
+            let dst = distance(points[point_idx].pos, p.pos);
+            if point_idx == idx || dst > grid_size {
+                continue;
+            }
+
+            let q = dst / grid_size;
+            density += pow(1 - q * q, 3f);
+        }
+    }
+
+    points_density[idx] = density;
+}
+
+fn force_field(pos: vec2<f32>) -> vec2<f32> {
+    let phase = param.time * param.field_frequency * tau;
+
+    if param.force_field == 1u {
+        let height = pos.y / param.boundary.y * tau;
+        return vec2(sin(phase + height) * param.field_strength, 0f);
+    } else if param.force_field == 2u {
+        let to_center = param.boundary / 2 - pos;
+        let dst = length(to_center);
+        if dst < 0.00001 {
+            return vec2(0f);
+        }
+        // full strength at frequency 0
+        let pulse = (1 + cos(phase)) / 2;
+        return to_center / dst * param.field_strength * pulse;
+    }
+    return vec2(0f);
+}
+
+// simulation space to clip space
+fn to_clip(pos: vec2<f32>) -> vec2<f32> {
+    return (pos / param.boundary - 0.5) * 2.0 * param.view_scale;
+}
+
+// window space from 0 to 1 to simulation space
+fn screen_to_world(pos: vec2<f32>) -> vec2<f32> {
+    return ((pos - 0.5) / param.view_scale + 0.5) * param.boundary;
+}
+
 fn point_to_grid_id(p: Point) -> vec2<i32> {
     return vec2<i32>(p.pos / grid_size);
 }
 
 fn grid_id_to_hash(id: vec2<i32>) -> u32 {
-    let hash = u32(id.x) * 15823 + u32(id.y) + 9737333;
-    return hash % arrayLength(&points_hash_data);
+    return grid_id_to_hash_unbounded(id) % arrayLength(&points_hash_data);
+}
+
+fn grid_id_to_hash_unbounded(id: vec2<i32>) -> u32 {
+    return u32(id.x) * 15823 + u32(id.y) + 9737333;
 }
 
 fn distanse_squared(a: vec2<f32>, b: vec2<f32>) -> f32 {
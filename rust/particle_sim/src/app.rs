@@ -13,10 +13,17 @@ use winit::{
 };
 
 use self::viewport::{
-    renderer::{command::Command, param::Param, Renderer},
+    renderer::{
+        capture::timestamped, command::Command, obstacle::Shape,
+        param::Param,
+        point::SpawnConfig,
+        replay::{Player, Recorder},
+        timestep::Timestep,
+        Renderer,
+    },
     Viewport,
 };
-use crate::wgpu_context::WgpuContext;
+use crate::{config::WindowConfig, wgpu_context::WgpuContext};
 pub mod viewport;
 
 #[derive(Debug)]
@@ -24,8 +31,18 @@ pub struct App {
     pub ctx: WgpuContext,
     pub state: Arc<Mutex<Param>>,
     pub command_queue: Arc<Mutex<VecDeque<Command>>>,
+    /// Points the renderer starts with
+    pub spawn_config: SpawnConfig,
+    /// Handed to the renderer once it's created
+    pub recorder: Option<Recorder>,
+    pub player: Option<Player>,
+    pub window_config: WindowConfig,
+    /// Handed to the renderer once it's created
+    pub timestep: Timestep,
 
     pub paused: bool,
+    /// Run a single tick on the next frame while paused
+    pub step_requested: bool,
 
     pub viewport: Option<Viewport>,
 }
@@ -37,21 +54,32 @@ impl ApplicationHandler for App {
     ) {
         let window: Arc<_> = event_loop
             .create_window(
-                WindowAttributes::default()
-                    .with_inner_size(PhysicalSize::new(1200, 1200)),
+                WindowAttributes::default().with_inner_size(
+                    PhysicalSize::<u32>::from(self.window_config.size),
+                ),
             )
             .expect("failed to crate window")
             .into();
 
         self.viewport = Some(
-            Viewport::new(window.clone(), &self.ctx, |ctx, surface| {
-                Renderer::new(
-                    ctx,
-                    surface,
-                    self.state.clone(),
-                    self.command_queue.clone(),
-                )
-            })
+            Viewport::new(
+                window.clone(),
+                &self.ctx,
+                &self.window_config,
+                |ctx, surface| {
+                    let mut renderer = Renderer::new(
+                        ctx,
+                        surface,
+                        self.state.clone(),
+                        self.command_queue.clone(),
+                        &self.spawn_config,
+                    );
+                    renderer.recorder = self.recorder.take();
+                    renderer.player = self.player.take();
+                    renderer.timestep = self.timestep.clone();
+                    renderer
+                },
+            )
             .expect("failed to create viewport"),
         );
     }
@@ -62,6 +90,14 @@ impl ApplicationHandler for App {
         _window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        if let Some(viewport) = self.viewport.as_mut() {
+            if viewport.overlay.on_window_event(&viewport.window, &event) {
+                // the simulation might be paused, show the change anyway
+                viewport.window.request_redraw();
+                return;
+            }
+        }
+
         match event {
             WindowEvent::CloseRequested => {
                 info!("WindowEvent::CloseRequested");
@@ -76,21 +112,15 @@ impl ApplicationHandler for App {
             }
             WindowEvent::RedrawRequested => {
                 if let Some(viewport) = self.viewport.as_mut() {
-                    //let start = std::time::Instant::now();
-                    viewport.renderer.update(&self.ctx);
-                    //let update = start.elapsed();
+                    viewport.renderer.reload_shader(&self.ctx);
+                    if !self.paused {
+                        viewport.renderer.update(&self.ctx);
+                    } else if std::mem::take(&mut self.step_requested) {
+                        viewport.renderer.step(&self.ctx);
+                    } else {
+                        viewport.renderer.apply_commands(&self.ctx);
+                    }
                     viewport.render(&self.ctx).expect("failed to render");
-                    //let render = start.elapsed() - update;
-                    //println!(
-                    //    "update: {: >8.2?}, render: {: >8.2?}",
-                    //    update, render
-                    //);
-                    //while !self
-                    //    .ctx
-                    //    .device
-                    //    .poll(wgpu::MaintainBase::Poll)
-                    //    .is_queue_empty()
-                    //{}
                     if !self.paused {
                         viewport.window.request_redraw();
                     }
@@ -127,72 +157,90 @@ impl ApplicationHandler for App {
                                 self.command_queue.lock().unwrap();
                             cmd_queue.push_back(Command::Reset);
                         }
-                        "c" => {
+                        "g" => {
                             let mut state = self.state.lock().unwrap();
-                            state.global_velocity_damping -= 1;
-                            info!(
-                                "global_velocity_damping: {}",
-                                state.global_velocity_damping
-                            );
+                            state.gravity = if state.gravity == [0.0; 2] {
+                                Param::default().gravity
+                            } else {
+                                [0.0; 2]
+                            };
+                            info!("gravity: {:?}", state.gravity);
                         }
-                        "h" => {
+                        "f" => {
                             let mut state = self.state.lock().unwrap();
-                            state.global_velocity_damping += 1;
-                            info!(
-                                "global_velocity_damping: {}",
-                                state.global_velocity_damping
-                            );
+                            state.force_field = state.force_field.next();
+                            info!("force field: {:?}", state.force_field);
                         }
-                        "C" => {
-                            let mut state = self.state.lock().unwrap();
-                            state.global_velocity_damping -= 10;
-                            info!(
-                                "global_velocity_damping: {}",
-                                state.global_velocity_damping
-                            );
+                        "a" => {
+                            let fit = !self.window_config.fit_boundary;
+                            self.window_config.fit_boundary = fit;
+                            if let Some(viewport) = self.viewport.as_mut()
+                            {
+                                viewport.fit_boundary = fit;
+                                viewport.update_view();
+                                viewport.window.request_redraw();
+                            }
+                            info!("fit boundary: {fit}");
                         }
-                        "H" => {
+                        "v" => {
                             let mut state = self.state.lock().unwrap();
-                            state.global_velocity_damping += 10;
-                            info!(
-                                "global_velocity_damping: {}",
-                                state.global_velocity_damping
-                            );
+                            state.color_mode = state.color_mode.next();
+                            info!("color mode: {:?}", state.color_mode);
+                            if let Some(viewport) = self.viewport.as_ref()
+                            {
+                                viewport.window.request_redraw();
+                            }
+                        }
+                        "c" | "b" | "x" | "p" | "m" => {
+                            let command = match key.as_str() {
+                                "c" => Command::AddObstacle(Shape::Circle),
+                                "b" => Command::AddObstacle(Shape::Box),
+                                "x" => Command::RemoveObstacle,
+                                "p" => Command::Screenshot(timestamped(
+                                    "screenshot",
+                                    ".png",
+                                )),
+                                _ => Command::ToggleRecording(timestamped(
+                                    "recording",
+                                    "",
+                                )),
+                            };
+                            self.command_queue
+                                .lock()
+                                .unwrap()
+                                .push_back(command);
+                            // shown even when paused
+                            if let Some(viewport) = self.viewport.as_ref()
+                            {
+                                viewport.window.request_redraw();
+                            }
+                        }
+                        "o" => {
+                            if let Some(viewport) = self.viewport.as_mut()
+                            {
+                                viewport.overlay.visible =
+                                    !viewport.overlay.visible;
+                                viewport.window.request_redraw();
+                            }
                         }
                         _ => {}
                     },
                     Key::Named(key) => match key {
-                        NamedKey::ArrowUp => {
-                            let mut state = self.state.lock().unwrap();
-                            state.boundary_collision_factor += 1;
-                            info!(
-                                "boundary_collision_factor: {}",
-                                state.boundary_collision_factor
-                            );
-                        }
-                        NamedKey::ArrowDown => {
-                            let mut state = self.state.lock().unwrap();
-                            state.boundary_collision_factor = state
-                                .boundary_collision_factor
-                                .saturating_sub(1);
-                            info!(
-                                "boundary_collision_factor: {}",
-                                state.boundary_collision_factor
-                            );
-                        }
                         NamedKey::ArrowRight => {
                             if let Some(viewport) = self.viewport.as_ref()
                             {
+                                self.step_requested = true;
                                 viewport.window.request_redraw();
                                 info!("requesting new frame");
                             }
                         }
                         NamedKey::Space => {
                             self.paused = !self.paused;
-                            if !self.paused {
-                                if let Some(viewport) =
-                                    self.viewport.as_ref()
-                                {
+                            if let Some(viewport) = self.viewport.as_mut()
+                            {
+                                // time spent paused isn't caught up on
+                                viewport.renderer.timestep.reset();
+                                if !self.paused {
                                     viewport.window.request_redraw();
                                 }
                             }
@@ -2,15 +2,20 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, Context};
 use functional_utils::FunctionalUtils;
+use tracing::error;
 use wgpu::{
     Device, PresentMode, Surface, SurfaceConfiguration,
     TextureViewDescriptor,
 };
 use winit::{dpi::PhysicalSize, window::Window};
 
-use self::renderer::Renderer;
-use crate::wgpu_context::WgpuContext;
+use self::{
+    overlay::Overlay,
+    renderer::{param::BOUNDARY_SIZE, Renderer},
+};
+use crate::{config::WindowConfig, wgpu_context::WgpuContext};
 
+pub mod overlay;
 pub mod renderer;
 
 #[derive(Debug)]
@@ -19,12 +24,16 @@ pub struct Viewport {
     pub surface: Surface<'static>,
     pub config: SurfaceConfiguration,
     pub renderer: Renderer,
+    pub overlay: Overlay,
+    /// Grow the simulated area to the shape of the window
+    pub fit_boundary: bool,
 }
 
 impl Viewport {
     pub fn new(
         window: Arc<Window>,
         ctx: &WgpuContext,
+        window_config: &WindowConfig,
         build_renderer: impl FnOnce(&WgpuContext, &Surface) -> Renderer,
     ) -> anyhow::Result<Self> {
         let surface = ctx
@@ -39,17 +48,31 @@ impl Viewport {
                 size.height.max(1),
             )
             .ok_or(anyhow!("failed to get default surface config"))?;
-        config.present_mode = PresentMode::Immediate;
+        config.present_mode = if window_config.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::Immediate
+        };
 
         let renderer = build_renderer(ctx, &surface);
+        let overlay = Overlay::new(
+            ctx,
+            config.format,
+            renderer.input_state.clone(),
+            renderer.command_queue.clone(),
+            renderer.spawn_config,
+        );
 
-        Self {
+        let viewport = Self {
             window,
             surface,
             config,
             renderer,
-        }
-        .into_ok()
+            overlay,
+            fit_boundary: window_config.fit_boundary,
+        };
+        viewport.update_view();
+        viewport.into_ok()
     }
 
     pub fn resize(&mut self, device: &Device, size: PhysicalSize<u32>) {
@@ -57,9 +80,33 @@ impl Viewport {
         self.config.height = size.height.max(1);
 
         self.surface.configure(device, &self.config);
+        self.update_view();
     }
 
-    pub fn render(&self, ctx: &WgpuContext) -> anyhow::Result<()> {
+    /// Set the boundary and the view for the window's aspect ratio
+    pub fn update_view(&self) {
+        let aspect = self.config.width as f32 / self.config.height as f32;
+        let mut state = self.renderer.input_state.lock().unwrap();
+
+        state.boundary = if !self.fit_boundary {
+            [BOUNDARY_SIZE; 2]
+        } else if aspect > 1.0 {
+            [BOUNDARY_SIZE * aspect, BOUNDARY_SIZE]
+        } else {
+            [BOUNDARY_SIZE, BOUNDARY_SIZE / aspect]
+        };
+
+        // shrink the longer side of the window to keep the area's shape
+        let [width, height] = state.boundary;
+        let boundary_aspect = width / height;
+        state.view_scale = if aspect > boundary_aspect {
+            [boundary_aspect / aspect, 1.0]
+        } else {
+            [1.0, aspect / boundary_aspect]
+        };
+    }
+
+    pub fn render(&mut self, ctx: &WgpuContext) -> anyhow::Result<()> {
         let frame = self
             .surface
             .get_current_texture()
@@ -67,7 +114,13 @@ impl Viewport {
         let view =
             frame.texture.create_view(&TextureViewDescriptor::default());
 
+        let size = self.window.inner_size();
+        let format = self.config.format;
+        if let Err(err) = self.renderer.capture(ctx, format, size) {
+            error!("failed to capture frame: {err:?}");
+        }
         self.renderer.render(ctx, &view);
+        self.overlay.render(ctx, &self.window, &view, &mut self.renderer);
         frame.present();
 
         Ok(())
@@ -0,0 +1,565 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum as _;
+use egui::{
+    Context, Event, MouseWheelUnit, PointerButton, Pos2, RawInput, Rect,
+    Slider, ViewportId,
+};
+use egui_wgpu::ScreenDescriptor;
+use wgpu::{
+    CommandEncoderDescriptor, LoadOp, Operations,
+    RenderPassColorAttachment, RenderPassDescriptor, StoreOp,
+    TextureFormat, TextureView,
+};
+use winit::{
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    window::Window,
+};
+
+use super::renderer::{
+    capture::timestamped,
+    command::Command,
+    gpu_timer::GpuTimer,
+    param::{ColorMode, ForceField, Model, Param},
+    point::{Shape, SpawnConfig, Velocity},
+    timestep::Timestep,
+    Renderer,
+};
+use crate::wgpu_context::WgpuContext;
+
+/// Parameter panel drawn over the simulation
+pub struct Overlay {
+    pub visible: bool,
+
+    ctx: Context,
+    renderer: egui_wgpu::Renderer,
+    start: Instant,
+    /// Input collected since the last frame
+    events: Vec<Event>,
+    pointer_pos: Pos2,
+
+    state: Arc<Mutex<Param>>,
+    command_queue: Arc<Mutex<VecDeque<Command>>>,
+    /// Applied with the respawn button
+    spawn_config: SpawnConfig,
+    rates: Rates,
+}
+
+/// How often the measured rates are updated
+const RATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Ticks and frames per second, averaged over [`RATE_INTERVAL`]
+#[derive(Debug)]
+struct Rates {
+    since: Instant,
+    tick_count: u64,
+    frames: u32,
+
+    ticks_per_sec: f64,
+    frames_per_sec: f64,
+}
+
+impl Rates {
+    fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            tick_count: 0,
+            frames: 0,
+
+            ticks_per_sec: 0.0,
+            frames_per_sec: 0.0,
+        }
+    }
+
+    fn update(&mut self, tick_count: u64) {
+        self.frames += 1;
+
+        let elapsed = self.since.elapsed();
+        if elapsed < RATE_INTERVAL {
+            return;
+        }
+        let secs = elapsed.as_secs_f64();
+        self.ticks_per_sec =
+            tick_count.saturating_sub(self.tick_count) as f64 / secs;
+        self.frames_per_sec = self.frames as f64 / secs;
+
+        self.since = Instant::now();
+        self.tick_count = tick_count;
+        self.frames = 0;
+    }
+}
+
+impl std::fmt::Debug for Overlay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Overlay")
+            .field("visible", &self.visible)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Overlay {
+    pub fn new(
+        ctx: &WgpuContext,
+        format: TextureFormat,
+        state: Arc<Mutex<Param>>,
+        command_queue: Arc<Mutex<VecDeque<Command>>>,
+        spawn_config: SpawnConfig,
+    ) -> Self {
+        Self {
+            visible: true,
+
+            ctx: Context::default(),
+            renderer: egui_wgpu::Renderer::new(
+                &ctx.device,
+                format,
+                None,
+                1,
+            ),
+            start: Instant::now(),
+            events: vec![],
+            pointer_pos: Pos2::ZERO,
+
+            state,
+            command_queue,
+            spawn_config,
+            rates: Rates::new(),
+        }
+    }
+
+    /// Return: the event is for the overlay, hide it from the simulation
+    pub fn on_window_event(
+        &mut self,
+        window: &Window,
+        event: &WindowEvent,
+    ) -> bool {
+        if !self.visible {
+            return false;
+        }
+        let scale = window.scale_factor() as f32;
+
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.pointer_pos = Pos2::new(
+                    position.x as f32 / scale,
+                    position.y as f32 / scale,
+                );
+                self.events.push(Event::PointerMoved(self.pointer_pos));
+                // the simulation still tracks the mouse
+                false
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.events.push(Event::PointerGone);
+                false
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => PointerButton::Primary,
+                    MouseButton::Right => PointerButton::Secondary,
+                    MouseButton::Middle => PointerButton::Middle,
+                    _ => return false,
+                };
+                self.events.push(Event::PointerButton {
+                    pos: self.pointer_pos,
+                    button,
+                    pressed: *state == ElementState::Pressed,
+                    modifiers: Default::default(),
+                });
+                // releases always reach the simulation, so none get stuck
+                *state == ElementState::Pressed
+                    && self.ctx.is_pointer_over_area()
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (unit, delta) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        (MouseWheelUnit::Line, egui::vec2(*x, *y))
+                    }
+                    MouseScrollDelta::PixelDelta(delta) => (
+                        MouseWheelUnit::Point,
+                        egui::vec2(delta.x as f32, delta.y as f32) / scale,
+                    ),
+                };
+                self.events.push(Event::MouseWheel {
+                    unit,
+                    delta,
+                    modifiers: Default::default(),
+                });
+                self.ctx.is_pointer_over_area()
+            }
+            _ => false,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        ctx: &WgpuContext,
+        window: &Window,
+        view: &TextureView,
+        renderer: &mut Renderer,
+    ) {
+        // keep sampling while hidden, the rates are right when shown
+        self.rates.update(renderer.tick_count);
+        if !self.visible {
+            return;
+        }
+
+        let size = window.inner_size();
+        let scale = window.scale_factor() as f32;
+        let mut raw_input = RawInput {
+            screen_rect: Some(Rect::from_min_size(
+                Pos2::ZERO,
+                egui::vec2(size.width as f32, size.height as f32) / scale,
+            )),
+            time: Some(self.start.elapsed().as_secs_f64()),
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        };
+        raw_input
+            .viewports
+            .entry(ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(scale);
+
+        // cheap, shares the state
+        let egui_ctx = self.ctx.clone();
+        let output = egui_ctx.run(raw_input, |egui_ctx| {
+            egui::Window::new("param")
+                .default_pos([10.0, 10.0])
+                .show(egui_ctx, |ui| {
+                    self.param_ui(ui);
+                    timestep_ui(ui, &mut renderer.timestep);
+                    ui.separator();
+                    self.spawn_ui(ui);
+                });
+            egui::Window::new("stats")
+                .default_pos([10.0, 420.0])
+                .show(egui_ctx, |ui| {
+                    self.stats_ui(ui, renderer);
+                });
+        });
+        let paint_jobs =
+            self.ctx.tessellate(output.shapes, output.pixels_per_point);
+        let screen = ScreenDescriptor {
+            size_in_pixels: [size.width, size.height],
+            pixels_per_point: output.pixels_per_point,
+        };
+
+        for (id, delta) in &output.textures_delta.set {
+            self.renderer.update_texture(
+                &ctx.device,
+                &ctx.queue,
+                *id,
+                delta,
+            );
+        }
+
+        let mut encoder =
+            ctx.device
+                .create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("overlay"),
+                });
+        let mut cmds = self.renderer.update_buffers(
+            &ctx.device,
+            &ctx.queue,
+            &mut encoder,
+            &paint_jobs,
+            &screen,
+        );
+
+        {
+            let mut rpass =
+                encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("overlay render pass"),
+                    color_attachments: &[Some(
+                        RenderPassColorAttachment {
+                            view,
+                            resolve_target: None,
+                            ops: Operations {
+                                load: LoadOp::Load,
+                                store: StoreOp::Store,
+                            },
+                        },
+                    )],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+            self.renderer.render(&mut rpass, &paint_jobs, &screen);
+        }
+
+        cmds.push(encoder.finish());
+        ctx.queue.submit(cmds);
+
+        for id in &output.textures_delta.free {
+            self.renderer.free_texture(id);
+        }
+    }
+
+    fn param_ui(&self, ui: &mut egui::Ui) {
+        let mut state = self.state.lock().unwrap();
+
+        ui.add(
+            Slider::new(&mut state.time_delta, 0.0001..=0.005)
+                .logarithmic(true)
+                .text("time delta per tick"),
+        );
+        ui.add(
+            Slider::new(&mut state.global_velocity_damping, 9000..=10000)
+                .text("velocity damping (1/10000)"),
+        );
+        ui.add(
+            Slider::new(&mut state.boundary_collision_factor, 0..=200)
+                .text("boundary collision factor (%)"),
+        );
+        egui::ComboBox::from_label("model")
+            .selected_text(format!("{:?}", state.model))
+            .show_ui(ui, |ui| {
+                for model in Model::ALL {
+                    ui.selectable_value(
+                        &mut state.model,
+                        model,
+                        format!("{model:?}"),
+                    );
+                }
+            });
+        match state.model {
+            Model::Repulsion => {
+                ui.add(
+                    Slider::new(
+                        &mut state.repulsion_distance,
+                        10.0..=1000.0,
+                    )
+                    .text("repulsion distance"),
+                );
+                ui.add(
+                    Slider::new(
+                        &mut state.repulsion_strength,
+                        0.0..=200000.0,
+                    )
+                    .text("repulsion strength"),
+                );
+            }
+            Model::Sph => {
+                ui.add(
+                    Slider::new(&mut state.rest_density, 1.0..=10.0)
+                        .text("rest density"),
+                );
+                ui.add(
+                    Slider::new(&mut state.stiffness, 1000.0..=2000000.0)
+                        .logarithmic(true)
+                        .text("stiffness"),
+                );
+                ui.add(
+                    Slider::new(&mut state.viscosity, 0.0..=500.0)
+                        .text("viscosity"),
+                );
+            }
+        }
+        ui.add(
+            Slider::new(&mut state.gravity[0], -1000.0..=1000.0)
+                .text("gravity x"),
+        );
+        ui.add(
+            Slider::new(&mut state.gravity[1], -1000.0..=1000.0)
+                .text("gravity y"),
+        );
+        egui::ComboBox::from_label("force field")
+            .selected_text(format!("{:?}", state.force_field))
+            .show_ui(ui, |ui| {
+                for field in ForceField::ALL {
+                    ui.selectable_value(
+                        &mut state.force_field,
+                        field,
+                        format!("{field:?}"),
+                    );
+                }
+            });
+        ui.add(
+            Slider::new(&mut state.field_strength, 0.0..=5000.0)
+                .text("field strength"),
+        );
+        ui.add(
+            Slider::new(&mut state.field_frequency, 0.0..=5.0)
+                .text("field frequency (Hz)"),
+        );
+        egui::ComboBox::from_label("color by")
+            .selected_text(format!("{:?}", state.color_mode))
+            .show_ui(ui, |ui| {
+                for mode in ColorMode::ALL {
+                    ui.selectable_value(
+                        &mut state.color_mode,
+                        mode,
+                        format!("{mode:?}"),
+                    );
+                }
+            });
+        ui.add(
+            Slider::new(&mut state.color_scale, 0.1..=10.0)
+                .logarithmic(true)
+                .text("color scale"),
+        );
+
+        ui.horizontal(|ui| {
+            if ui.button("reset points").clicked() {
+                self.command_queue
+                    .lock()
+                    .unwrap()
+                    .push_back(Command::Reset);
+            }
+            if ui.button("clear obstacles").clicked() {
+                self.command_queue
+                    .lock()
+                    .unwrap()
+                    .push_back(Command::ClearObstacles);
+            }
+            if ui.button("default params").clicked() {
+                *state = state.defaults();
+            }
+            if ui.button("fluid params").clicked() {
+                *state = state.fluid();
+            }
+        });
+        ui.label("o: hide, r: reset, space: pause");
+        ui.label("g: toggle gravity, f: next force field, v: color by");
+        ui.label("p: screenshot, m: start/stop recording");
+        ui.label("a: fit the area to the window");
+        ui.label("c: circle, b: box, x: remove obstacle at the mouse");
+    }
+
+    fn spawn_ui(&mut self, ui: &mut egui::Ui) {
+        let config = &mut self.spawn_config;
+
+        egui::ComboBox::from_label("shape")
+            .selected_text(format!("{:?}", config.shape))
+            .show_ui(ui, |ui| {
+                for shape in Shape::value_variants() {
+                    ui.selectable_value(
+                        &mut config.shape,
+                        *shape,
+                        format!("{shape:?}"),
+                    );
+                }
+            });
+        egui::ComboBox::from_label("velocity")
+            .selected_text(format!("{:?}", config.velocity))
+            .show_ui(ui, |ui| {
+                for velocity in Velocity::value_variants() {
+                    ui.selectable_value(
+                        &mut config.velocity,
+                        *velocity,
+                        format!("{velocity:?}"),
+                    );
+                }
+            });
+        ui.add(
+            Slider::new(&mut config.count, 1..=1 << 20)
+                .logarithmic(true)
+                .text("count"),
+        );
+        ui.add(
+            Slider::new(&mut config.speed, 0.0..=10000.0).text("speed"),
+        );
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut config.seed));
+            ui.label("seed");
+        });
+
+        if ui.button("respawn").clicked() {
+            self.command_queue
+                .lock()
+                .unwrap()
+                .push_back(Command::Respawn(*config));
+        }
+    }
+
+    fn stats_ui(&self, ui: &mut egui::Ui, renderer: &Renderer) {
+        ui.horizontal(|ui| {
+            let mut command = None;
+            if ui.button("screenshot").clicked() {
+                command = Some(Command::Screenshot(timestamped(
+                    "screenshot",
+                    ".png",
+                )));
+            }
+            let recording = renderer.capture.recording.is_some();
+            let label = if recording {
+                "stop recording"
+            } else {
+                "record"
+            };
+            if ui.button(label).clicked() {
+                command = Some(Command::ToggleRecording(timestamped(
+                    "recording",
+                    "",
+                )));
+            }
+            if let Some(command) = command {
+                self.command_queue.lock().unwrap().push_back(command);
+            }
+        });
+        if let Some(recording) = &renderer.capture.recording {
+            ui.label(format!("recorded frames: {}", recording.frame));
+        }
+        if let Some(player) = &renderer.player {
+            ui.label(if player.is_finished() {
+                "replay finished"
+            } else {
+                "replaying, input is ignored"
+            });
+        }
+        if renderer.recorder.is_some() {
+            ui.label("recording the replay");
+        }
+
+        ui.label(format!("particles: {}", renderer.points.len()));
+        ui.label(format!(
+            "ticks/s: {:.0}, frames/s: {:.0}",
+            self.rates.ticks_per_sec, self.rates.frames_per_sec
+        ));
+        ui.separator();
+        timer_ui(ui, renderer.timer.as_ref());
+    }
+}
+
+/// GPU time of each pass, of one measured tick
+fn timer_ui(ui: &mut egui::Ui, timer: Option<&GpuTimer>) {
+    let Some(timer) = timer else {
+        ui.label("timestamp queries unsupported");
+        return;
+    };
+    let Some(times) = timer.last else {
+        ui.label("waiting for timestamps");
+        return;
+    };
+
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    egui::Grid::new("pass times").show(ui, |ui| {
+        for (name, duration) in [
+            ("hash", times.hash),
+            ("sort", times.sort),
+            ("index", times.index),
+            ("update", times.update),
+            ("render", times.render),
+        ] {
+            ui.label(name);
+            ui.label(format!("{:.3} ms", ms(duration)));
+            ui.end_row();
+        }
+    });
+}
+
+fn timestep_ui(ui: &mut egui::Ui, timestep: &mut Timestep) {
+    ui.add(
+        Slider::new(&mut timestep.tick_rate, 1.0..=20000.0)
+            .logarithmic(true)
+            .text("ticks per second"),
+    );
+    ui.add(
+        Slider::new(&mut timestep.max_ticks_per_frame, 1..=256)
+            .logarithmic(true)
+            .text("max ticks per frame"),
+    );
+}
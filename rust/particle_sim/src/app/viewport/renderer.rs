@@ -1,55 +1,93 @@
 use std::{
     collections::VecDeque,
-    mem::size_of,
     sync::{Arc, Mutex},
-    time::Instant,
 };
 
 use bytemuck::cast_slice;
-use tracing::info;
+use itertools::Itertools as _;
+use tracing::{error, info};
 use wgpu::{
     include_wgsl,
     util::{BufferInitDescriptor, DeviceExt},
-    vertex_attr_array, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
-    BufferAddress, BufferBindingType, BufferDescriptor, BufferUsages,
-    Color, ColorTargetState, ColorWrites, CommandEncoderDescriptor,
-    ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor,
-    Face, LoadOp, Operations, PipelineLayoutDescriptor,
-    PushConstantRange, RenderPassColorAttachment, RenderPassDescriptor,
-    RenderPipeline, ShaderStages, StoreOp, Surface, TextureView,
-    VertexBufferLayout, VertexStepMode,
+    BufferBindingType, BufferDescriptor, BufferUsages, Color,
+    CommandEncoderDescriptor, ComputePassDescriptor,
+    ComputePassTimestampWrites, Device, LoadOp, Operations,
+    RenderPassColorAttachment, RenderPassDescriptor, ShaderStages,
+    StoreOp, Surface, Extent3d, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureView,
 };
+use winit::dpi::PhysicalSize;
 use wgpu_bitonic_sort::BitonicSorter;
 
-use self::{command::Command, param::Param, point::Point};
+use self::{
+    capture::Capture,
+    command::Command,
+    gpu_timer::{GpuTimer, Pass},
+    obstacle::{Obstacle, MAX_OBSTACLES},
+    param::{Model, Param},
+    pipelines::Pipelines,
+    point::{Point, SpawnConfig},
+    replay::{Player, Recorder},
+    shader::{ShaderWatcher, SHADER_PATH},
+    timestep::Timestep,
+};
 use crate::wgpu_context::WgpuContext;
 
+pub mod capture;
 pub mod command;
+pub mod gpu_timer;
+pub mod obstacle;
 pub mod param;
+pub mod pipelines;
 pub mod point;
+pub mod replay;
+pub mod shader;
+pub mod timestep;
 
 #[derive(Debug)]
 pub struct Renderer {
-    pub last_update: Instant,
+    pub timestep: Timestep,
+    /// Ticks run since the start
+    pub tick_count: u64,
+    /// Simulated seconds, for the force field
+    pub time: f32,
+    /// `None` if timestamp queries are unsupported
+    pub timer: Option<GpuTimer>,
+    pub capture: Capture,
+    pub recorder: Option<Recorder>,
+    /// Replaying, the live input is ignored
+    pub player: Option<Player>,
 
     pub input_state: Arc<Mutex<Param>>,
     pub command_queue: Arc<Mutex<VecDeque<Command>>>,
 
+    pub spawn_config: SpawnConfig,
     pub points: Vec<Point>,
-    pub points_buffer: Buffer,
-    pub points_out_buffer: Buffer,
+    /// Swapping roles each tick, one is read and the other written
+    pub points_buffers: [Buffer; 2],
+    /// Index of the buffer with the latest points
+    pub parity: usize,
 
     pub points_hash_data_buffer: Buffer,
     pub points_hash_index_buffer: Buffer,
+    /// Neighbour count of each point, for coloring
+    pub points_density_buffer: Buffer,
+
+    pub obstacles: Vec<Obstacle>,
+    /// [`MAX_OBSTACLES`] slots, the unused ones empty
+    pub obstacles_buffer: Buffer,
 
-    pub compute_bind_group: BindGroup,
+    pub compute_bind_group_layout: BindGroupLayout,
+    /// Reading from the buffer of the same index
+    pub compute_bind_groups: [BindGroup; 2],
 
-    pub calc_hash_data_pipeline: ComputePipeline,
     pub hash_data_sorter: BitonicSorter,
-    pub calc_hash_index_pipeline: ComputePipeline,
-    pub compute_pipeline: ComputePipeline,
-    pub render_pipeline: RenderPipeline,
+    pub pipelines: Pipelines,
+    /// Format the render pipelines are built for
+    pub swapchain_format: TextureFormat,
+    pub shader_watcher: ShaderWatcher,
 }
 
 impl Renderer {
@@ -58,47 +96,21 @@ impl Renderer {
         surface: &Surface,
         input_state: Arc<Mutex<Param>>,
         command_queue: Arc<Mutex<VecDeque<Command>>>,
+        spawn_config: &SpawnConfig,
     ) -> Self {
         let WgpuContext {
             adapter, device, ..
         } = &ctx;
 
         // data
-        let points = Point::gen();
-
-        let points_buffer =
-            device.create_buffer_init(&BufferInitDescriptor {
-                label: Some("points_buffer"),
-                contents: cast_slice(&points),
-                usage: BufferUsages::STORAGE
-                    | BufferUsages::VERTEX
-                    | BufferUsages::COPY_DST,
-            });
+        let points = Point::gen(spawn_config);
 
-        let points_out_buffer =
-            device.create_buffer_init(&BufferInitDescriptor {
-                label: Some("points_out_buffer"),
-                contents: cast_slice(&points),
-                usage: BufferUsages::STORAGE
-                    | BufferUsages::VERTEX
-                    | BufferUsages::COPY_SRC,
-            });
-
-        let points_hash_data_buffer =
-            device.create_buffer(&BufferDescriptor {
-                label: Some("points_hash_data_buffer"),
-                size: (4 + 4) * points.len() as u64,
-                usage: BufferUsages::STORAGE,
-                mapped_at_creation: false,
-            });
-
-        let points_hash_index_buffer =
-            device.create_buffer(&BufferDescriptor {
-                label: Some("points_hash_index_buffer"),
-                size: 4 * points.len() as u64,
-                usage: BufferUsages::STORAGE,
-                mapped_at_creation: false,
-            });
+        let (
+            points_buffers,
+            points_hash_data_buffer,
+            points_hash_index_buffer,
+            points_density_buffer,
+        ) = Self::create_buffers(device, &points);
 
         let compute_bind_group_layout =
             device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -152,123 +164,64 @@ impl Renderer {
                         },
                         count: None,
                     },
-                ],
-            });
-
-        // stats out
-        let compute_bind_group =
-            device.create_bind_group(&BindGroupDescriptor {
-                label: Some("compute_bind_group"),
-                layout: &compute_bind_group_layout,
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: points_buffer.as_entire_binding(),
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: points_out_buffer.as_entire_binding(),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: points_hash_data_buffer
-                            .as_entire_binding(),
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage {
+                                read_only: true,
+                            },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    BindGroupEntry {
-                        binding: 3,
-                        resource: points_hash_index_buffer
-                            .as_entire_binding(),
+                    BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage {
+                                read_only: false,
+                            },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
                 ],
             });
 
-        // pipeline
-        let shader = device
-            .create_shader_module(include_wgsl!("../../../shader.wgsl"));
-
-        // compute pipeline
-        let compute_pipeline_layout =
-            device.create_pipeline_layout(&PipelineLayoutDescriptor {
-                label: Some("compute layout"),
-                bind_group_layouts: &[&compute_bind_group_layout],
-                push_constant_ranges: &[PushConstantRange {
-                    stages: ShaderStages::COMPUTE,
-                    range: 0..size_of::<Param>() as u32,
-                }],
-            });
-
-        let calc_hash_data_pipeline =
-            device.create_compute_pipeline(&ComputePipelineDescriptor {
-                label: Some("calc hash data pipeline"),
-                layout: Some(&compute_pipeline_layout),
-                module: &shader,
-                entry_point: "calc_hash_data",
-                compilation_options: Default::default(),
-            });
-
-        let calc_hash_index_pipeline =
-            device.create_compute_pipeline(&ComputePipelineDescriptor {
-                label: Some("calc hash index pipeline"),
-                layout: Some(&compute_pipeline_layout),
-                module: &shader,
-                entry_point: "calc_hash_index",
-                compilation_options: Default::default(),
+        let obstacles_buffer =
+            device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("obstacles_buffer"),
+                contents: cast_slice(&[Obstacle::NONE; MAX_OBSTACLES]),
+                usage: BufferUsages::STORAGE
+                    | BufferUsages::VERTEX
+                    | BufferUsages::COPY_DST,
             });
 
-        let compute_pipeline =
-            device.create_compute_pipeline(&ComputePipelineDescriptor {
-                label: Some("compute pipeline"),
-                layout: Some(&compute_pipeline_layout),
-                module: &shader,
-                entry_point: "cs_main",
-                compilation_options: Default::default(),
-            });
+        let compute_bind_groups = Self::create_compute_bind_groups(
+            device,
+            &compute_bind_group_layout,
+            &points_buffers,
+            [
+                &points_hash_data_buffer,
+                &points_hash_index_buffer,
+                &points_density_buffer,
+            ],
+            &obstacles_buffer,
+        );
 
-        // render pipeline
+        // pipeline
         let swapchain_capabilities = surface.get_capabilities(adapter);
         let swapchain_format = swapchain_capabilities.formats[0];
-
-        let instance_buffer_layout = VertexBufferLayout {
-            array_stride: size_of::<Point>() as BufferAddress,
-            step_mode: VertexStepMode::Instance,
-            attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2],
-        };
-
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&PipelineLayoutDescriptor {
-                label: Some("render layout"),
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
-            });
-
-        let render_pipeline = device.create_render_pipeline(
-            &wgpu::RenderPipelineDescriptor {
-                label: Some("render pipeline"),
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: "vs_main",
-                    buffers: &[instance_buffer_layout],
-                    compilation_options: Default::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: "fs_main",
-                    compilation_options: Default::default(),
-                    targets: &[Some(ColorTargetState {
-                        format: swapchain_format,
-                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                        write_mask: ColorWrites::COLOR,
-                    })],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    cull_mode: Some(Face::Back),
-                    ..Default::default()
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            },
+        let shader = device
+            .create_shader_module(include_wgsl!("../../../shader.wgsl"));
+        let pipelines = Pipelines::new(
+            device,
+            &shader,
+            &compute_bind_group_layout,
+            swapchain_format,
         );
 
         let hash_data_sorter = BitonicSorter::new(
@@ -279,55 +232,345 @@ impl Renderer {
         );
 
         Self {
-            last_update: Instant::now(),
+            timestep: Timestep::default(),
+            tick_count: 0,
+            time: 0.0,
+            timer: GpuTimer::new(ctx),
+            capture: Capture::default(),
+            recorder: None,
+            player: None,
 
             input_state,
             command_queue,
 
+            spawn_config: *spawn_config,
             points,
-            points_buffer,
-            points_out_buffer,
+            points_buffers,
+            parity: 0,
 
             points_hash_data_buffer,
             points_hash_index_buffer,
+            points_density_buffer,
 
-            compute_bind_group,
+            obstacles: vec![],
+            obstacles_buffer,
+
+            compute_bind_group_layout,
+            compute_bind_groups,
 
-            calc_hash_data_pipeline,
             hash_data_sorter,
-            calc_hash_index_pipeline,
-            compute_pipeline,
-            render_pipeline,
+            pipelines,
+            swapchain_format,
+            shader_watcher: ShaderWatcher::new(SHADER_PATH),
         }
     }
 
+    /// Return: the points buffers, hash data, hash index
+    /// and density buffer
+    fn create_buffers(
+        device: &Device,
+        points: &[Point],
+    ) -> ([Buffer; 2], Buffer, Buffer, Buffer) {
+        let points_buffers = ["points_buffer_a", "points_buffer_b"].map(
+            |label| {
+                device.create_buffer_init(&BufferInitDescriptor {
+                    label: Some(label),
+                    contents: cast_slice(points),
+                    usage: BufferUsages::STORAGE
+                        | BufferUsages::VERTEX
+                        | BufferUsages::COPY_DST,
+                })
+            },
+        );
+
+        let points_hash_data_buffer =
+            device.create_buffer(&BufferDescriptor {
+                label: Some("points_hash_data_buffer"),
+                size: (4 + 4) * points.len() as u64,
+                usage: BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+
+        let points_hash_index_buffer =
+            device.create_buffer(&BufferDescriptor {
+                label: Some("points_hash_index_buffer"),
+                size: 4 * points.len() as u64,
+                usage: BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+
+        let points_density_buffer =
+            device.create_buffer(&BufferDescriptor {
+                label: Some("points_density_buffer"),
+                size: 4 * points.len() as u64,
+                usage: BufferUsages::STORAGE | BufferUsages::VERTEX,
+                mapped_at_creation: false,
+            });
+
+        (
+            points_buffers,
+            points_hash_data_buffer,
+            points_hash_index_buffer,
+            points_density_buffer,
+        )
+    }
+
+    /// One bind group for each direction between the points buffers
+    fn create_compute_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        points_buffers: &[Buffer; 2],
+        // hash data, hash index and density
+        per_point_buffers: [&Buffer; 3],
+        obstacles_buffer: &Buffer,
+    ) -> [BindGroup; 2] {
+        let [a, b] = points_buffers;
+        let [hash_data_buffer, hash_index_buffer, density_buffer] =
+            per_point_buffers;
+        [[a, b], [b, a]].map(|[input, output]| {
+            Self::create_compute_bind_group(
+                device,
+                layout,
+                [
+                    input,
+                    output,
+                    hash_data_buffer,
+                    hash_index_buffer,
+                    obstacles_buffer,
+                    density_buffer,
+                ],
+            )
+        })
+    }
+
+    /// Buffers in the order of their bindings
+    fn create_compute_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffers: [&Buffer; 6],
+    ) -> BindGroup {
+        let entries = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect_vec();
+
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("compute_bind_group"),
+            layout,
+            entries: &entries,
+        })
+    }
+
+    /// Replace the points, recreating the buffers as the count may change
+    fn respawn(&mut self, device: &Device, config: &SpawnConfig) {
+        self.spawn_config = *config;
+        self.points = Point::gen(config);
+        (
+            self.points_buffers,
+            self.points_hash_data_buffer,
+            self.points_hash_index_buffer,
+            self.points_density_buffer,
+        ) = Self::create_buffers(device, &self.points);
+        self.parity = 0;
+
+        self.compute_bind_groups = Self::create_compute_bind_groups(
+            device,
+            &self.compute_bind_group_layout,
+            &self.points_buffers,
+            [
+                &self.points_hash_data_buffer,
+                &self.points_hash_index_buffer,
+                &self.points_density_buffer,
+            ],
+            &self.obstacles_buffer,
+        );
+        self.hash_data_sorter
+            .change_buffer(device, &self.points_hash_data_buffer);
+    }
+
+    /// Rebuild the pipelines if `shader.wgsl` changed, keeping the old
+    /// ones if the new source is invalid
+    pub fn reload_shader(&mut self, ctx: &WgpuContext) {
+        let Some(src) = self.shader_watcher.poll() else {
+            return;
+        };
+        match Pipelines::try_from_source(
+            &ctx.device,
+            &src,
+            &self.compute_bind_group_layout,
+            self.swapchain_format,
+        ) {
+            Ok(pipelines) => {
+                info!("reloaded {SHADER_PATH}");
+                self.pipelines = pipelines;
+            }
+            Err(err) => {
+                error!("kept the previous shader, {SHADER_PATH}: {err}")
+            }
+        }
+    }
+
+    /// Apply commands and run the ticks due since the last update
     pub fn update(&mut self, ctx: &WgpuContext) {
-        // time delta
-        // let time_delta = self.last_update.elapsed().as_secs_f32();
-        self.last_update = Instant::now();
-
-        // command
-        let mut cmd_queue = self.command_queue.lock().unwrap();
-
-        while let Some(command) = cmd_queue.pop_front() {
-            info!("on command: {command:?}");
-            match command {
-                Command::Reset => {
-                    ctx.queue.write_buffer(
-                        &self.points_buffer,
-                        0,
-                        cast_slice(&self.points),
-                    );
+        self.apply_commands(ctx);
+        self.collect_timer(ctx);
+
+        let ticks = self.timestep.advance();
+        for idx in 0..ticks {
+            // only the last one, its output is what gets rendered
+            if idx + 1 == ticks {
+                self.start_timer();
+            }
+            self.tick(ctx);
+        }
+    }
+
+    /// Apply commands and run a single tick, for stepping while paused
+    pub fn step(&mut self, ctx: &WgpuContext) {
+        self.apply_commands(ctx);
+        self.collect_timer(ctx);
+        self.start_timer();
+        self.tick(ctx);
+    }
+
+    fn collect_timer(&mut self, ctx: &WgpuContext) {
+        if let Some(timer) = &mut self.timer {
+            timer.collect(&ctx.device);
+        }
+    }
+
+    fn start_timer(&mut self) {
+        if let Some(timer) = &mut self.timer {
+            timer.start();
+        }
+    }
+
+    pub fn apply_commands(&mut self, ctx: &WgpuContext) {
+        let commands =
+            std::mem::take(&mut *self.command_queue.lock().unwrap());
+
+        for command in commands {
+            if command.is_input() {
+                if self.player.is_some() {
+                    info!("replaying, ignored command: {command:?}");
+                    continue;
+                }
+                // the mouse position is part of some commands
+                let param = self.param();
+                self.record(|recorder, tick| {
+                    recorder.param(tick, &param)?;
+                    recorder.command(tick, &command)
+                });
+            }
+            self.apply_command(ctx, command);
+        }
+    }
+
+    fn apply_command(&mut self, ctx: &WgpuContext, command: Command) {
+        info!("on command: {command:?}");
+        match command {
+            Command::Reset => {
+                ctx.queue.write_buffer(
+                    &self.points_buffers[self.parity],
+                    0,
+                    cast_slice(&self.points),
+                );
+            }
+            Command::Respawn(config) => {
+                self.respawn(&ctx.device, &config);
+            }
+            Command::AddObstacle(shape) => {
+                if self.obstacles.len() < MAX_OBSTACLES {
+                    let obstacle =
+                        Obstacle::from_shape(shape, self.mouse_pos());
+                    self.obstacles.push(obstacle);
+                    self.write_obstacles(ctx);
+                } else {
+                    info!("at most {MAX_OBSTACLES} obstacles");
                 }
             }
+            Command::RemoveObstacle => {
+                let pos = self.mouse_pos();
+                if let Some(idx) = self
+                    .obstacles
+                    .iter()
+                    .rposition(|obstacle| obstacle.contains(pos))
+                {
+                    self.obstacles.remove(idx);
+                    self.write_obstacles(ctx);
+                }
+            }
+            Command::ClearObstacles => {
+                self.obstacles.clear();
+                self.write_obstacles(ctx);
+            }
+            Command::Screenshot(path) => {
+                self.capture.screenshot = Some(path);
+            }
+            Command::ToggleRecording(dir) => {
+                if let Err(err) = self.capture.toggle_recording(&dir) {
+                    error!("failed to start recording: {err:?}");
+                }
+            }
+        }
+    }
+
+    /// Write to the replay file if recording, stopping on errors
+    fn record(
+        &mut self,
+        write: impl FnOnce(&mut Recorder, u64) -> anyhow::Result<()>,
+    ) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        if let Err(err) = write(recorder, self.tick_count) {
+            error!("stopped recording the replay: {err:?}");
+            self.recorder = None;
+        }
+    }
+
+    /// Input of the current tick, from the replay if there is one
+    pub fn param(&self) -> Param {
+        match &self.player {
+            Some(player) => player.param,
+            None => *self.input_state.lock().unwrap(),
+        }
+    }
+
+    /// Mouse position in simulation space
+    fn mouse_pos(&self) -> [f32; 2] {
+        let param = self.param();
+        param.screen_to_world(param.mouse_pos)
+    }
+
+    fn write_obstacles(&self, ctx: &WgpuContext) {
+        let mut slots = [Obstacle::NONE; MAX_OBSTACLES];
+        slots[..self.obstacles.len()].copy_from_slice(&self.obstacles);
+        ctx.queue
+            .write_buffer(&self.obstacles_buffer, 0, cast_slice(&slots));
+    }
+
+    /// Advance the simulation by `time_delta`
+    fn tick(&mut self, ctx: &WgpuContext) {
+        while let Some(command) = self
+            .player
+            .as_mut()
+            .and_then(|player| player.next_command(self.tick_count))
+        {
+            self.apply_command(ctx, command);
         }
 
         // input state & param
-        let state = self.input_state.lock().unwrap();
-        let param = [Param {
-            time_delta: 1f32 / 1000.0,
-            ..*state
-        }];
+        let mut param = self.param();
+        self.record(|recorder, tick| recorder.param(tick, &param));
+        self.tick_count += 1;
+        param.time = self.time;
+        self.time += param.time_delta;
+        let param = [param];
         let param_slice = cast_slice::<_, u8>(&param);
 
         // dimensions
@@ -336,72 +579,117 @@ impl Renderer {
         let y = ((size / 65535.0).ceil() as u32).min(65535);
         let z = (size / 65535.0 / 65535.0).ceil() as u32;
 
-        // hash data
-        let hash_data_cmd = {
-            let mut encoder = ctx.device.create_command_encoder(
-                &CommandEncoderDescriptor { label: None },
-            );
+        let bind_group = &self.compute_bind_groups[self.parity];
 
-            {
-                let mut pass =
-                    encoder.begin_compute_pass(&ComputePassDescriptor {
-                        label: Some("hash data compute pass"),
-                        timestamp_writes: None,
-                    });
+        // one encoder for the whole tick, submitted once
+        let mut encoder = ctx
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("tick command encoder"),
+            });
 
-                pass.set_pipeline(&self.calc_hash_data_pipeline);
-                pass.set_bind_group(0, &self.compute_bind_group, &[]);
-                pass.dispatch_workgroups(x, y, z);
-            }
+        // hash data
+        {
+            let mut pass =
+                encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("hash data compute pass"),
+                    timestamp_writes: self.compute_writes(Pass::Hash),
+                });
 
-            encoder.finish()
-        };
+            pass.set_pipeline(&self.pipelines.calc_hash_data);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(x, y, z);
+        }
 
-        let sort_cmd = self
-            .hash_data_sorter
-            .sort_command_buffer(&ctx.device, self.points.len() as u32);
+        self.hash_data_sorter.encode(
+            &ctx.device,
+            &mut encoder,
+            self.points.len() as u32,
+        );
 
-        // hash index & update points
-        let hash_idx_upd_cmd = {
-            let mut encoder = ctx.device.create_command_encoder(
-                &CommandEncoderDescriptor { label: None },
-            );
+        // hash index
+        {
+            let mut pass =
+                encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("hash index compute pass"),
+                    timestamp_writes: self.compute_writes(Pass::Index),
+                });
 
-            {
-                let mut pass =
-                    encoder.begin_compute_pass(&ComputePassDescriptor {
-                        label: Some(
-                            "hash index & update points compute pass",
-                        ),
-                        timestamp_writes: None,
-                    });
-
-                pass.set_pipeline(&self.calc_hash_index_pipeline);
-                pass.set_bind_group(0, &self.compute_bind_group, &[]);
-                pass.dispatch_workgroups(x, y, z);
+            pass.set_pipeline(&self.pipelines.calc_hash_index);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(x, y, z);
+        }
+
+        // update points, separate pass to time it separately
+        {
+            let mut pass =
+                encoder.begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("update points compute pass"),
+                    timestamp_writes: self.compute_writes(Pass::Update),
+                });
 
-                pass.set_pipeline(&self.compute_pipeline);
-                pass.set_push_constants(0, param_slice);
-                pass.set_bind_group(0, &self.compute_bind_group, &[]);
+            // all densities are needed before any forces
+            if param[0].model == Model::Sph {
+                pass.set_pipeline(&self.pipelines.calc_density);
+                pass.set_bind_group(0, bind_group, &[]);
                 pass.dispatch_workgroups(x, y, z);
             }
 
-            encoder.copy_buffer_to_buffer(
-                &self.points_out_buffer,
-                0,
-                &self.points_buffer,
-                0,
-                (size_of::<Point>() * self.points.len()) as BufferAddress,
-            );
+            pass.set_pipeline(&self.pipelines.compute);
+            pass.set_push_constants(0, param_slice);
+            pass.set_bind_group(0, bind_group, &[]);
+            pass.dispatch_workgroups(x, y, z);
+        }
 
-            encoder.finish()
-        };
+        ctx.queue.submit([encoder.finish()]);
+        // the output is read next tick
+        self.parity ^= 1;
+    }
 
-        ctx.queue
-            .submit([hash_data_cmd, sort_cmd, hash_idx_upd_cmd]);
+    fn compute_writes(
+        &self,
+        pass: Pass,
+    ) -> Option<ComputePassTimestampWrites<'_>> {
+        self.timer.as_ref()?.compute_writes(pass)
+    }
+
+    /// Render into a texture of its own and save it, if requested
+    pub fn capture(
+        &mut self,
+        ctx: &WgpuContext,
+        format: TextureFormat,
+        size: PhysicalSize<u32>,
+    ) -> anyhow::Result<()> {
+        let paths = self.capture.next_paths();
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let texture = ctx.device.create_texture(&TextureDescriptor {
+            label: Some("capture texture"),
+            size: Extent3d {
+                width: size.width.max(1),
+                height: size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        self.render(ctx, &texture.create_view(&Default::default()));
+
+        let image = capture::read_texture(ctx, &texture)?;
+        for path in paths {
+            capture::save(image.clone(), path);
+        }
+        Ok(())
     }
 
-    pub fn render(&self, ctx: &WgpuContext, view: &TextureView) {
+    pub fn render(&mut self, ctx: &WgpuContext, view: &TextureView) {
         let mut encoder = ctx.device.create_command_encoder(
             &CommandEncoderDescriptor { label: None },
         );
@@ -421,16 +709,46 @@ impl Renderer {
                         },
                     )],
                     depth_stencil_attachment: None,
-                    timestamp_writes: None,
+                    timestamp_writes: self
+                        .timer
+                        .as_ref()
+                        .and_then(GpuTimer::render_writes),
                     occlusion_query_set: None,
                 });
 
-            rpass.set_pipeline(&self.render_pipeline);
-            rpass.set_vertex_buffer(0, self.points_out_buffer.slice(..));
+            // the replay's view is for another window
+            let param = [Param {
+                view_scale: self.input_state.lock().unwrap().view_scale,
+                ..self.param()
+            }];
+            rpass.set_pipeline(&self.pipelines.render);
+            rpass.set_push_constants(
+                ShaderStages::VERTEX,
+                0,
+                cast_slice(&param),
+            );
+            rpass.set_vertex_buffer(
+                0,
+                self.points_buffers[self.parity].slice(..),
+            );
+            rpass.set_vertex_buffer(
+                1,
+                self.points_density_buffer.slice(..),
+            );
 
             rpass.draw(0..6, 0..self.points.len() as u32);
+
+            rpass.set_pipeline(&self.pipelines.obstacle_render);
+            rpass.set_vertex_buffer(0, self.obstacles_buffer.slice(..));
+            rpass.draw(0..6, 0..self.obstacles.len() as u32);
         }
 
+        if let Some(timer) = &self.timer {
+            timer.resolve(&mut encoder);
+        }
         ctx.queue.submit(Some(encoder.finish()));
+        if let Some(timer) = &mut self.timer {
+            timer.map();
+        }
     }
 }
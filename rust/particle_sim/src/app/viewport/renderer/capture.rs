@@ -0,0 +1,162 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context};
+use image::RgbaImage;
+use tracing::{error, info};
+use wgpu::{
+    BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d,
+    ImageCopyBuffer, ImageDataLayout, Maintain, MapMode, Texture,
+    TextureFormat, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::wgpu_context::WgpuContext;
+
+/// Screenshot waiting for the next frame and the recording in progress
+#[derive(Debug, Default)]
+pub struct Capture {
+    pub screenshot: Option<PathBuf>,
+    pub recording: Option<Recording>,
+}
+
+/// Numbered frames in a directory, for assembling with ffmpeg
+#[derive(Debug)]
+pub struct Recording {
+    pub dir: PathBuf,
+    pub frame: u64,
+}
+
+impl Capture {
+    /// Start recording into `dir`, or stop the recording in progress
+    pub fn toggle_recording(&mut self, dir: &Path) -> anyhow::Result<()> {
+        if let Some(recording) = self.recording.take() {
+            info!(
+                "recorded {} frames, assemble them with: \
+                 ffmpeg -framerate 60 -i {}/frame_%06d.png \
+                 -pix_fmt yuv420p recording.mp4",
+                recording.frame,
+                recording.dir.display()
+            );
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(dir).with_context(|| {
+            format!("failed to create directory {}", dir.display())
+        })?;
+        info!("recording to {}", dir.display());
+        self.recording = Some(Recording {
+            dir: dir.to_path_buf(),
+            frame: 0,
+        });
+        Ok(())
+    }
+
+    /// Where to save the current frame, empty if it isn't captured
+    pub fn next_paths(&mut self) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        paths.extend(self.screenshot.take());
+        if let Some(recording) = &mut self.recording {
+            paths.push(
+                recording
+                    .dir
+                    .join(format!("frame_{:06}.png", recording.frame)),
+            );
+            recording.frame += 1;
+        }
+        paths
+    }
+}
+
+/// File name with the current time, to not overwrite earlier captures
+pub fn timestamped(prefix: &str, extension: &str) -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|it| it.as_secs())
+        .unwrap_or_default();
+    PathBuf::from(format!("{prefix}_{secs}{extension}"))
+}
+
+/// Save the image on another thread, not to stall the frame
+pub fn save(image: RgbaImage, path: PathBuf) {
+    rayon::spawn(move || match image.save(&path) {
+        Ok(()) => info!("saved {}", path.display()),
+        Err(err) => error!("failed to save {}: {err}", path.display()),
+    });
+}
+
+/// Copy a rendered texture back, blocking until it's done
+pub fn read_texture(
+    ctx: &WgpuContext,
+    texture: &Texture,
+) -> anyhow::Result<RgbaImage> {
+    let bgra = match texture.format() {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        format => bail!("capturing {format:?} is unsupported"),
+    };
+
+    let Extent3d { width, height, .. } = texture.size();
+    let row_len = width * 4;
+    let padded_row_len =
+        row_len.next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = ctx.device.create_buffer(&BufferDescriptor {
+        label: Some("capture buffer"),
+        size: padded_row_len as u64 * height as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = ctx
+        .device
+        .create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("capture"),
+        });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_len),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    ctx.queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(MapMode::Read, move |result| {
+        sender.send(result).ok();
+    });
+    ctx.device.poll(Maintain::Wait);
+    receiver
+        .recv()
+        .context("map callback dropped")?
+        .context("failed to map capture buffer")?;
+
+    let mut image = RgbaImage::new(width, height);
+    {
+        let data = slice.get_mapped_range();
+        for (src, dst) in data
+            .chunks(padded_row_len as usize)
+            .zip(image.chunks_mut(row_len as usize))
+        {
+            dst.copy_from_slice(&src[..row_len as usize]);
+        }
+    }
+    buffer.unmap();
+
+    for pixel in image.pixels_mut() {
+        if bgra {
+            pixel.0.swap(0, 2);
+        }
+        pixel.0[3] = 255;
+    }
+    Ok(image)
+}
@@ -1,4 +1,29 @@
-#[derive(Debug, Clone, PartialEq)]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::{obstacle, point::SpawnConfig};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Command {
+    /// Put the points back where they started
     Reset,
+    /// Generate new points, the count may change
+    Respawn(SpawnConfig),
+    /// Place an obstacle at the mouse
+    AddObstacle(obstacle::Shape),
+    /// Remove the newest obstacle under the mouse
+    RemoveObstacle,
+    ClearObstacles,
+    /// Save the next frame as a png
+    Screenshot(PathBuf),
+    /// Start saving every frame into a directory, or stop
+    ToggleRecording(PathBuf),
+}
+
+impl Command {
+    /// Whether it changes the simulation, for replays
+    pub fn is_input(&self) -> bool {
+        !matches!(self, Self::Screenshot(_) | Self::ToggleRecording(_))
+    }
 }
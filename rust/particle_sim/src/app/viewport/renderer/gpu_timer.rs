@@ -0,0 +1,217 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytemuck::cast_slice;
+use wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder,
+    ComputePassTimestampWrites, Device, Features, Maintain, MapMode,
+    QuerySet, QuerySetDescriptor, QueryType, RenderPassTimestampWrites,
+};
+
+use crate::wgpu_context::WgpuContext;
+
+/// Passes with timestamps, the sort runs between `Hash` and `Index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    Hash,
+    Index,
+    Update,
+    Render,
+}
+
+const QUERY_COUNT: u32 = 2 * 4;
+
+/// GPU time of each pass of one tick and the frame it was rendered in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PassTimes {
+    pub hash: Duration,
+    /// From the end of the hash pass to the start of the index pass
+    pub sort: Duration,
+    pub index: Duration,
+    pub update: Duration,
+    pub render: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Free to measure the next tick
+    Idle,
+    /// Timestamps of the current frame are being written
+    Recording,
+    /// Waiting to read the results back
+    Mapping,
+}
+
+/// Timestamp queries around the passes, read back without blocking.
+/// Only one frame is measured at a time, frames in between are skipped.
+#[derive(Debug)]
+pub struct GpuTimer {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    read_buffer: Buffer,
+    /// Nanoseconds per timestamp unit
+    period: f32,
+
+    state: State,
+    mapped: Arc<AtomicBool>,
+
+    pub last: Option<PassTimes>,
+}
+
+impl GpuTimer {
+    /// `None` if the device can't write timestamps
+    pub fn new(ctx: &WgpuContext) -> Option<Self> {
+        let device = &ctx.device;
+        if !device.features().contains(Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let size = QUERY_COUNT as u64 * 8;
+        Some(Self {
+            query_set: device.create_query_set(&QuerySetDescriptor {
+                label: Some("pass timestamps"),
+                ty: QueryType::Timestamp,
+                count: QUERY_COUNT,
+            }),
+            resolve_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("timestamp resolve buffer"),
+                size,
+                usage: BufferUsages::QUERY_RESOLVE
+                    | BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            read_buffer: device.create_buffer(&BufferDescriptor {
+                label: Some("timestamp read buffer"),
+                size,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period: ctx.queue.get_timestamp_period(),
+
+            state: State::Idle,
+            mapped: Arc::new(AtomicBool::new(false)),
+
+            last: None,
+        })
+    }
+
+    /// Measure the current frame if the last results were read.
+    /// Return: whether the passes should write timestamps
+    pub fn start(&mut self) -> bool {
+        if self.state == State::Idle {
+            self.state = State::Recording;
+        }
+        self.state == State::Recording
+    }
+
+    fn indices(&self, pass: Pass) -> (Option<u32>, Option<u32>) {
+        if self.state != State::Recording {
+            return (None, None);
+        }
+        let idx = pass as u32 * 2;
+        (Some(idx), Some(idx + 1))
+    }
+
+    pub fn compute_writes(
+        &self,
+        pass: Pass,
+    ) -> Option<ComputePassTimestampWrites<'_>> {
+        let (beginning, end) = self.indices(pass);
+        beginning.map(|_| ComputePassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: beginning,
+            end_of_pass_write_index: end,
+        })
+    }
+
+    pub fn render_writes(&self) -> Option<RenderPassTimestampWrites<'_>> {
+        let (beginning, end) = self.indices(Pass::Render);
+        beginning.map(|_| RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: beginning,
+            end_of_pass_write_index: end,
+        })
+    }
+
+    /// Copy the timestamps out, after the last measured pass
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        if self.state != State::Recording {
+            return;
+        }
+        encoder.resolve_query_set(
+            &self.query_set,
+            0..QUERY_COUNT,
+            &self.resolve_buffer,
+            0,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.read_buffer,
+            0,
+            self.resolve_buffer.size(),
+        );
+    }
+
+    /// Start reading back, after the resolve was submitted
+    pub fn map(&mut self) {
+        if self.state != State::Recording {
+            return;
+        }
+        self.state = State::Mapping;
+
+        let mapped = self.mapped.clone();
+        self.read_buffer.slice(..).map_async(
+            MapMode::Read,
+            move |result| {
+                if result.is_ok() {
+                    mapped.store(true, Ordering::Release);
+                }
+            },
+        );
+    }
+
+    /// Update [`GpuTimer::last`] if the results arrived
+    pub fn collect(&mut self, device: &Device) {
+        if self.state != State::Mapping {
+            return;
+        }
+        device.poll(Maintain::Poll);
+        if !self.mapped.swap(false, Ordering::Acquire) {
+            return;
+        }
+
+        let timestamps = {
+            let view = self.read_buffer.slice(..).get_mapped_range();
+            cast_slice::<_, u64>(&view).to_vec()
+        };
+        self.read_buffer.unmap();
+        self.state = State::Idle;
+
+        let between = |start: usize, end: usize| {
+            let ticks = timestamps[end].saturating_sub(timestamps[start]);
+            Duration::from_nanos(
+                (ticks as f64 * self.period as f64) as u64,
+            )
+        };
+        let pass = |pass: Pass| {
+            let idx = pass as usize * 2;
+            between(idx, idx + 1)
+        };
+        self.last = Some(PassTimes {
+            hash: pass(Pass::Hash),
+            sort: between(
+                Pass::Hash as usize * 2 + 1,
+                Pass::Index as usize * 2,
+            ),
+            index: pass(Pass::Index),
+            update: pass(Pass::Update),
+            render: pass(Pass::Render),
+        });
+    }
+}
@@ -0,0 +1,77 @@
+use bytemuck::NoUninit;
+use serde::{Deserialize, Serialize};
+
+use super::param::BOUNDARY_SIZE;
+
+/// Slots in the obstacle buffer, unused ones are [`Obstacle::NONE`]
+pub const MAX_OBSTACLES: usize = 32;
+
+// kinds, same as in the shader
+const NONE: u32 = 0;
+const CIRCLE: u32 = 1;
+const BOX: u32 = 2;
+
+/// Static collision geometry, also drawn as an instance
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, NoUninit)]
+#[repr(C)]
+pub struct Obstacle {
+    pub center: [f32; 2],
+    /// Radius twice for a circle, half of the width and height for a box
+    pub size: [f32; 2],
+    pub kind: u32,
+    _padding: u32,
+}
+
+/// Obstacle placed at the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Shape {
+    Circle,
+    /// Wide and flat, for building funnels
+    Box,
+}
+
+impl Obstacle {
+    pub const NONE: Self = Self::new(NONE, [0.0; 2], [0.0; 2]);
+
+    const fn new(kind: u32, center: [f32; 2], size: [f32; 2]) -> Self {
+        Self {
+            center,
+            size,
+            kind,
+            _padding: 0,
+        }
+    }
+
+    pub fn circle(center: [f32; 2], radius: f32) -> Self {
+        Self::new(CIRCLE, center, [radius; 2])
+    }
+
+    pub fn aabb(center: [f32; 2], half_size: [f32; 2]) -> Self {
+        Self::new(BOX, center, half_size)
+    }
+
+    pub fn from_shape(shape: Shape, center: [f32; 2]) -> Self {
+        match shape {
+            Shape::Circle => Self::circle(center, BOUNDARY_SIZE / 20.0),
+            Shape::Box => Self::aabb(
+                center,
+                [BOUNDARY_SIZE / 10.0, BOUNDARY_SIZE / 80.0],
+            ),
+        }
+    }
+
+    pub fn contains(&self, pos: [f32; 2]) -> bool {
+        let offset = [pos[0] - self.center[0], pos[1] - self.center[1]];
+        match self.kind {
+            CIRCLE => {
+                offset[0].powi(2) + offset[1].powi(2)
+                    <= self.size[0].powi(2)
+            }
+            BOX => {
+                offset[0].abs() <= self.size[0]
+                    && offset[1].abs() <= self.size[1]
+            }
+            _ => false,
+        }
+    }
+}
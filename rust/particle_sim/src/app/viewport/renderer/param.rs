@@ -1,6 +1,17 @@
 use bytemuck::NoUninit;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, NoUninit)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    PartialOrd,
+    NoUninit,
+    Serialize,
+    Deserialize,
+)]
+#[serde(default)]
 #[repr(C)]
 pub struct Param {
     pub time_delta: f32,
@@ -8,6 +19,123 @@ pub struct Param {
     pub mouse_pos: [f32; 2],
     pub boundary_collision_factor: u32,
     pub global_velocity_damping: u32,
+    /// Distance at which two points neither attract nor repel
+    pub repulsion_distance: f32,
+    pub repulsion_strength: f32,
+    /// Acceleration applied to every point
+    pub gravity: [f32; 2],
+    pub force_field: ForceField,
+    pub field_strength: f32,
+    /// Oscillations per second of the force field
+    pub field_frequency: f32,
+    /// Simulated seconds, set by the renderer every tick
+    pub time: f32,
+    pub color_mode: ColorMode,
+    /// Multiplier for the value colored at full heat
+    pub color_scale: f32,
+    pub model: Model,
+    /// Sph density the pressure keeps points at, 1 for a lone point
+    pub rest_density: f32,
+    /// Sph pressure per density above the rest density
+    pub stiffness: f32,
+    pub viscosity: f32,
+    /// Size of the simulated area
+    pub boundary: [f32; 2],
+    /// Fits the area into the window without stretching it
+    pub view_scale: [f32; 2],
+}
+
+/// Default width and height of the simulated area
+pub const BOUNDARY_SIZE: f32 = 80000.0;
+
+/// How points push each other
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    NoUninit,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum Model {
+    /// Lennard-Jones like, `repulsion_distance` and `repulsion_strength`
+    Repulsion = 0,
+    /// Fluid with pressure and viscosity
+    Sph = 1,
+}
+
+impl Model {
+    pub const ALL: [Self; 2] = [Self::Repulsion, Self::Sph];
+}
+
+/// Extra force on top of gravity, varying with time
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    NoUninit,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum ForceField {
+    None = 0,
+    /// Horizontal gusts, changing with height
+    Wind = 1,
+    /// Pulling towards the center
+    Attractor = 2,
+}
+
+/// What the color of a point shows
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    NoUninit,
+    Serialize,
+    Deserialize,
+)]
+#[repr(u32)]
+pub enum ColorMode {
+    Speed = 0,
+    /// Neighbours within the interaction range
+    Density = 1,
+    /// Hash grid cell the point is in
+    Cell = 2,
+}
+
+impl ColorMode {
+    pub const ALL: [Self; 3] = [Self::Speed, Self::Density, Self::Cell];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Speed => Self::Density,
+            Self::Density => Self::Cell,
+            Self::Cell => Self::Speed,
+        }
+    }
+}
+
+impl ForceField {
+    pub const ALL: [Self; 3] = [Self::None, Self::Wind, Self::Attractor];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Wind,
+            Self::Wind => Self::Attractor,
+            Self::Attractor => Self::None,
+        }
+    }
 }
 
 impl Default for Param {
@@ -18,6 +146,53 @@ impl Default for Param {
             mouse_pos: [0.0, 0.0],
             boundary_collision_factor: 100,
             global_velocity_damping: 10000,
+            repulsion_distance: 200.0,
+            repulsion_strength: 50000.0,
+            gravity: [0.0, -250.0],
+            force_field: ForceField::None,
+            field_strength: 1000.0,
+            field_frequency: 0.5,
+            time: 0.0,
+            color_mode: ColorMode::Speed,
+            color_scale: 1.0,
+            model: Model::Repulsion,
+            rest_density: 2.0,
+            stiffness: 200000.0,
+            viscosity: 50.0,
+            boundary: [BOUNDARY_SIZE; 2],
+            view_scale: [1.0; 2],
         }
     }
 }
+
+impl Param {
+    /// Default settings, keeping the input and the window's view
+    pub fn defaults(&self) -> Self {
+        Self {
+            mouse_press: self.mouse_press,
+            mouse_pos: self.mouse_pos,
+            boundary: self.boundary,
+            view_scale: self.view_scale,
+            ..Self::default()
+        }
+    }
+
+    /// Settings the sph model is stable with, keeping the input
+    pub fn fluid(&self) -> Self {
+        Self {
+            model: Model::Sph,
+            boundary_collision_factor: 50,
+            global_velocity_damping: 9999,
+            ..self.defaults()
+        }
+    }
+
+    /// From window space, 0 to 1 from the bottom left,
+    /// to simulation space
+    pub fn screen_to_world(&self, pos: [f32; 2]) -> [f32; 2] {
+        [0, 1].map(|axis| {
+            ((pos[axis] - 0.5) / self.view_scale[axis] + 0.5)
+                * self.boundary[axis]
+        })
+    }
+}
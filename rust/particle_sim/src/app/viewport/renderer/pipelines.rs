@@ -0,0 +1,221 @@
+use std::{
+    future::Future,
+    mem::size_of,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use anyhow::bail;
+use wgpu::{
+    vertex_attr_array, BindGroupLayout, BufferAddress, ColorTargetState,
+    ColorWrites, ComputePipeline, ComputePipelineDescriptor, Device,
+    ErrorFilter, Face, PipelineLayoutDescriptor, PushConstantRange,
+    RenderPipeline, ShaderModule, ShaderModuleDescriptor, ShaderSource,
+    ShaderStages, TextureFormat, VertexBufferLayout, VertexStepMode,
+};
+
+use super::{obstacle::Obstacle, param::Param, point::Point, shader};
+
+/// Every pipeline built from `shader.wgsl`
+#[derive(Debug)]
+pub struct Pipelines {
+    pub calc_hash_data: ComputePipeline,
+    pub calc_hash_index: ComputePipeline,
+    pub calc_density: ComputePipeline,
+    pub compute: ComputePipeline,
+    pub render: RenderPipeline,
+    pub obstacle_render: RenderPipeline,
+}
+
+impl Pipelines {
+    pub fn new(
+        device: &Device,
+        shader: &ShaderModule,
+        compute_bind_group_layout: &BindGroupLayout,
+        swapchain_format: TextureFormat,
+    ) -> Self {
+
+        // compute pipeline
+        let compute_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("compute layout"),
+                bind_group_layouts: &[compute_bind_group_layout],
+                push_constant_ranges: &[PushConstantRange {
+                    stages: ShaderStages::COMPUTE,
+                    range: 0..size_of::<Param>() as u32,
+                }],
+            });
+
+        let calc_hash_data =
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("calc hash data pipeline"),
+                layout: Some(&compute_layout),
+                module: shader,
+                entry_point: "calc_hash_data",
+                compilation_options: Default::default(),
+            });
+
+        let calc_hash_index =
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("calc hash index pipeline"),
+                layout: Some(&compute_layout),
+                module: shader,
+                entry_point: "calc_hash_index",
+                compilation_options: Default::default(),
+            });
+
+        let calc_density =
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("calc density pipeline"),
+                layout: Some(&compute_layout),
+                module: shader,
+                entry_point: "calc_density",
+                compilation_options: Default::default(),
+            });
+
+        let compute =
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some("compute pipeline"),
+                layout: Some(&compute_layout),
+                module: shader,
+                entry_point: "cs_main",
+                compilation_options: Default::default(),
+            });
+
+        // render pipeline
+
+        let instance_buffer_layout = VertexBufferLayout {
+            array_stride: size_of::<Point>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2],
+        };
+        let density_buffer_layout = VertexBufferLayout {
+            array_stride: size_of::<u32>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &vertex_attr_array![2 => Float32],
+        };
+
+        let render_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("render layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[PushConstantRange {
+                    stages: ShaderStages::VERTEX,
+                    range: 0..size_of::<Param>() as u32,
+                }],
+            });
+
+        let render = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("render pipeline"),
+                layout: Some(&render_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_main",
+                    buffers: &[
+                        instance_buffer_layout,
+                        density_buffer_layout,
+                    ],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: "fs_main",
+                    compilation_options: Default::default(),
+                    targets: &[Some(ColorTargetState {
+                        format: swapchain_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: ColorWrites::COLOR,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode: Some(Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            },
+        );
+
+        let obstacle_buffer_layout = VertexBufferLayout {
+            array_stride: size_of::<Obstacle>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &vertex_attr_array![
+                0 => Float32x2,
+                1 => Float32x2,
+                2 => Uint32,
+            ],
+        };
+
+        let obstacle_render = device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("obstacle render pipeline"),
+                layout: Some(&render_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: "vs_obstacle",
+                    buffers: &[obstacle_buffer_layout],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: "fs_obstacle",
+                    compilation_options: Default::default(),
+                    targets: &[Some(ColorTargetState {
+                        format: swapchain_format,
+                        blend: None,
+                        write_mask: ColorWrites::COLOR,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    cull_mode: Some(Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            },
+        );
+
+        Self {
+            calc_hash_data,
+            calc_hash_index,
+            calc_density,
+            compute,
+            render,
+            obstacle_render,
+        }
+    }
+
+    /// Build from a changed shader source, `Err` with the report if it's
+    /// invalid or doesn't fit the layouts, instead of panicking
+    pub fn try_from_source(
+        device: &Device,
+        src: &str,
+        compute_bind_group_layout: &BindGroupLayout,
+        swapchain_format: TextureFormat,
+    ) -> anyhow::Result<Self> {
+        shader::validate(src)?;
+
+        device.push_error_scope(ErrorFilter::Validation);
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("shader.wgsl"),
+            source: ShaderSource::Wgsl(src.into()),
+        });
+        let pipelines = Self::new(
+            device,
+            &shader,
+            compute_bind_group_layout,
+            swapchain_format,
+        );
+
+        // ready right away on native
+        let mut error = pin!(device.pop_error_scope());
+        let mut cx = Context::from_waker(Waker::noop());
+        if let Poll::Ready(Some(err)) = error.as_mut().poll(&mut cx) {
+            bail!("{err}");
+        }
+        Ok(pipelines)
+    }
+}
@@ -1,5 +1,13 @@
 use bytemuck::NoUninit;
+use clap::{Args, ValueEnum};
 use itertools::Itertools as _;
+use rand::{rngs::StdRng, Rng as _, SeedableRng as _};
+use serde::{Deserialize, Serialize};
+
+/// Distance between neighbouring points when spawned, the size of a point
+const SPACING: f32 = 195.0;
+/// Center of the area points spawn in
+const CENTER: [f32; 2] = [40000.0, 40000.0];
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, NoUninit)]
 #[repr(C)]
@@ -8,58 +16,173 @@ pub struct Point {
     pub velocity: [f32; 2],
 }
 
+/// How points are placed on reset
+#[derive(Debug, Clone, Copy, PartialEq, Args, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnConfig {
+    /// Shape the points start in
+    #[arg(long, value_enum, default_value_t = Shape::Grid)]
+    pub shape: Shape,
+    /// Number of points
+    #[arg(long, default_value_t = 65536)]
+    pub count: usize,
+    /// Initial velocity of the points
+    #[arg(long, value_enum, default_value_t = Velocity::Zero)]
+    pub velocity: Velocity,
+    /// Largest initial speed, unused for `--velocity zero`
+    #[arg(long, default_value_t = 1000.0)]
+    pub speed: f32,
+    /// Seed for the random velocities
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            shape: Shape::Grid,
+            count: 65536,
+            velocity: Velocity::Zero,
+            speed: 1000.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize,
+)]
+pub enum Shape {
+    /// Square in the bottom left corner
+    Grid,
+    /// Disc in the center
+    Ball,
+    /// Disc with a hole in the center
+    Ring,
+    /// Two discs side by side
+    TwoBlobs,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize,
+)]
+pub enum Velocity {
+    /// At rest
+    Zero,
+    /// Random direction and speed
+    Random,
+    /// Rotating around the center of the shape, faster further out
+    Swirl,
+}
+
 impl Point {
-    pub fn gen() -> Vec<Point> {
-        let size = 50000_f32;
-        let spacing = 195_f32;
-        let points_num = (size / spacing).powi(2);
-        let num = points_num.sqrt();
-        let spacing = size / num;
-        let half_spacing = spacing / 2.0;
-
-        // let mut rng = rand::thread_rng();
-        // let velocity_range = -100.0..100.0;
-
-        // let step = 0.001;
-        // let rotate_radians = std::f32::consts::PI * 2.0 * (step / 360.0);
-        //
-        // let center = vec2(20000.0, 20000.0);
-
-        // let points = vec![
-        //     Point {
-        //         pos: [5000.0, 5000.0],
-        //         velocity: [0.0, 0.0],
-        //     },
-        //     Point {
-        //         pos: [5000.0, 5250.0],
-        //         velocity: [0.0, 0.0],
-        //     },
-        // ];
-
-        (0..points_num as usize)
-            .map(move |idx| {
-                let idx = idx as f32;
-                let x = (idx % num).floor() * spacing + half_spacing;
-                let y = (idx / num).floor() * spacing + half_spacing;
-                // let pos = vec2(x, y) - center;
-                // let rotated = vec2(
-                //     pos.x * rotate_radians.cos() - pos.y * rotate_radians.sin(),
-                //     pos.x * rotate_radians.sin() + pos.y * rotate_radians.cos(),
-                // );
-                // let rotate = (rotated - pos) / step * 10.0;
-                Point {
-                    velocity: [0.0, 0.0],
-                    // velocity: [rotate.x, rotate.y],
-                    // velocity: [
-                    //     rotate.x + rng.gen_range(velocity_range.clone()),
-                    //     rotate.y + rng.gen_range(velocity_range.clone()),
-                    // ],
-                    pos: [x, y], /* [
-                                     x as f32 + rng.gen_range(velocity_range.clone()),
-                                     y as f32 + rng.gen_range(velocity_range.clone()),
-                                 ] */
-                }
+    pub fn gen(config: &SpawnConfig) -> Vec<Point> {
+        let count = config.count.max(1);
+        let positions = match config.shape {
+            Shape::Grid => grid(count),
+            Shape::Ball => disc(CENTER, 0.0, count),
+            Shape::Ring => {
+                // as much area for the hole as for the points
+                let inner = (count as f32 / std::f32::consts::PI).sqrt()
+                    * SPACING;
+                disc(CENTER, inner, count)
+            }
+            Shape::TwoBlobs => {
+                // just touching
+                let offset = disc_radius(0.0, count - count / 2) + SPACING;
+                let [x, y] = CENTER;
+                let mut positions = disc([x - offset, y], 0.0, count / 2);
+                positions.extend(disc(
+                    [x + offset, y],
+                    0.0,
+                    count - count / 2,
+                ));
+                positions
+            }
+        };
+
+        let center = centroid(&positions);
+        let max_dst = positions
+            .iter()
+            .map(|pos| distance(*pos, center))
+            .fold(0.0, f32::max)
+            .max(1.0);
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        positions
+            .into_iter()
+            .map(|pos| {
+                let velocity = match config.velocity {
+                    Velocity::Zero => [0.0, 0.0],
+                    Velocity::Random => {
+                        let angle =
+                            rng.gen_range(0.0..std::f32::consts::TAU);
+                        let speed = rng.gen_range(0.0..=config.speed);
+                        [angle.cos() * speed, angle.sin() * speed]
+                    }
+                    Velocity::Swirl => {
+                        let offset =
+                            [pos[0] - center[0], pos[1] - center[1]];
+                        let scale = config.speed / max_dst;
+                        [-offset[1] * scale, offset[0] * scale]
+                    }
+                };
+                Point { pos, velocity }
             })
             .collect_vec()
     }
 }
+
+/// Square starting at the origin
+fn grid(count: usize) -> Vec<[f32; 2]> {
+    let num = (count as f32).sqrt().ceil() as usize;
+    let half_spacing = SPACING / 2.0;
+
+    (0..count)
+        .map(|idx| {
+            let x = (idx % num) as f32 * SPACING + half_spacing;
+            let y = (idx / num) as f32 * SPACING + half_spacing;
+            [x, y]
+        })
+        .collect_vec()
+}
+
+/// The `count` grid positions closest to `center`,
+/// skipping the ones closer than `inner`
+fn disc(center: [f32; 2], inner: f32, count: usize) -> Vec<[f32; 2]> {
+    let steps = (disc_radius(inner, count) / SPACING).ceil() as i32 + 1;
+
+    (-steps..=steps)
+        .cartesian_product(-steps..=steps)
+        .map(|(x, y)| {
+            [
+                center[0] + x as f32 * SPACING,
+                center[1] + y as f32 * SPACING,
+            ]
+        })
+        .filter(|pos| distance(*pos, center) >= inner)
+        .sorted_by(|a, b| {
+            distance(*a, center).total_cmp(&distance(*b, center))
+        })
+        .take(count)
+        .collect_vec()
+}
+
+/// Radius of a disc fitting `count` points around a hole of `inner` radius
+fn disc_radius(inner: f32, count: usize) -> f32 {
+    // a bit more area than needed, the edge is ragged
+    let area = count as f32 * SPACING * SPACING * 1.1;
+    (area / std::f32::consts::PI + inner * inner).sqrt()
+}
+
+fn centroid(positions: &[[f32; 2]]) -> [f32; 2] {
+    let len = positions.len().max(1) as f32;
+    let [x, y] = positions
+        .iter()
+        .fold([0.0, 0.0], |acc, pos| [acc[0] + pos[0], acc[1] + pos[1]]);
+    [x / len, y / len]
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
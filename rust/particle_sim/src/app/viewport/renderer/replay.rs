@@ -0,0 +1,219 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use super::{command::Command, param::Param, point::SpawnConfig};
+
+#[derive(Debug, Clone, Args)]
+pub struct ReplayArgs {
+    /// Record the inputs into a replay file
+    #[arg(long, conflicts_with = "replay")]
+    pub record_replay: Option<PathBuf>,
+    /// Play back a replay file, the points spawn as recorded
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+}
+
+/// Input changing the simulation, applied before tick `tick`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Event {
+    tick: u64,
+    input: Input,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Input {
+    Param(Param),
+    Command(Command),
+}
+
+/// Writes a replay file: the spawn config on the first line,
+/// then one event per line
+#[derive(Debug)]
+pub struct Recorder {
+    file: BufWriter<File>,
+    last_param: Option<Param>,
+}
+
+impl Recorder {
+    pub fn create(
+        path: &Path,
+        spawn_config: &SpawnConfig,
+    ) -> anyhow::Result<Self> {
+        let file = File::create(path).with_context(|| {
+            format!("failed to create replay file {}", path.display())
+        })?;
+        let mut recorder = Self {
+            file: BufWriter::new(file),
+            last_param: None,
+        };
+        recorder.write_line(spawn_config)?;
+        Ok(recorder)
+    }
+
+    fn write_line(
+        &mut self,
+        value: &impl Serialize,
+    ) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut self.file, value)
+            .context("failed to write replay")?;
+        writeln!(self.file).context("failed to write replay")
+    }
+
+    /// Record `param` if it changed
+    pub fn param(
+        &mut self,
+        tick: u64,
+        param: &Param,
+    ) -> anyhow::Result<()> {
+        // set by the renderer, not an input
+        let param = Param {
+            time: 0.0,
+            ..*param
+        };
+        if self.last_param == Some(param) {
+            return Ok(());
+        }
+        self.last_param = Some(param);
+        self.write_line(&Event {
+            tick,
+            input: Input::Param(param),
+        })
+    }
+
+    pub fn command(
+        &mut self,
+        tick: u64,
+        command: &Command,
+    ) -> anyhow::Result<()> {
+        self.write_line(&Event {
+            tick,
+            input: Input::Command(command.clone()),
+        })
+    }
+}
+
+/// Feeds the events of a replay file back at their ticks
+#[derive(Debug)]
+pub struct Player {
+    events: VecDeque<Event>,
+    /// Input of the current tick, instead of the live one
+    pub param: Param,
+}
+
+impl Player {
+    /// Return: the spawn config the recording started with
+    pub fn open(path: &Path) -> anyhow::Result<(SpawnConfig, Self)> {
+        let file = File::open(path).with_context(|| {
+            format!("failed to open replay file {}", path.display())
+        })?;
+        let mut lines = BufReader::new(file).lines();
+
+        let spawn_config = lines
+            .next()
+            .context("replay file is empty")?
+            .context("failed to read replay")?;
+        let spawn_config = serde_json::from_str(&spawn_config)
+            .context("failed to parse the spawn config")?;
+
+        let events = lines
+            .enumerate()
+            .map(|(idx, line)| {
+                let line = line.context("failed to read replay")?;
+                serde_json::from_str(&line).with_context(|| {
+                    format!("failed to parse event on line {}", idx + 2)
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let player = Self {
+            events,
+            param: Param::default(),
+        };
+        Ok((spawn_config, player))
+    }
+
+    /// Next command due before `tick`, updating [`Player::param`]
+    /// with the changes recorded before it
+    pub fn next_command(&mut self, tick: u64) -> Option<Command> {
+        while self.events.front()?.tick <= tick {
+            match self.events.pop_front()?.input {
+                Input::Param(param) => self.param = param,
+                Input::Command(command) => return Some(command),
+            }
+        }
+        None
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::viewport::renderer::obstacle;
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join("particle_sim_test.replay");
+        let spawn_config = SpawnConfig {
+            count: 7,
+            seed: 3,
+            ..SpawnConfig::default()
+        };
+        let param = Param {
+            time: 1.5,
+            ..Param::default()
+        };
+        let changed = Param {
+            gravity: [0.0, 0.0],
+            time: 3.0,
+            ..param
+        };
+        let add = Command::AddObstacle(obstacle::Shape::Box);
+
+        let mut recorder = Recorder::create(&path, &spawn_config).unwrap();
+        recorder.param(0, &param).unwrap();
+        // only the time differs, not recorded
+        recorder.param(1, &Param { time: 2.0, ..param }).unwrap();
+        recorder.command(2, &Command::Reset).unwrap();
+        recorder.param(3, &changed).unwrap();
+        recorder.command(3, &add).unwrap();
+        recorder.param(4, &changed).unwrap();
+        recorder.command(6, &Command::ClearObstacles).unwrap();
+        drop(recorder);
+
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(recorded.lines().count(), 1 + 5);
+
+        let (spawned, mut player) = Player::open(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(spawned, spawn_config);
+
+        let recorded = |param: Param| Param { time: 0.0, ..param };
+        assert_eq!(player.next_command(0), None);
+        assert_eq!(player.param, recorded(param));
+        assert_eq!(player.next_command(1), None);
+        assert_eq!(player.next_command(2), Some(Command::Reset));
+        assert_eq!(player.next_command(2), None);
+        assert_eq!(player.param, recorded(param));
+        assert_eq!(player.next_command(3), Some(add));
+        assert_eq!(player.param, recorded(changed));
+        assert_eq!(player.next_command(5), None);
+        assert!(!player.is_finished());
+        assert_eq!(
+            player.next_command(6),
+            Some(Command::ClearObstacles)
+        );
+        assert!(player.is_finished());
+    }
+}
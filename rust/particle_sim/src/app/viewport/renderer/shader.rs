@@ -0,0 +1,77 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::anyhow;
+use tracing::error;
+use wgpu::naga::{
+    front::wgsl,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+
+/// Source of the embedded shader, watched for changes while running
+pub const SHADER_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/shader.wgsl");
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls the modification time of the shader source
+#[derive(Debug)]
+pub struct ShaderWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    /// The current version of the file counts as already loaded
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let mut this = Self {
+            path: path.into(),
+            modified: None,
+            last_poll: Instant::now(),
+        };
+        this.modified = this.modified();
+        this
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// New source if the file changed since the last poll,
+    /// `None` if it's missing
+    pub fn poll(&mut self) -> Option<String> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = self.modified()?;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+
+        fs::read_to_string(&self.path)
+            .inspect_err(|err| {
+                error!("failed to read {}: {err}", self.path.display())
+            })
+            .ok()
+    }
+}
+
+/// Parse and validate with naga, to report errors instead of panicking
+/// in wgpu
+pub fn validate(src: &str) -> anyhow::Result<()> {
+    let module = wgsl::parse_str(src)
+        .map_err(|err| anyhow!(err.emit_to_string(src)))?;
+    Validator::new(ValidationFlags::all(), Capabilities::PUSH_CONSTANT)
+        .validate(&module)
+        .map_err(|err| anyhow!(err.emit_to_string(src)))?;
+    Ok(())
+}
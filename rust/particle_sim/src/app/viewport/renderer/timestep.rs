@@ -0,0 +1,59 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Fixed timestep accumulator, turns elapsed real time into ticks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Timestep {
+    /// Ticks per second of real time
+    pub tick_rate: f64,
+    /// Time past this is dropped instead of caught up on, so a slow frame
+    /// doesn't make the next one slower
+    pub max_ticks_per_frame: u32,
+
+    #[serde(skip)]
+    accumulator: Duration,
+    #[serde(skip)]
+    last: Option<Instant>,
+}
+
+impl Default for Timestep {
+    fn default() -> Self {
+        Self {
+            tick_rate: 1000.0,
+            max_ticks_per_frame: 32,
+
+            accumulator: Duration::ZERO,
+            last: None,
+        }
+    }
+}
+
+impl Timestep {
+    /// Ticks due since the last call
+    pub fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        if let Some(last) = self.last {
+            self.accumulator += now - last;
+        }
+        self.last = Some(now);
+
+        let tick = Duration::from_secs_f64(1.0 / self.tick_rate.max(1.0));
+        let due = (self.accumulator.as_secs_f64() / tick.as_secs_f64())
+            as u32;
+        if due > self.max_ticks_per_frame {
+            self.accumulator = Duration::ZERO;
+            self.max_ticks_per_frame
+        } else {
+            self.accumulator -= tick * due;
+            due
+        }
+    }
+
+    /// Forget the time passed so far, for after a pause
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+        self.last = None;
+    }
+}
@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use anyhow::Context;
+use clap::{parser::ValueSource, ArgMatches};
+use serde::{
+    de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize,
+    Serializer,
+};
+
+use crate::app::viewport::renderer::{
+    param::Param, point::SpawnConfig, timestep::Timestep,
+};
+
+/// Read when no config file is given, if it exists
+const DEFAULT_PATH: &str = "particle_sim.toml";
+
+/// Fields of [`Param`] set while running, which a config file can't set
+const RUNTIME_PARAMS: [&str; 5] =
+    ["mouse_press", "mouse_pos", "time", "boundary", "view_scale"];
+
+/// Startup settings, missing ones are the defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Initial simulation parameters, without the [`RUNTIME_PARAMS`]
+    #[serde(with = "startup_param")]
+    pub param: Param,
+    pub spawn: SpawnConfig,
+    pub window: WindowConfig,
+    pub timestep: Timestep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// Inner size in physical pixels
+    pub size: [u32; 2],
+    /// Wait for the display to refresh instead of presenting immediately
+    pub vsync: bool,
+    /// Grow the simulated area to the shape of the window
+    pub fit_boundary: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            size: [1200, 1200],
+            vsync: false,
+            fit_boundary: false,
+        }
+    }
+}
+
+/// [`Param`] without the [`RUNTIME_PARAMS`], they are an error in a file
+mod startup_param {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        param: &Param,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut table =
+            toml::Table::try_from(param).map_err(S::Error::custom)?;
+        table.retain(|key, _| !RUNTIME_PARAMS.contains(&key));
+        table.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Param, D::Error> {
+        let table = toml::Table::deserialize(deserializer)?;
+        let runtime = table
+            .keys()
+            .find(|key| RUNTIME_PARAMS.contains(&key.as_str()));
+        if let Some(key) = runtime {
+            return Err(D::Error::custom(format!(
+                "`{key}` is set while running, not by the config"
+            )));
+        }
+        table.try_into().map_err(D::Error::custom)
+    }
+}
+
+impl Config {
+    /// Load `path`, or `particle_sim.toml` if there's one
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_PATH).exists() => {
+                Path::new(DEFAULT_PATH)
+            }
+            None => return Ok(Self::default()),
+        };
+
+        let config = std::fs::read_to_string(path).with_context(|| {
+            format!("failed to read config file {}", path.display())
+        })?;
+        Self::parse(&config).with_context(|| {
+            format!("failed to parse config file {}", path.display())
+        })
+    }
+
+    pub fn parse(config: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(config)?)
+    }
+
+    /// Config file contents, the defaults give a template to edit
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Replace the spawn settings given on the command line
+    pub fn apply_spawn_flags(
+        &mut self,
+        flags: &SpawnConfig,
+        matches: &ArgMatches,
+    ) {
+        let given = |id| {
+            matches.value_source(id) == Some(ValueSource::CommandLine)
+        };
+        if given("shape") {
+            self.spawn.shape = flags.shape;
+        }
+        if given("count") {
+            self.spawn.count = flags.count;
+        }
+        if given("velocity") {
+            self.spawn.velocity = flags.velocity;
+        }
+        if given("speed") {
+            self.spawn.speed = flags.speed;
+        }
+        if given("seed") {
+            self.spawn.seed = flags.seed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{Args, Command, FromArgMatches};
+
+    use super::*;
+    use crate::app::viewport::renderer::point::Shape;
+
+    #[test]
+    fn test_default_round_trip() {
+        let dumped = Config::default().to_toml().unwrap();
+        let config = Config::parse(&dumped).unwrap();
+
+        let default = Config::default();
+        assert_eq!(config.param, default.param);
+        assert_eq!(config.spawn, default.spawn);
+        assert_eq!(config.window, default.window);
+        assert_eq!(config.timestep.tick_rate, default.timestep.tick_rate);
+        assert_eq!(config.to_toml().unwrap(), dumped);
+
+        let dumped = dumped.parse::<toml::Table>().unwrap();
+        for key in RUNTIME_PARAMS {
+            assert!(dumped["param"].get(key).is_none(), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_partial_config() {
+        let config = Config::parse("[window]\nvsync = true\n").unwrap();
+        assert!(config.window.vsync);
+        assert_eq!(config.window.size, WindowConfig::default().size);
+        assert!(Config::parse("[window]\nvsync = 1\n").is_err());
+
+        let config = Config::parse("[param]\ngravity = [0.0, -1.0]\n");
+        assert_eq!(config.unwrap().param.gravity, [0.0, -1.0]);
+        let config = Config::parse("[param]\nboundary = [1.0, 1.0]\n");
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_apply_spawn_flags() {
+        let command = SpawnConfig::augment_args(Command::new("sim"));
+        let matches = command
+            .try_get_matches_from(["sim", "--count", "10", "--seed", "3"])
+            .unwrap();
+        let flags = SpawnConfig::from_arg_matches(&matches).unwrap();
+
+        let mut config = Config::parse(
+            "[spawn]\nshape = \"Ball\"\ncount = 5\nspeed = 2.0\n",
+        )
+        .unwrap();
+        config.apply_spawn_flags(&flags, &matches);
+
+        // only the flags given replace the file's values
+        assert_eq!(
+            config.spawn,
+            SpawnConfig {
+                shape: Shape::Ball,
+                count: 10,
+                speed: 2.0,
+                seed: 3,
+                ..SpawnConfig::default()
+            }
+        );
+    }
+}
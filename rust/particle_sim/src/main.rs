@@ -2,11 +2,17 @@
 
 use std::{
     collections::VecDeque,
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
 use anyhow::Context;
-use app::viewport::renderer::param::Param;
+use app::viewport::renderer::{
+    point::SpawnConfig,
+    replay::{Player, Recorder, ReplayArgs},
+};
+use clap::{CommandFactory as _, FromArgMatches as _, Parser};
+use config::Config;
 use tracing::Level;
 use tracing_subscriber::EnvFilter;
 use wgpu_context::WgpuContext;
@@ -14,15 +20,46 @@ use winit::event_loop::{ControlFlow, EventLoop};
 
 use crate::app::App;
 
+#[derive(Debug, Parser)]
+#[command(about = "Particle simulation on the GPU")]
+struct Cli {
+    #[command(flatten)]
+    spawn: SpawnConfig,
+    #[command(flatten)]
+    replay: ReplayArgs,
+    /// Grow the simulated area to the shape of the window
+    #[arg(long)]
+    fit_boundary: bool,
+    /// Wait for the display to refresh instead of presenting immediately
+    #[arg(long)]
+    vsync: bool,
+    /// TOML file with the startup settings, overridden by the flags
+    /// given, `particle_sim.toml` is read if it exists
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Print the default config and exit
+    #[arg(long)]
+    dump_default_config: bool,
+}
+
 #[tokio::main]
 async fn main() {
     run().await.expect("failed to run");
 }
 
 mod app;
+mod config;
 mod wgpu_context;
 
 async fn run() -> anyhow::Result<()> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
+
+    if cli.dump_default_config {
+        print!("{}", Config::default().to_toml()?);
+        return Ok(());
+    }
+
     dotenv::dotenv().ok();
     tracing_subscriber::fmt::fmt()
         .with_env_filter(
@@ -32,14 +69,42 @@ async fn run() -> anyhow::Result<()> {
         )
         .init();
 
+    let mut config = Config::load(cli.config.as_deref())
+        .context("failed to load config")?;
+    config.apply_spawn_flags(&cli.spawn, &matches);
+    config.window.fit_boundary |= cli.fit_boundary;
+    config.window.vsync |= cli.vsync;
+
+    let mut spawn_config = config.spawn;
+    let mut player = None;
+    if let Some(path) = &cli.replay.replay {
+        let (config, replay) =
+            Player::open(path).context("failed to load replay")?;
+        spawn_config = config;
+        player = Some(replay);
+    }
+    let recorder = cli
+        .replay
+        .record_replay
+        .as_deref()
+        .map(|path| Recorder::create(path, &spawn_config))
+        .transpose()
+        .context("failed to start recording the replay")?;
+
     let mut app = App {
         ctx: WgpuContext::new()
             .await
             .context("failed to initialize wgpu context")?,
-        state: Arc::new(Mutex::new(Param::default())),
+        state: Arc::new(Mutex::new(config.param)),
         command_queue: Arc::new(Mutex::new(VecDeque::new())),
+        spawn_config,
+        recorder,
+        player,
+        window_config: config.window,
+        timestep: config.timestep,
 
         paused: false,
+        step_requested: false,
 
         viewport: None,
     };
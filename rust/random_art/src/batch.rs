@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    ops::Range,
+    path::PathBuf,
+    time::Instant,
+};
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use image::RgbImage;
+use rand::random;
+use random_art::{grammar::Grammer, render::View};
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    ThreadPoolBuilder,
+};
+use serde::Serialize;
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Seeds to render, like `0..100`
+    #[arg(
+        long,
+        value_parser = parse_range,
+        required_unless_present = "count",
+        conflicts_with = "count"
+    )]
+    seeds: Option<Range<u64>>,
+    /// Render this many random seeds instead
+    #[arg(long)]
+    count: Option<usize>,
+    /// Width and height of the images in pixels
+    #[arg(long, default_value_t = 1024)]
+    size: u32,
+    /// Directory for the images and `manifest.json`
+    #[arg(long, default_value = "output")]
+    out: PathBuf,
+    /// Worker threads, one per core if omitted
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+fn parse_range(s: &str) -> anyhow::Result<Range<u64>> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or(anyhow!("expected a range like `0..100`"))?;
+    Ok(start.parse()?..end.parse()?)
+}
+
+/// Manifest entry of a seed
+#[derive(Debug, Serialize)]
+struct SeedStats {
+    /// Image file, relative to the manifest
+    file: String,
+    /// Nodes in the expression
+    nodes: usize,
+    /// Average color from 0 to 255
+    mean_rgb: [f64; 3],
+    render_ms: f64,
+}
+
+/// Render every seed in parallel, each worker reusing its image buffer,
+/// then write `manifest.json` mapping the seeds to their stats
+pub fn batch(args: &BatchArgs) -> anyhow::Result<()> {
+    let seeds = match (&args.seeds, args.count) {
+        (Some(seeds), _) => seeds.clone().collect::<Vec<_>>(),
+        (None, Some(count)) => (0..count).map(|_| random()).collect(),
+        (None, None) => unreachable!("required by clap"),
+    };
+    fs::create_dir_all(&args.out).context("failed to create output dir")?;
+
+    let mut pool = ThreadPoolBuilder::new();
+    if let Some(threads) = args.threads {
+        pool = pool.num_threads(threads);
+    }
+    let pool = pool.build().context("failed to build thread pool")?;
+
+    let grammar = Grammer::art();
+    let start = Instant::now();
+    let manifest = pool.install(|| {
+        seeds
+            .into_par_iter()
+            .map_init(
+                || RgbImage::new(args.size, args.size),
+                |img, seed| {
+                    let stats = render_seed(img, &grammar, seed, args)?;
+                    Ok((seed, stats))
+                },
+            )
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()
+    })?;
+    println!(
+        "rendered {} images in {:.2?}",
+        manifest.len(),
+        start.elapsed()
+    );
+
+    let file = fs::File::create(args.out.join("manifest.json"))
+        .context("failed to create manifest")?;
+    serde_json::to_writer_pretty(file, &manifest)
+        .context("failed to write manifest")?;
+
+    Ok(())
+}
+
+fn render_seed(
+    img: &mut RgbImage,
+    grammar: &Grammer,
+    seed: u64,
+    args: &BatchArgs,
+) -> anyhow::Result<SeedStats> {
+    let start = Instant::now();
+    let view = View {
+        seed,
+        ..View::default()
+    };
+    let expr = view.expr(grammar);
+
+    // the seeds are already spread over the threads
+    let size = args.size as f64;
+    let mut sum = [0.0; 3];
+    for (x, y, px) in img.enumerate_pixels_mut() {
+        let (x, y) = view.to_image_space(x as f64 / size, y as f64 / size);
+        px.0 = expr.eval(x, y).to_rgb8();
+        for (sum, channel) in sum.iter_mut().zip(px.0) {
+            *sum += channel as f64;
+        }
+    }
+    let render_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let file = format!("{seed}-{}.png", args.size);
+    img.save(args.out.join(&file))
+        .with_context(|| format!("failed to save {file}"))?;
+
+    let pixels = img.pixels().len() as f64;
+    Ok(SeedStats {
+        file,
+        nodes: expr.node_count(),
+        mean_rgb: sum.map(|sum| sum / pixels),
+        render_ms,
+    })
+}
@@ -11,6 +11,129 @@ pub struct Grammer {
 }
 
 impl Grammer {
+    /// Grammar the images are generated with
+    pub fn art() -> Self {
+        let mut rules = HashMap::new();
+        let rule_ref = |id: u64| Box::new(RuleNode::Rule(RuleId(id)));
+        let rgb = || {
+            Box::new(RuleNode::Rgb(rule_ref(2), rule_ref(2), rule_ref(2)))
+        };
+
+        rules.insert(
+            RuleId(0),
+            Rule {
+                items: vec![
+                    RuleItem {
+                        a: *rgb(),
+                        weight: 1.0 / 2.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Polar(rgb()),
+                        weight: 1.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Mirror(rgb()),
+                        weight: 1.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Rotate(3, rgb()),
+                        weight: 1.0 / 12.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Rotate(5, rgb()),
+                        weight: 1.0 / 12.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Rotate(6, rgb()),
+                        weight: 1.0 / 12.0,
+                    },
+                ],
+            },
+        );
+        rules.insert(
+            RuleId(1),
+            Rule {
+                items: vec![
+                    RuleItem {
+                        a: RuleNode::Lit(-1.0..=1.0),
+                        weight: 1.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::X,
+                        weight: 1.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Y,
+                        weight: 1.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Sqrt(
+                            RuleNode::Add(
+                                RuleNode::Pow(
+                                    RuleNode::Sub(
+                                        RuleNode::Const(0.0).into(),
+                                        RuleNode::Y.into(),
+                                    )
+                                    .into(),
+                                    RuleNode::Const(2.0).into(),
+                                )
+                                .into(),
+                                RuleNode::Pow(
+                                    RuleNode::Sub(
+                                        RuleNode::Const(0.0).into(),
+                                        RuleNode::X.into(),
+                                    )
+                                    .into(),
+                                    RuleNode::Const(2.0).into(),
+                                )
+                                .into(),
+                            )
+                            .into(),
+                        ),
+                        weight: 1.0,
+                    },
+                ],
+            },
+        );
+        rules.insert(
+            RuleId(2),
+            Rule {
+                items: vec![
+                    RuleItem {
+                        a: *rule_ref(1),
+                        weight: 1.0 / 4.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Add(rule_ref(2), rule_ref(2)),
+                        weight: 3.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Sub(rule_ref(2), rule_ref(2)),
+                        weight: 3.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Mul(rule_ref(2), rule_ref(2)),
+                        weight: 3.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Div(rule_ref(2), rule_ref(2)),
+                        weight: 3.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Mod(rule_ref(2), rule_ref(2)),
+                        weight: 3.0 / 8.0,
+                    },
+                    RuleItem {
+                        a: RuleNode::Sin(rule_ref(2)),
+                        weight: 3.0 / 8.0,
+                    },
+                ],
+            },
+        );
+
+        Self { rules }
+    }
+
     /// #Panics:
     ///     panic if has invalid rule reference or empty rule
     pub fn gen(
@@ -87,6 +210,13 @@ pub enum RuleNode {
     Exp(Box<RuleNode>),
     Sqrt(Box<RuleNode>),
     Mix(Box<RuleNode>, Box<RuleNode>, Box<RuleNode>, Box<RuleNode>),
+
+    /// See [`Node::Polar`]
+    Polar(Box<RuleNode>),
+    /// See [`Node::Mirror`]
+    Mirror(Box<RuleNode>),
+    /// See [`Node::Rotate`]
+    Rotate(u32, Box<RuleNode>),
 }
 
 impl RuleNode {
@@ -134,6 +264,9 @@ impl RuleNode {
             RuleNode::Mix(a, b, c, d) => {
                 Node::Mix(expand(a), expand(b), expand(c), expand(d))
             }
+            RuleNode::Polar(a) => Node::Polar(expand(a)),
+            RuleNode::Mirror(a) => Node::Mirror(expand(a)),
+            RuleNode::Rotate(n, a) => Node::Rotate(*n, expand(a)),
         }
     }
 }
@@ -0,0 +1,8 @@
+//! Generating and rendering the images, without windowing or file IO,
+//! so it also builds for the web
+
+pub mod grammar;
+pub mod node;
+pub mod render;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
@@ -1,15 +1,16 @@
-use core::f64;
-use std::{collections::HashMap, num::NonZeroU32, sync::Arc};
+use std::{num::NonZeroU32, sync::Arc};
 
 use anyhow::Context;
-use grammar::{Grammer, Rule, RuleId, RuleItem, RuleNode};
+use batch::BatchArgs;
+use clap::{Parser, Subcommand};
 use image::RgbImage;
-use node::{Node, Value};
 use rand::{random, rngs::StdRng, SeedableRng};
-use rayon::iter::{
-    IndexedParallelIterator, IntoParallelRefMutIterator, ParallelBridge,
-    ParallelIterator,
+use random_art::{
+    grammar::{Grammer, RuleId},
+    node::Node,
+    render::{Canvas, View},
 };
+use rayon::iter::{ParallelBridge, ParallelIterator};
 use softbuffer::Surface;
 use tracing::{debug_span, instrument, warn};
 use tracing_subscriber::{fmt::format::FmtSpan, EnvFilter};
@@ -22,15 +23,34 @@ use winit::{
     window::{Fullscreen, Window},
 };
 
-pub mod grammar;
-pub mod node;
+mod batch;
+
+#[derive(Debug, Parser)]
+#[command(about = "Images from random expressions")]
+struct Cli {
+    /// Opens the viewer if omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Render many seeds to files without a window
+    Batch(BatchArgs),
+}
 
 fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     tracing_subscriber::fmt::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .with_span_events(FmtSpan::ENTER | FmtSpan::CLOSE)
         .init();
 
+    if let Some(Command::Batch(args)) = &cli.command {
+        return batch::batch(args);
+    }
+
     let event_loop =
         EventLoop::new().expect("failed to create event loop");
 
@@ -40,58 +60,24 @@ fn main() -> anyhow::Result<()> {
         .run_app(&mut app)
         .expect("failed to run application");
 
-    //let mut img = RgbImage::new(512, 512);
-    //for _ in 0..100 {
-    //    let seed = random::<u64>();
-    //    // 17959246647187379579
-    //    gen_for_seed(seed, &grammar, &mut img)
-    //        .context("failed to generate")?;
-    //}
-    //gen_for_seed(
-    //    &mut img,
-    //    &grammar,
-    //    4573230301832827450,
-    //    (-1.0, -1.0),
-    //    (2.0, 2.0),
-    //)
-    //.context("failed to generate")?;
-
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 struct RenderParameters {
     save: bool,
     save_scaled: bool,
 
-    seed: u64,
-
-    offset: (f64, f64),
-    dimensions: (f64, f64),
-}
-
-impl Default for RenderParameters {
-    fn default() -> Self {
-        Self {
-            save: false,
-            save_scaled: false,
-
-            seed: 10409678234255179372,
-
-            offset: (-1.0, -1.0),
-            dimensions: (2.0, 2.0),
-        }
-    }
+    view: View,
 }
 
-const CANVAS_SIZE: usize = 512;
 struct AppState {
     window: Arc<Window>,
     surface: Surface<Arc<Window>, Arc<Window>>,
 
     grammar: Grammer,
 
-    render_buf: Box<[[f64; 3]; CANVAS_SIZE * CANVAS_SIZE]>,
+    canvas: Canvas,
 
     param: RenderParameters,
     last_param: Option<RenderParameters>,
@@ -102,112 +88,13 @@ impl AppState {
         window: Arc<Window>,
         surface: Surface<Arc<Window>, Arc<Window>>,
     ) -> Self {
-        let mut rules = HashMap::new();
-        let rule_ref = |id: u64| Box::new(RuleNode::Rule(RuleId(id)));
-
-        rules.insert(
-            RuleId(0),
-            Rule {
-                items: vec![RuleItem {
-                    a: RuleNode::Rgb(
-                        rule_ref(2),
-                        rule_ref(2),
-                        rule_ref(2),
-                    ),
-                    weight: 1.0,
-                }],
-            },
-        );
-        rules.insert(
-            RuleId(1),
-            Rule {
-                items: vec![
-                    RuleItem {
-                        a: RuleNode::Lit(-1.0..=1.0),
-                        weight: 1.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::X,
-                        weight: 1.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Y,
-                        weight: 1.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Sqrt(
-                            RuleNode::Add(
-                                RuleNode::Pow(
-                                    RuleNode::Sub(
-                                        RuleNode::Const(0.0).into(),
-                                        RuleNode::Y.into(),
-                                    )
-                                    .into(),
-                                    RuleNode::Const(2.0).into(),
-                                )
-                                .into(),
-                                RuleNode::Pow(
-                                    RuleNode::Sub(
-                                        RuleNode::Const(0.0).into(),
-                                        RuleNode::X.into(),
-                                    )
-                                    .into(),
-                                    RuleNode::Const(2.0).into(),
-                                )
-                                .into(),
-                            )
-                            .into(),
-                        ),
-                        weight: 1.0,
-                    },
-                ],
-            },
-        );
-        rules.insert(
-            RuleId(2),
-            Rule {
-                items: vec![
-                    RuleItem {
-                        a: *rule_ref(1),
-                        weight: 1.0 / 4.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Add(rule_ref(2), rule_ref(2)),
-                        weight: 3.0 / 8.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Sub(rule_ref(2), rule_ref(2)),
-                        weight: 3.0 / 8.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Mul(rule_ref(2), rule_ref(2)),
-                        weight: 3.0 / 8.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Div(rule_ref(2), rule_ref(2)),
-                        weight: 3.0 / 8.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Mod(rule_ref(2), rule_ref(2)),
-                        weight: 3.0 / 8.0,
-                    },
-                    RuleItem {
-                        a: RuleNode::Sin(rule_ref(2)),
-                        weight: 3.0 / 8.0,
-                    },
-                ],
-            },
-        );
-        let grammar = Grammer { rules };
-
-        let render_buf =
-            Box::new([Default::default(); CANVAS_SIZE * CANVAS_SIZE]);
+        let grammar = Grammer::art();
 
         Self {
             window,
             surface,
             grammar,
-            render_buf,
+            canvas: Canvas::default(),
             param: RenderParameters::default(),
             last_param: None,
         }
@@ -247,17 +134,8 @@ impl AppState {
             return;
         }
 
-        let size_f = CANVAS_SIZE as f64;
-        let x_scaler = size_f / width as f64;
-        let y_scaler = size_f / height as f64;
-        buf.par_iter_mut().enumerate().for_each(|(idx, px)| {
-            let x = idx as u32 % width;
-            let y = idx as u32 / width;
-            let x = (x as f64 * x_scaler) as usize;
-            let y = (y as f64 * y_scaler) as usize;
-
-            let v = Value::from(self.render_buf[y * CANVAS_SIZE + x]);
-            *px = u32::from_be_bytes(v.to_argb8());
+        self.canvas.scale_into(&mut buf, width, height, |v| {
+            u32::from_be_bytes(v.to_argb8())
         });
         drop(span);
 
@@ -293,10 +171,12 @@ impl AppState {
             save,
             save_scaled,
 
-            seed,
-
-            offset,
-            dimensions,
+            view:
+                View {
+                    seed,
+                    offset,
+                    dimensions,
+                },
         } = self.param;
         if save || save_scaled {
             let mut img = RgbImage::new(1024, 1024);
@@ -333,24 +213,7 @@ impl AppState {
             self.param.save = false;
             self.param.save_scaled = false;
         }
-        let mut rng = StdRng::seed_from_u64(seed);
-        let expr = self.grammar.gen(&mut rng, RuleId(0), 12);
-
-        let size = CANVAS_SIZE as u32;
-        let size_f = size as f64;
-        self.render_buf.par_iter_mut().enumerate().for_each(
-            |(idx, px)| {
-                let x = idx as u32 % size;
-                let y = idx as u32 / size;
-                let x = x as f64 / size_f;
-                let y = y as f64 / size_f;
-
-                let x = x * dimensions.0 + offset.0;
-                let y = y * dimensions.0 + offset.1;
-                let v = expr.eval(x, y);
-                *px = v.to_rgb();
-            },
-        );
+        self.canvas.render(&self.grammar, &self.param.view);
     }
 }
 
@@ -407,48 +270,10 @@ impl ApplicationHandler for RandomArt {
                     if event.state != ElementState::Released {
                         return;
                     }
-                    fn update_off(
-                        param: &mut RenderParameters,
-                        dx: f64,
-                        dy: f64,
-                    ) {
-                        param.offset.0 += dx;
-                        param.offset.1 += dy;
-                    }
-                    fn do_move(
-                        param: &mut RenderParameters,
-                        dir: (f64, f64),
-                        update_off: &impl Fn(&mut RenderParameters, f64, f64),
-                    ) {
-                        let scaler = 0.1;
-                        update_off(
-                            param,
-                            dir.0 * (scaler * param.dimensions.0).abs(),
-                            dir.1 * (scaler * param.dimensions.1).abs(),
-                        );
-                    }
-                    fn do_zoom(
-                        param: &mut RenderParameters,
-                        scale_up: bool,
-                        update_off: &impl Fn(&mut RenderParameters, f64, f64),
-                    ) {
-                        let scaler = 1.5;
-                        let scaler =
-                            if scale_up { scaler } else { 1.0 / scaler };
-
-                        let (w, h) = param.dimensions;
-                        let (new_w, new_h) = (w * scaler, h * scaler);
-                        param.dimensions = (new_w, new_h);
-                        let (dw, dh) = (new_w - w, new_h - h);
-                        update_off(
-                            param,
-                            dw / 2.0 * -1.0,
-                            dh / 2.0 * -1.0,
-                        )
-                    }
+                    let view = &mut state.param.view;
                     match event.physical_key {
                         PhysicalKey::Code(KeyCode::KeyR) => {
-                            state.param.seed = random::<u64>();
+                            view.seed = random::<u64>();
                         }
                         PhysicalKey::Code(KeyCode::Space) => {
                             //let _ = state.window.request_inner_size(
@@ -457,9 +282,7 @@ impl ApplicationHandler for RandomArt {
                             //        INIT_SIZE.0,
                             //    ),
                             //);
-                            let default = RenderParameters::default();
-                            state.param.offset = default.offset;
-                            state.param.dimensions = default.dimensions;
+                            view.reset_area();
                         }
                         PhysicalKey::Code(KeyCode::KeyF) => {
                             if state.window.fullscreen().is_none() {
@@ -484,38 +307,22 @@ impl ApplicationHandler for RandomArt {
                         }
                         // zooming and moving
                         PhysicalKey::Code(KeyCode::KeyU) => {
-                            do_zoom(&mut state.param, true, &update_off);
+                            view.zoom(true);
                         }
                         PhysicalKey::Code(KeyCode::KeyD) => {
-                            do_zoom(&mut state.param, false, &update_off);
+                            view.zoom(false);
                         }
                         PhysicalKey::Code(KeyCode::KeyH) => {
-                            do_move(
-                                &mut state.param,
-                                (-1.0, 0.0),
-                                &update_off,
-                            );
+                            view.move_by((-1.0, 0.0));
                         }
                         PhysicalKey::Code(KeyCode::KeyJ) => {
-                            do_move(
-                                &mut state.param,
-                                (0.0, 1.0),
-                                &update_off,
-                            );
+                            view.move_by((0.0, 1.0));
                         }
                         PhysicalKey::Code(KeyCode::KeyK) => {
-                            do_move(
-                                &mut state.param,
-                                (0.0, -1.0),
-                                &update_off,
-                            );
+                            view.move_by((0.0, -1.0));
                         }
                         PhysicalKey::Code(KeyCode::KeyL) => {
-                            do_move(
-                                &mut state.param,
-                                (1.0, 0.0),
-                                &update_off,
-                            );
+                            view.move_by((1.0, 0.0));
                         }
                         // saving to disk
                         PhysicalKey::Code(KeyCode::KeyS) => {
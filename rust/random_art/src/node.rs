@@ -1,4 +1,4 @@
-use core::f64;
+use core::f64::{self, consts::PI};
 use std::ops::{Add, Div, Mul, Sub};
 
 use serde::{Deserialize, Serialize};
@@ -21,6 +21,15 @@ pub enum Node {
     Exp(Box<Node>),
     Sqrt(Box<Node>),
     Mix(Box<Node>, Box<Node>, Box<Node>, Box<Node>),
+
+    /// Evaluated with the distance from the center as x
+    /// and the angle as y, both from -1 to 1
+    Polar(Box<Node>),
+    /// Evaluated on the right half, mirrored to the left
+    Mirror(Box<Node>),
+    /// Evaluated in one of `n` slices around the center,
+    /// repeated in the others
+    Rotate(u32, Box<Node>),
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -169,6 +178,29 @@ impl<T: Into<Self>> Div<T> for Value {
 }
 
 impl Node {
+    /// Nodes in the expression, this one included
+    pub fn node_count(&self) -> usize {
+        let children: &[&Node] = match self {
+            Node::X | Node::Y | Node::Lit(_) => &[],
+            Node::Sin(a)
+            | Node::Cos(a)
+            | Node::Exp(a)
+            | Node::Sqrt(a)
+            | Node::Polar(a)
+            | Node::Mirror(a)
+            | Node::Rotate(_, a) => &[a],
+            Node::Add(a, b)
+            | Node::Sub(a, b)
+            | Node::Mul(a, b)
+            | Node::Div(a, b)
+            | Node::Mod(a, b)
+            | Node::Pow(a, b) => &[a, b],
+            Node::Rgb(a, b, c) => &[a, b, c],
+            Node::Mix(a, b, c, d) => &[a, b, c, d],
+        };
+        1 + children.iter().map(|node| node.node_count()).sum::<usize>()
+    }
+
     pub fn eval(&self, x: f64, y: f64) -> Value {
         match self {
             Node::X => Value::Single(x),
@@ -215,6 +247,20 @@ impl Node {
 
                 (Value::from(1.0) - g) * c + g * d
             }
+            Node::Polar(a) => {
+                let r = x.hypot(y) * f64::consts::SQRT_2 - 1.0;
+                let theta = y.atan2(x) / PI;
+
+                a.eval(r, theta)
+            }
+            Node::Mirror(a) => a.eval(x.abs(), y),
+            Node::Rotate(n, a) => {
+                let slice = 2.0 * PI / (*n).max(1) as f64;
+                let r = x.hypot(y);
+                let theta = y.atan2(x).rem_euclid(slice);
+
+                a.eval(r * theta.cos(), r * theta.sin())
+            }
         }
     }
 }
@@ -226,3 +272,71 @@ pub fn clamp(x: f64) -> f64 {
 pub fn to_luma(x: f64) -> u8 {
     ((x + 1.0) / 2.0 * 255.0).round() as u8
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Different everywhere, so symmetries come from the node only
+    fn skewed() -> Box<Node> {
+        let y = Node::Mul(Box::new(Node::Y), Box::new(Node::Lit(0.3)));
+        Box::new(Node::Sub(Box::new(Node::X), Box::new(y)))
+    }
+
+    fn eval(node: &Node, x: f64, y: f64) -> f64 {
+        node.eval(x, y).to_single()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_mirror() {
+        let node = Node::Mirror(skewed());
+        for (x, y) in [(0.3, 0.5), (0.9, -0.2), (0.0, 1.0)] {
+            assert_eq!(eval(&node, x, y), eval(&node, -x, y));
+        }
+        assert_eq!(eval(&node, 0.3, 0.5), eval(&skewed(), 0.3, 0.5));
+    }
+
+    #[test]
+    fn test_rotate() {
+        for n in [1, 3, 7] {
+            let node = Node::Rotate(n, skewed());
+            let slice = 2.0 * PI / n as f64;
+            // away from the slice boundaries, where rounding may
+            // pick the other side
+            let (r, theta) = (0.8, slice / 3.0);
+            let at = |theta: f64| {
+                eval(&node, r * theta.cos(), r * theta.sin())
+            };
+            for turn in 1..n {
+                assert_close(at(theta), at(theta + slice * turn as f64));
+            }
+        }
+
+        // 0 slices is one slice instead of a division by zero
+        let node = Node::Rotate(0, Box::new(Node::X));
+        assert_close(eval(&node, 0.3, 0.4), 0.3);
+    }
+
+    #[test]
+    fn test_polar() {
+        let radius = Node::Polar(Box::new(Node::X));
+        assert_eq!(eval(&radius, 0.0, 0.0), -1.0);
+        assert_close(eval(&radius, 1.0, 1.0), 1.0);
+
+        let angle = Node::Polar(Box::new(Node::Y));
+        let values = (0..=64)
+            .map(|i| {
+                let theta = (i as f64 / 32.0 - 1.0) * PI * 0.999;
+                eval(&angle, theta.cos(), theta.sin())
+            })
+            .collect::<Vec<_>>();
+        assert!(values.iter().all(|v| (-1.0..=1.0).contains(v)));
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(values[0] < -0.99 && values[64] > 0.99);
+        assert_close(values[32], 0.0);
+    }
+}
@@ -0,0 +1,130 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator,
+};
+
+use crate::{
+    grammar::{Grammer, RuleId},
+    node::{Node, Value},
+};
+
+pub const CANVAS_SIZE: usize = 512;
+
+/// Which image is shown and which part of it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub seed: u64,
+
+    pub offset: (f64, f64),
+    pub dimensions: (f64, f64),
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            seed: 10409678234255179372,
+
+            offset: (-1.0, -1.0),
+            dimensions: (2.0, 2.0),
+        }
+    }
+}
+
+impl View {
+    /// Expression of the seed's image
+    pub fn expr(&self, grammar: &Grammer) -> Node {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        grammar.gen(&mut rng, RuleId(0), 12)
+    }
+
+    /// Point of the image at `(x, y)` from 0 to 1 across the view
+    pub fn to_image_space(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x * self.dimensions.0 + self.offset.0,
+            y * self.dimensions.0 + self.offset.1,
+        )
+    }
+
+    /// Show the whole image again
+    pub fn reset_area(&mut self) {
+        let default = Self::default();
+        self.offset = default.offset;
+        self.dimensions = default.dimensions;
+    }
+
+    /// Move by a tenth of the shown area in `dir`
+    pub fn move_by(&mut self, dir: (f64, f64)) {
+        let scaler = 0.1;
+        self.offset.0 += dir.0 * (scaler * self.dimensions.0).abs();
+        self.offset.1 += dir.1 * (scaler * self.dimensions.1).abs();
+    }
+
+    /// Grow or shrink the shown area around its center
+    pub fn zoom(&mut self, scale_up: bool) {
+        let scaler = 1.5;
+        let scaler = if scale_up { scaler } else { 1.0 / scaler };
+
+        let (w, h) = self.dimensions;
+        let (new_w, new_h) = (w * scaler, h * scaler);
+        self.dimensions = (new_w, new_h);
+        let (dw, dh) = (new_w - w, new_h - h);
+        self.offset.0 -= dw / 2.0;
+        self.offset.1 -= dh / 2.0;
+    }
+}
+
+/// Image for a view at a fixed resolution, scaled to the output
+pub struct Canvas {
+    buf: Box<[[f64; 3]; CANVAS_SIZE * CANVAS_SIZE]>,
+}
+
+impl Default for Canvas {
+    fn default() -> Self {
+        Self {
+            // built on the heap, the array is too big for the 1 MiB
+            // stack of wasm
+            buf: vec![[0.0; 3]; CANVAS_SIZE * CANVAS_SIZE]
+                .into_boxed_slice()
+                .try_into()
+                .unwrap(),
+        }
+    }
+}
+
+impl Canvas {
+    pub fn render(&mut self, grammar: &Grammer, view: &View) {
+        let expr = view.expr(grammar);
+
+        let size = CANVAS_SIZE as u32;
+        let size_f = size as f64;
+        self.buf.par_iter_mut().enumerate().for_each(|(idx, px)| {
+            let x = idx as u32 % size;
+            let y = idx as u32 / size;
+            let (x, y) =
+                view.to_image_space(x as f64 / size_f, y as f64 / size_f);
+            let v = expr.eval(x, y);
+            *px = v.to_rgb();
+        });
+    }
+
+    /// Fill `out`, `width` pixels a row, with the image stretched over it
+    pub fn scale_into<T: Send>(
+        &self,
+        out: &mut [T],
+        width: u32,
+        height: u32,
+        to_px: impl Fn(Value) -> T + Sync,
+    ) {
+        let size_f = CANVAS_SIZE as f64;
+        let x_scaler = size_f / width as f64;
+        let y_scaler = size_f / height as f64;
+        out.par_iter_mut().enumerate().for_each(|(idx, px)| {
+            let x = idx as u32 % width;
+            let y = idx as u32 / width;
+            let x = (x as f64 * x_scaler) as usize;
+            let y = (y as f64 * y_scaler) as usize;
+
+            *px = to_px(Value::from(self.buf[y * CANVAS_SIZE + x]));
+        });
+    }
+}
@@ -0,0 +1,72 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    grammar::Grammer,
+    render::{Canvas, View},
+};
+
+/// Random art for a canvas, driven by `web/index.html`
+#[wasm_bindgen]
+pub struct WebArt {
+    grammar: Grammer,
+    canvas: Canvas,
+
+    view: View,
+    last_view: Option<View>,
+}
+
+impl Default for WebArt {
+    fn default() -> Self {
+        Self {
+            grammar: Grammer::art(),
+            canvas: Canvas::default(),
+            view: View::default(),
+            last_view: None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl WebArt {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle a released key by its `KeyboardEvent.code`,
+    /// same keys as the native window
+    ///
+    /// Return: whether the image needs drawing again
+    pub fn key(&mut self, code: &str) -> bool {
+        let view = &mut self.view;
+        match code {
+            "KeyR" => view.seed = rand::random::<u64>(),
+            "Space" => view.reset_area(),
+            // zooming and moving
+            "KeyU" => view.zoom(true),
+            "KeyD" => view.zoom(false),
+            "KeyH" => view.move_by((-1.0, 0.0)),
+            "KeyJ" => view.move_by((0.0, 1.0)),
+            "KeyK" => view.move_by((0.0, -1.0)),
+            "KeyL" => view.move_by((1.0, 0.0)),
+            _ => return false,
+        }
+        true
+    }
+
+    /// RGBA pixels of the image scaled to `width` x `height`,
+    /// for an `ImageData`
+    pub fn pixels(&mut self, width: u32, height: u32) -> Vec<u8> {
+        if self.last_view != Some(self.view) {
+            self.canvas.render(&self.grammar, &self.view);
+            self.last_view = Some(self.view);
+        }
+
+        let mut pixels = vec![[0; 4]; (width * height) as usize];
+        self.canvas.scale_into(&mut pixels, width, height, |v| {
+            let [r, g, b] = v.to_rgb8();
+            [r, g, b, u8::MAX]
+        });
+        pixels.into_flattened()
+    }
+}
@@ -0,0 +1,83 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use text_diff::{
+    granularity::{diff_text, Granularity, DEFAULT_MAX_CELLS},
+    Algorithm,
+};
+
+/// The crate's own sources repeated, about 20k lines
+fn large_source() -> String {
+    let sources = [
+        include_str!("../src/lib.rs"),
+        include_str!("../src/differ.rs"),
+        include_str!("../src/myers.rs"),
+        include_str!("../src/stream.rs"),
+        include_str!("../src/patch.rs"),
+    ]
+    .concat();
+    sources.repeat(20_000 / sources.lines().count() + 1)
+}
+
+/// Every 50th line edited and every 300th removed
+fn edit_lines(text: &str) -> String {
+    text.lines()
+        .enumerate()
+        .filter(|(idx, _)| idx % 300 != 299)
+        .map(|(idx, line)| match idx % 50 {
+            0 => format!("{line} // edited\n"),
+            _ => format!("{line}\n"),
+        })
+        .collect()
+}
+
+fn long_line(len: usize) -> String {
+    (0..len)
+        .map(|idx| char::from(b'a' + (idx * 7 % 26) as u8))
+        .collect()
+}
+
+fn bench_lines(c: &mut Criterion) {
+    let source = large_source();
+    let target = edit_lines(&source);
+
+    let mut group = c.benchmark_group("large file lines");
+    group.sample_size(10);
+    for algorithm in [Algorithm::Myers, Algorithm::Patience] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{algorithm:?}")),
+            &algorithm,
+            |b, &algorithm| {
+                b.iter(|| text_diff::diff_lines_with(algorithm, black_box(&source), &target))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_long_lines(c: &mut Criterion) {
+    let mut group = c.benchmark_group("near identical long line");
+    for len in [1_000, 10_000] {
+        let source = long_line(len);
+        let mut target = source.clone();
+        target.replace_range(len / 3..len / 3 + 5, "XXXXX");
+        target.insert_str(len * 2 / 3, "inserted");
+
+        group.bench_with_input(BenchmarkId::new("chars", len), &len, |b, _| {
+            b.iter(|| text_diff::diff_chars(black_box(&source), &target))
+        });
+        group.bench_with_input(BenchmarkId::new("matrix capped", len), &len, |b, _| {
+            b.iter(|| {
+                diff_text(
+                    Algorithm::WagnerFischer,
+                    Granularity::Chars,
+                    DEFAULT_MAX_CELLS,
+                    black_box(&source),
+                    &target,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lines, bench_long_lines);
+criterion_main!(benches);
@@ -0,0 +1,335 @@
+//! Postprocessing of edit scripts to make them easier to read,
+//! modeled after diff-match-patch's semantic cleanup.
+//!
+//! Every pass keeps the script valid, applying it still turns the source into the target,
+//! but [`Edit::Substitute`] is split into a deletion and an insertion.
+
+use crate::{Edit, EditScript};
+
+/// Consecutive edits of the same kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Run<T> {
+    /// Source and target item of each unchanged edit
+    Unchange(Vec<(T, T)>),
+    /// Deletions are always emitted before insertions
+    Change { delete: Vec<T>, insert: Vec<T> },
+}
+
+fn to_runs<T>(script: EditScript<T>) -> Vec<Run<T>> {
+    let mut runs = vec![];
+    for edit in script {
+        match (edit, runs.last_mut()) {
+            (Edit::Unchange { source, target }, Some(Run::Unchange(items))) => {
+                items.push((source, target))
+            }
+            (Edit::Unchange { source, target }, _) => {
+                runs.push(Run::Unchange(vec![(source, target)]))
+            }
+            (edit, Some(Run::Change { delete, insert })) => push_change(edit, delete, insert),
+            (edit, _) => {
+                let (mut delete, mut insert) = (vec![], vec![]);
+                push_change(edit, &mut delete, &mut insert);
+                runs.push(Run::Change { delete, insert });
+            }
+        }
+    }
+    runs
+}
+
+fn push_change<T>(edit: Edit<T>, delete: &mut Vec<T>, insert: &mut Vec<T>) {
+    match edit {
+        Edit::Unchange { .. } => unreachable!("unchanged items aren't part of a change"),
+        Edit::Delete { source } => delete.push(source),
+        Edit::Insert { target } => insert.push(target),
+        Edit::Substitute { source, target } => {
+            delete.push(source);
+            insert.push(target);
+        }
+    }
+}
+
+fn from_runs<T>(runs: Vec<Run<T>>) -> EditScript<T> {
+    let mut script = vec![];
+    for run in runs {
+        match run {
+            Run::Unchange(items) => script.extend(
+                items
+                    .into_iter()
+                    .map(|(source, target)| Edit::Unchange { source, target }),
+            ),
+            Run::Change { delete, insert } => {
+                script.extend(delete.into_iter().map(|source| Edit::Delete { source }));
+                script.extend(insert.into_iter().map(|target| Edit::Insert { target }));
+            }
+        }
+    }
+    script
+}
+
+/// Reorder every block of consecutive changes into all of its deletions followed by
+/// all of its insertions, so interleaved single item edits show up as one block.
+pub fn cleanup_merge<T>(script: EditScript<T>) -> EditScript<T> {
+    from_runs(to_runs(script))
+}
+
+/// Merge, then replace short unchanged runs sandwiched between larger changes with
+/// a deletion and insertion of the same items, and finally slide the remaining
+/// single sided changes to the position `boundary_score` rates highest.
+///
+/// `boundary_score(before, after)` rates cutting between two adjacent items,
+/// `None` is the start or end of the input.
+pub fn cleanup_semantic<T: PartialEq + Clone>(
+    script: EditScript<T>,
+    boundary_score: impl Fn(Option<&T>, Option<&T>) -> u32,
+) -> EditScript<T> {
+    let mut runs = to_runs(script);
+    eliminate_trivial_equalities(&mut runs);
+    shift_boundaries(&mut runs, boundary_score);
+    from_runs(runs)
+}
+
+fn change_len<T>(run: &Run<T>) -> Option<usize> {
+    match run {
+        Run::Change { delete, insert } => Some(delete.len().max(insert.len())),
+        Run::Unchange(_) => None,
+    }
+}
+
+fn eliminate_trivial_equalities<T: Clone>(runs: &mut Vec<Run<T>>) {
+    let mut idx = 1;
+    while idx + 1 < runs.len() {
+        let trivial = match (
+            change_len(&runs[idx - 1]),
+            &runs[idx],
+            change_len(&runs[idx + 1]),
+        ) {
+            (Some(before), Run::Unchange(items), Some(after)) => {
+                items.len() <= before && items.len() <= after
+            }
+            _ => false,
+        };
+        if !trivial {
+            idx += 1;
+            continue;
+        }
+
+        let merged = runs.drain(idx - 1..=idx + 1).collect::<Vec<_>>();
+        let (mut delete, mut insert) = (vec![], vec![]);
+        for run in merged {
+            match run {
+                Run::Unchange(items) => {
+                    for (source, target) in items {
+                        delete.push(source);
+                        insert.push(target);
+                    }
+                }
+                Run::Change {
+                    delete: run_delete,
+                    insert: run_insert,
+                } => {
+                    delete.extend(run_delete);
+                    insert.extend(run_insert);
+                }
+            }
+        }
+        runs.insert(idx - 1, Run::Change { delete, insert });
+
+        // the merged change may make the previous equality trivial as well
+        idx = idx.saturating_sub(1).max(1);
+    }
+}
+
+fn shift_boundaries<T: PartialEq + Clone>(
+    runs: &mut Vec<Run<T>>,
+    boundary_score: impl Fn(Option<&T>, Option<&T>) -> u32,
+) {
+    for idx in 1..runs.len().saturating_sub(1) {
+        let (Run::Unchange(before), Run::Unchange(after)) = (&runs[idx - 1], &runs[idx + 1]) else {
+            continue;
+        };
+        let (edit, is_delete) = match &runs[idx] {
+            Run::Change { delete, insert } if insert.is_empty() => (delete, true),
+            Run::Change { delete, insert } if delete.is_empty() => (insert, false),
+            _ => continue,
+        };
+
+        // the edit can move one item right whenever its first item equals the one after it,
+        // the unchanged items of the other side stay in order
+        let side = |items: &[(T, T)]| {
+            items
+                .iter()
+                .map(|(source, target)| if is_delete { source } else { target }.clone())
+                .collect::<Vec<_>>()
+        };
+        let other_side = before
+            .iter()
+            .chain(after)
+            .map(|(source, target)| if is_delete { target } else { source }.clone())
+            .collect::<Vec<_>>();
+        let combined = [side(before).as_slice(), edit, &side(after)].concat();
+        let len = edit.len();
+        let mut start = before.len();
+        while start > 0 && combined[start - 1] == combined[start - 1 + len] {
+            start -= 1;
+        }
+
+        let score = |start: usize| {
+            let end = start + len;
+            boundary_score(
+                start.checked_sub(1).map(|it| &combined[it]),
+                combined.get(start),
+            ) + boundary_score(combined.get(end - 1), combined.get(end))
+        };
+        let mut best = (score(start), start);
+        while start + len < combined.len() && combined[start] == combined[start + len] {
+            start += 1;
+            // prefer later positions on ties, like diff-match-patch
+            best = best.max((score(start), start));
+        }
+
+        let start = best.1;
+        let edit = combined[start..start + len].to_vec();
+        let pair = |items: &[T], other_side: &[T]| {
+            items
+                .iter()
+                .cloned()
+                .zip(other_side.iter().cloned())
+                .map(|(item, other)| {
+                    if is_delete {
+                        (item, other)
+                    } else {
+                        (other, item)
+                    }
+                })
+                .collect()
+        };
+        runs[idx - 1] = Run::Unchange(pair(&combined[..start], &other_side[..start]));
+        runs[idx + 1] = Run::Unchange(pair(&combined[start + len..], &other_side[start..]));
+        runs[idx] = if is_delete {
+            Run::Change {
+                delete: edit,
+                insert: vec![],
+            }
+        } else {
+            Run::Change {
+                delete: vec![],
+                insert: edit,
+            }
+        };
+    }
+
+    runs.retain(|run| !matches!(run, Run::Unchange(items) if items.is_empty()));
+}
+
+/// Boundary score for character diffs, prefers cutting at line breaks, then whitespace,
+/// then punctuation.
+pub fn char_boundary_score(before: Option<&char>, after: Option<&char>) -> u32 {
+    let (Some(&before), Some(&after)) = (before, after) else {
+        return 5;
+    };
+
+    if before == '\n' || after == '\n' {
+        4
+    } else if before.is_whitespace() || after.is_whitespace() {
+        2
+    } else if !before.is_alphanumeric() || !after.is_alphanumeric() {
+        1
+    } else {
+        0
+    }
+}
+
+/// Boundary score for line diffs, prefers cutting at blank lines.
+pub fn line_boundary_score<S: AsRef<str>>(before: Option<&S>, after: Option<&S>) -> u32 {
+    let (Some(before), Some(after)) = (before, after) else {
+        return 2;
+    };
+
+    let is_blank = |line: &S| line.as_ref().trim().is_empty();
+    (is_blank(before) || is_blank(after)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff_chars;
+
+    fn render(script: &[Edit<char>]) -> String {
+        script
+            .iter()
+            .map(|edit| match edit {
+                Edit::Unchange { source, .. } => source.to_string(),
+                Edit::Delete { source } => format!("-{source}"),
+                Edit::Insert { target } => format!("+{target}"),
+                Edit::Substitute { .. } => unreachable!(),
+            })
+            .collect()
+    }
+
+    fn sides(script: &[Edit<char>]) -> (String, String) {
+        (
+            script.iter().filter_map(Edit::source).collect(),
+            script.iter().filter_map(Edit::target).collect(),
+        )
+    }
+
+    #[test]
+    fn test_merge() {
+        let script = vec![
+            Edit::Delete { source: 'a' },
+            Edit::Insert { target: 'b' },
+            Edit::Delete { source: 'c' },
+            Edit::unchanged('x'),
+        ];
+        assert_eq!(render(&cleanup_merge(script)), "-a-c+bx");
+    }
+
+    #[test]
+    fn test_eliminates_trivial_equalities() {
+        let script = diff_chars("test source", "test target");
+        let cleaned = cleanup_semantic(script.clone(), char_boundary_score);
+
+        assert_eq!(sides(&cleaned), sides(&script));
+        assert_eq!(render(&cleaned), "test -s-o-u-r-c-e+t+a+r+g+e+t");
+    }
+
+    #[test]
+    fn test_shifts_to_word_boundary() {
+        let script = diff_chars("The cat came.", "The cat came. The cat came.");
+        let cleaned = cleanup_semantic(script.clone(), char_boundary_score);
+
+        assert_eq!(sides(&cleaned), sides(&script));
+        assert_eq!(
+            render(&cleaned),
+            "The cat came.+ +T+h+e+ +c+a+t+ +c+a+m+e+."
+        );
+    }
+
+    #[test]
+    fn test_keeps_unchanged_targets() {
+        // unchanged items matched case insensitively
+        let script = vec![
+            Edit::Unchange {
+                source: 'a',
+                target: 'A',
+            },
+            Edit::Delete { source: 'a' },
+            Edit::unchanged(' '),
+            Edit::Unchange {
+                source: 'b',
+                target: 'B',
+            },
+        ];
+        let cleaned = cleanup_semantic(script.clone(), char_boundary_score);
+
+        assert_eq!(sides(&cleaned), ("aa b".to_string(), "A B".to_string()));
+        assert_eq!(render(&cleaned), "-aa b");
+        assert_eq!(
+            cleaned[1],
+            Edit::Unchange {
+                source: 'a',
+                target: 'A'
+            }
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Text diffs at a chosen granularity, with a cap on the cost of the matrix backend.
+
+use crate::{diff_slices_capped, matrix_cells, split_words, Algorithm, EditScript};
+
+/// Cells of the [`Algorithm::WagnerFischer`] matrix allowed by default, 64 MiB
+pub const DEFAULT_MAX_CELLS: usize = 1 << 22;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Granularity {
+    Chars,
+    Words,
+    Lines,
+}
+
+impl Granularity {
+    /// Split text into tokens, concatenating them gives back the text,
+    /// lines keep their line endings.
+    pub fn split(self, text: &str) -> Vec<&str> {
+        match self {
+            Granularity::Chars => text
+                .char_indices()
+                .map(|(idx, c)| &text[idx..idx + c.len_utf8()])
+                .collect(),
+            Granularity::Words => split_words(text),
+            Granularity::Lines => text.split_inclusive('\n').collect(),
+        }
+    }
+
+    pub fn coarser(self) -> Option<Self> {
+        match self {
+            Granularity::Chars => Some(Granularity::Words),
+            Granularity::Words => Some(Granularity::Lines),
+            Granularity::Lines => None,
+        }
+    }
+}
+
+/// Diff at `granularity`, unless the matrix backend would need more than `max_cells`
+/// cells, then at the finest coarser granularity that fits.
+/// If not even lines fit, [`diff_slices_capped`] diffs them with [`Algorithm::Myers`] instead.
+///
+/// Returns the granularity actually used.
+pub fn diff_text<'a>(
+    algorithm: Algorithm,
+    mut granularity: Granularity,
+    max_cells: usize,
+    source: &'a str,
+    target: &'a str,
+) -> (Granularity, EditScript<&'a str>) {
+    loop {
+        let (source, target) = (granularity.split(source), granularity.split(target));
+        let fits = matrix_cells(source.len(), target.len()) <= max_cells;
+        match granularity.coarser() {
+            Some(coarser) if algorithm == Algorithm::WagnerFischer && !fits => {
+                granularity = coarser
+            }
+            _ => {
+                return (
+                    granularity,
+                    diff_slices_capped(algorithm, max_cells, &source, &target),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Edit;
+
+    #[test]
+    fn test_fallback() {
+        let source = "the quick brown fox\njumps over\nthe lazy dog\n";
+        let target = "the quick red fox\njumps over\nthe lazy cat\n";
+        let text = |script: &[Edit<&str>]| {
+            (
+                script
+                    .iter()
+                    .filter_map(Edit::source)
+                    .copied()
+                    .collect::<String>(),
+                script
+                    .iter()
+                    .filter_map(Edit::target)
+                    .copied()
+                    .collect::<String>(),
+            )
+        };
+
+        for (max_cells, expected) in [
+            (usize::MAX, Granularity::Chars),
+            (500, Granularity::Words),
+            (20, Granularity::Lines),
+            (0, Granularity::Lines),
+        ] {
+            let (granularity, script) = diff_text(
+                Algorithm::WagnerFischer,
+                Granularity::Chars,
+                max_cells,
+                source,
+                target,
+            );
+            assert_eq!(granularity, expected, "{max_cells}");
+            assert_eq!(text(&script), (source.to_string(), target.to_string()));
+        }
+    }
+}
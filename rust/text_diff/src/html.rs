@@ -0,0 +1,134 @@
+//! HTML output, for embedding diffs in reports or viewing them in a browser.
+
+use std::fmt::{Display, Write};
+
+use crate::Edit;
+
+/// Colors of the inline stylesheet, any CSS color value works
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub background: String,
+    pub foreground: String,
+    pub delete: String,
+    pub insert: String,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            background: "#ffffff".to_string(),
+            foreground: "#24292f".to_string(),
+            delete: "#ffd7d5".to_string(),
+            insert: "#ccffd8".to_string(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            background: "#0d1117".to_string(),
+            foreground: "#e6edf3".to_string(),
+            delete: "#5a1e1e".to_string(),
+            insert: "#1b4721".to_string(),
+        }
+    }
+
+    /// Stylesheet for the markup of [`render_html`]
+    pub fn css(&self) -> String {
+        format!(
+            "pre.diff {{ background: {}; color: {}; padding: 0.5em; white-space: pre-wrap; }}\n\
+             pre.diff del {{ background: {}; text-decoration: line-through; }}\n\
+             pre.diff ins {{ background: {}; text-decoration: none; }}\n",
+            self.background, self.foreground, self.delete, self.insert
+        )
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+/// Render a diff as a `<pre class="diff">` block, with deletions in `<del>`
+/// and insertions in `<ins>`, preceded by a `<style>` block if there's a theme.
+///
+/// Items are written back to back, line diffs need their line endings.
+pub fn render_html<T: Display>(script: &[Edit<T>], theme: Option<&Theme>) -> String {
+    let mut out = String::new();
+    if let Some(theme) = theme {
+        out.push_str("<style>\n");
+        out.push_str(&theme.css());
+        out.push_str("</style>\n");
+    }
+    out.push_str("<pre class=\"diff\">");
+
+    // deletions and insertions of the current change, written as one tag each
+    let (mut deleted, mut inserted) = (String::new(), String::new());
+    let flush = |out: &mut String, deleted: &mut String, inserted: &mut String| {
+        for (tag, text) in [("del", deleted), ("ins", inserted)] {
+            if !text.is_empty() {
+                write!(out, "<{tag}>{}</{tag}>", escape(text)).unwrap();
+                text.clear();
+            }
+        }
+    };
+
+    for edit in script {
+        if let Edit::Unchange { source, .. } = edit {
+            flush(&mut out, &mut deleted, &mut inserted);
+            out.push_str(&escape(&source.to_string()));
+            continue;
+        }
+        if let Some(source) = edit.source() {
+            write!(deleted, "{source}").unwrap();
+        }
+        if let Some(target) = edit.target() {
+            write!(inserted, "{target}").unwrap();
+        }
+    }
+    flush(&mut out, &mut deleted, &mut inserted);
+
+    out.push_str("</pre>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_html() {
+        let script = vec![
+            Edit::unchanged("if a "),
+            Edit::Substitute {
+                source: "<",
+                target: "<=",
+            },
+            Edit::unchanged(" b"),
+            Edit::Insert { target: " && c" },
+            Edit::unchanged(" {"),
+        ];
+        assert_eq!(
+            render_html(&script, None),
+            "<pre class=\"diff\">if a <del>&lt;</del><ins>&lt;=</ins> b<ins> &amp;&amp; c</ins> {</pre>\n"
+        );
+
+        let themed = render_html(&script, Some(&Theme::dark()));
+        assert!(themed.starts_with("<style>\n"));
+        assert!(themed.contains(&Theme::dark().insert));
+    }
+}
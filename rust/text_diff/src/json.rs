@@ -0,0 +1,472 @@
+//! Structural diff of JSON documents, by keys and array elements instead of by text.
+//! TOML documents are converted to the same tree and diffed the same way.
+
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value};
+
+use crate::{diff_slices, Edit};
+
+/// One change at a JSON Pointer path, applied in order like a JSON Patch,
+/// so array indices are those after the changes before it
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonChange {
+    Add {
+        path: String,
+        value: Value,
+    },
+    Remove {
+        path: String,
+        old: Value,
+    },
+    Replace {
+        path: String,
+        old: Value,
+        value: Value,
+    },
+    /// An array element moved to another index of the same array
+    Move {
+        from: String,
+        path: String,
+    },
+}
+
+impl JsonChange {
+    pub fn path(&self) -> &str {
+        match self {
+            JsonChange::Add { path, .. }
+            | JsonChange::Remove { path, .. }
+            | JsonChange::Replace { path, .. }
+            | JsonChange::Move { path, .. } => path,
+        }
+    }
+}
+
+/// Parse both documents and diff them structurally
+pub fn diff_json(source: &str, target: &str) -> serde_json::Result<Vec<JsonChange>> {
+    let source = serde_json::from_str(source)?;
+    let target = serde_json::from_str(target)?;
+    Ok(diff_values(&source, &target))
+}
+
+/// Parse both TOML documents as JSON values and diff them structurally
+pub fn diff_toml(source: &str, target: &str) -> Result<Vec<JsonChange>, toml::de::Error> {
+    let source = toml_to_json(source.parse()?);
+    let target = toml_to_json(target.parse()?);
+    Ok(diff_values(&source, &target))
+}
+
+/// Tables become objects, datetimes and non-finite floats become strings
+pub fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(int) => int.into(),
+        toml::Value::Float(float) => serde_json::Number::from_f64(float)
+            .map_or_else(|| Value::String(float.to_string()), Value::Number),
+        toml::Value::Boolean(bool) => Value::Bool(bool),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => array.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect(),
+    }
+}
+
+pub fn diff_values(source: &Value, target: &Value) -> Vec<JsonChange> {
+    let mut changes = vec![];
+    diff_at(&mut changes, String::new(), source, target);
+    changes
+}
+
+fn diff_at(changes: &mut Vec<JsonChange>, path: String, source: &Value, target: &Value) {
+    match (source, target) {
+        _ if source == target => {}
+        (Value::Object(source), Value::Object(target)) => {
+            diff_objects(changes, &path, source, target)
+        }
+        (Value::Array(source), Value::Array(target)) => diff_arrays(changes, &path, source, target),
+        _ => changes.push(JsonChange::Replace {
+            path,
+            old: source.clone(),
+            value: target.clone(),
+        }),
+    }
+}
+
+fn diff_objects(
+    changes: &mut Vec<JsonChange>,
+    path: &str,
+    source: &Map<String, Value>,
+    target: &Map<String, Value>,
+) {
+    for (key, old) in source {
+        let path = child_path(path, key);
+        match target.get(key) {
+            Some(value) => diff_at(changes, path, old, value),
+            None => changes.push(JsonChange::Remove {
+                path,
+                old: old.clone(),
+            }),
+        }
+    }
+    for (key, value) in target {
+        if !source.contains_key(key) {
+            changes.push(JsonChange::Add {
+                path: child_path(path, key),
+                value: value.clone(),
+            });
+        }
+    }
+}
+
+/// Element of the array being patched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Source(usize),
+    Target(usize),
+}
+
+/// Diff by the sequence differ, removed elements inserted elsewhere unchanged
+/// become moves, and removed and inserted elements of a change pair up to be
+/// diffed in place.
+///
+/// Positions come from replaying the changes on the elements, which always
+/// holds the target elements placed so far in order, with the source elements
+/// not handled yet in between.
+fn diff_arrays(changes: &mut Vec<JsonChange>, path: &str, source: &[Value], target: &[Value]) {
+    let script = diff_slices(&indexed(source), &indexed(target));
+    let removed = script
+        .iter()
+        .filter_map(|edit| match edit {
+            Edit::Delete { source } => Some(source.0),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // target index to the source index it moved from
+    let mut moved_from = vec![None; target.len()];
+    let mut is_moved = vec![false; source.len()];
+    for edit in &script {
+        let Edit::Insert {
+            target: Indexed(target_idx, value),
+        } = edit
+        else {
+            continue;
+        };
+        let source_idx = removed
+            .iter()
+            .copied()
+            .find(|&idx| !is_moved[idx] && source[idx] == **value);
+        if let Some(source_idx) = source_idx {
+            is_moved[source_idx] = true;
+            moved_from[*target_idx] = Some(source_idx);
+        }
+    }
+
+    let mut elements = (0..source.len()).map(Element::Source).collect::<Vec<_>>();
+    let position = |elements: &[Element], element| {
+        elements
+            .iter()
+            .position(|&it| it == element)
+            .expect("element is in the array")
+    };
+    // the last element placed at its target position
+    let mut last_placed = None;
+    let next_position = |elements: &[Element], last_placed: Option<Element>| {
+        last_placed.map_or(0, |it| position(elements, it) + 1)
+    };
+
+    let mut idx = 0;
+    while idx < script.len() {
+        if let Edit::Unchange { source, .. } = &script[idx] {
+            last_placed = Some(Element::Source(source.0));
+            idx += 1;
+            continue;
+        }
+
+        let change_len = script[idx..]
+            .iter()
+            .take_while(|edit| !matches!(edit, Edit::Unchange { .. }))
+            .count();
+        let change = &script[idx..idx + change_len];
+        let mut deleted = change
+            .iter()
+            .filter_map(Edit::source)
+            .map(|it| it.0)
+            .filter(|&idx| !is_moved[idx]);
+        let inserted = change.iter().filter_map(Edit::target).map(|it| it.0);
+
+        for target_idx in inserted {
+            if let Some(source_idx) = moved_from[target_idx] {
+                let element = Element::Source(source_idx);
+                let from = position(&elements, element);
+                elements.remove(from);
+                let to = next_position(&elements, last_placed);
+                elements.insert(to, element);
+                changes.push(JsonChange::Move {
+                    from: child_path(path, &from.to_string()),
+                    path: child_path(path, &to.to_string()),
+                });
+                last_placed = Some(element);
+            } else if let Some(source_idx) = deleted.next() {
+                let element = Element::Source(source_idx);
+                let at = position(&elements, element);
+                let child = child_path(path, &at.to_string());
+                diff_at(changes, child, &source[source_idx], &target[target_idx]);
+                last_placed = Some(element);
+            } else {
+                let element = Element::Target(target_idx);
+                let at = next_position(&elements, last_placed);
+                elements.insert(at, element);
+                changes.push(JsonChange::Add {
+                    path: child_path(path, &at.to_string()),
+                    value: target[target_idx].clone(),
+                });
+                last_placed = Some(element);
+            }
+        }
+        for source_idx in deleted {
+            let at = position(&elements, Element::Source(source_idx));
+            elements.remove(at);
+            changes.push(JsonChange::Remove {
+                path: child_path(path, &at.to_string()),
+                old: source[source_idx].clone(),
+            });
+        }
+
+        idx += change_len;
+    }
+}
+
+/// Array element with its index, compared by the value only
+#[derive(Debug, Clone, Copy)]
+struct Indexed<'a>(usize, &'a Value);
+
+impl PartialEq for Indexed<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.1 == other.1
+    }
+}
+
+fn indexed(values: &[Value]) -> Vec<Indexed<'_>> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| Indexed(idx, value))
+        .collect()
+}
+
+/// JSON Pointer to `key` under `path`, RFC 6901
+fn child_path(path: &str, key: &str) -> String {
+    format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// The changes as a JSON Patch document, RFC 6902
+pub fn to_json_patch(changes: &[JsonChange]) -> Value {
+    changes
+        .iter()
+        .map(|change| match change {
+            JsonChange::Add { path, value } => {
+                json!({ "op": "add", "path": path, "value": value })
+            }
+            JsonChange::Remove { path, .. } => json!({ "op": "remove", "path": path }),
+            JsonChange::Replace { path, value, .. } => {
+                json!({ "op": "replace", "path": path, "value": value })
+            }
+            JsonChange::Move { from, path } => {
+                json!({ "op": "move", "from": from, "path": path })
+            }
+        })
+        .collect()
+}
+
+/// The changes as a tree of the paths, with `+` for added, `-` for removed,
+/// `~` for replaced and `>` for moved values
+pub fn render_tree(changes: &[JsonChange]) -> String {
+    let mut out = String::new();
+    let mut last_parents: Vec<&str> = vec![];
+    for change in changes {
+        let mut segments = change.path().split('/').skip(1).collect::<Vec<_>>();
+        // the whole document has no key
+        let name = segments.pop().unwrap_or("/");
+        let parents = segments;
+
+        let common = last_parents
+            .iter()
+            .zip(&parents)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for (depth, parent) in parents.iter().enumerate().skip(common) {
+            writeln!(
+                out,
+                "{:indent$}{}",
+                "",
+                unescape(parent),
+                indent = depth * 2
+            )
+            .unwrap();
+        }
+        let indent = parents.len() * 2;
+        let name = unescape(name);
+        match change {
+            JsonChange::Add { value, .. } => writeln!(out, "{:indent$}+ {name}: {value}", ""),
+            JsonChange::Remove { old, .. } => writeln!(out, "{:indent$}- {name}: {old}", ""),
+            JsonChange::Replace { old, value, .. } => {
+                writeln!(out, "{:indent$}~ {name}: {old} -> {value}", "")
+            }
+            JsonChange::Move { from, .. } => {
+                let from = unescape(from.rsplit('/').next().unwrap_or(""));
+                writeln!(out, "{:indent$}> {name}: from {from}", "")
+            }
+        }
+        .unwrap();
+        last_parents = parents;
+    }
+    out
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply a JSON Patch, only the ops [`to_json_patch`] emits
+    fn apply_patch(mut doc: Value, patch: &Value) -> Value {
+        fn split(path: &str) -> (String, String) {
+            let (parent, key) = path.rsplit_once('/').unwrap();
+            (parent.to_string(), unescape(key))
+        }
+        fn remove(doc: &mut Value, path: &str) -> Value {
+            let (parent, key) = split(path);
+            match doc.pointer_mut(&parent).unwrap() {
+                Value::Object(map) => map.remove(&key).unwrap(),
+                Value::Array(array) => array.remove(key.parse().unwrap()),
+                _ => panic!("not a container"),
+            }
+        }
+        fn add(doc: &mut Value, path: &str, value: Value) {
+            let (parent, key) = split(path);
+            match doc.pointer_mut(&parent).unwrap() {
+                Value::Object(map) => {
+                    map.insert(key, value);
+                }
+                Value::Array(array) => array.insert(key.parse().unwrap(), value),
+                _ => panic!("not a container"),
+            }
+        }
+
+        for op in patch.as_array().unwrap() {
+            let path = op["path"].as_str().unwrap();
+            match op["op"].as_str().unwrap() {
+                "add" => add(&mut doc, path, op["value"].clone()),
+                "remove" => {
+                    remove(&mut doc, path);
+                }
+                "replace" if path.is_empty() => doc = op["value"].clone(),
+                "replace" => *doc.pointer_mut(path).unwrap() = op["value"].clone(),
+                "move" => {
+                    let value = remove(&mut doc, op["from"].as_str().unwrap());
+                    add(&mut doc, path, value);
+                }
+                op => panic!("unexpected op {op}"),
+            }
+        }
+        doc
+    }
+
+    fn check_patch(source: Value, target: Value) -> Vec<JsonChange> {
+        let changes = diff_values(&source, &target);
+        assert_eq!(apply_patch(source, &to_json_patch(&changes)), target);
+        changes
+    }
+
+    #[test]
+    fn test_diff_objects() {
+        let changes = check_patch(
+            json!({ "a": 1, "b": { "c": true, "d/e": "x" }, "f": null }),
+            json!({ "a": 2, "b": { "c": true, "d/e": "y" }, "g": [1] }),
+        );
+        assert_eq!(
+            to_json_patch(&changes),
+            json!([
+                { "op": "replace", "path": "/a", "value": 2 },
+                { "op": "replace", "path": "/b/d~1e", "value": "y" },
+                { "op": "remove", "path": "/f" },
+                { "op": "add", "path": "/g", "value": [1] },
+            ])
+        );
+        assert_eq!(
+            render_tree(&changes),
+            "~ a: 1 -> 2\nb\n  ~ d/e: \"x\" -> \"y\"\n- f: null\n+ g: [1]\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_arrays() {
+        check_patch(json!([1, 2, 3]), json!([1, 3, 4]));
+        check_patch(json!([]), json!([1, 2]));
+        check_patch(json!([1, 2]), json!([]));
+        check_patch(
+            json!([{ "id": 1, "v": "a" }, { "id": 2, "v": "b" }]),
+            json!([{ "id": 1, "v": "a" }, { "id": 2, "v": "c" }, 5]),
+        );
+        check_patch(json!({ "a": [[1, 2], [3]] }), json!({ "a": [[1], [3, 4]] }));
+        check_patch(json!(1), json!({ "a": 1 }));
+    }
+
+    #[test]
+    fn test_diff_array_moves() {
+        let changes = check_patch(json!(["a", "b", "c", "d"]), json!(["d", "a", "b", "c"]));
+        assert_eq!(
+            changes,
+            vec![JsonChange::Move {
+                from: "/3".to_string(),
+                path: "/0".to_string()
+            }]
+        );
+
+        let changes = check_patch(json!(["a", "b", "c", "d"]), json!(["b", "c", "d", "a"]));
+        assert!(changes
+            .iter()
+            .all(|change| matches!(change, JsonChange::Move { .. })));
+
+        check_patch(json!([1, 2, 3, 4, 5, 6]), json!([6, 2, 7, 1, 4, 3]));
+        check_patch(json!([1, 1, 2, 1]), json!([2, 1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn test_diff_json() {
+        assert_eq!(
+            diff_json("{\"a\": [1]}", "{ \"a\" : [ 1 ] }").unwrap(),
+            vec![]
+        );
+        assert!(diff_json("{", "{}").is_err());
+    }
+
+    #[test]
+    fn test_diff_toml() {
+        let source = "title = \"a\"\n[server]\nport = 80\nhosts = [\"x\", \"y\"]\n";
+        let target =
+            "title = \"a\"\nwhen = 2024-06-01\n\n[server]\nhosts = [\"y\", \"x\"]\nport = 8080\n";
+        assert_eq!(
+            to_json_patch(&diff_toml(source, target).unwrap()),
+            json!([
+                { "op": "move", "from": "/server/hosts/0", "path": "/server/hosts/1" },
+                { "op": "replace", "path": "/server/port", "value": 8080 },
+                { "op": "add", "path": "/when", "value": "2024-06-01" },
+            ])
+        );
+
+        // same tree as the equivalent JSON
+        assert_eq!(
+            toml_to_json(source.parse().unwrap()),
+            json!({ "title": "a", "server": { "port": 80, "hosts": ["x", "y"] } })
+        );
+        assert_eq!(diff_toml("a = 1\nb = 2", "b = 2\n  a = 1").unwrap(), vec![]);
+        assert!(diff_toml("a = ", "").is_err());
+    }
+}
@@ -0,0 +1,358 @@
+#![warn(missing_debug_implementations)]
+
+use std::hash::Hash;
+
+pub use differ::{Differ, EditInfo, EditType};
+pub use granularity::DEFAULT_MAX_CELLS;
+pub use options::DiffOptions;
+
+pub mod cleanup;
+pub mod differ;
+pub mod granularity;
+pub mod html;
+pub mod json;
+pub mod myers;
+pub mod options;
+pub mod patch;
+pub mod patience;
+pub mod session;
+pub mod similarity;
+pub mod stream;
+pub mod unified;
+
+/// Owned counterpart of [`EditInfo`], independent of the [`Differ`] it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Edit<T> {
+    /// Items which compare equal, they only differ when the comparison ignores something
+    Unchange {
+        source: T,
+        target: T,
+    },
+    Delete {
+        source: T,
+    },
+    Insert {
+        target: T,
+    },
+    Substitute {
+        source: T,
+        target: T,
+    },
+}
+
+impl<T> Edit<T> {
+    pub fn edit_type(&self) -> EditType {
+        match self {
+            Edit::Unchange { .. } => EditType::N,
+            Edit::Delete { .. } => EditType::D,
+            Edit::Insert { .. } => EditType::I,
+            Edit::Substitute { .. } => EditType::S,
+        }
+    }
+
+    /// Value on the source side, `None` for insertions
+    pub fn source(&self) -> Option<&T> {
+        match self {
+            Edit::Unchange { source, .. }
+            | Edit::Delete { source }
+            | Edit::Substitute { source, .. } => Some(source),
+            Edit::Insert { .. } => None,
+        }
+    }
+
+    /// Value on the target side, `None` for deletions
+    pub fn target(&self) -> Option<&T> {
+        match self {
+            Edit::Unchange { target, .. }
+            | Edit::Insert { target }
+            | Edit::Substitute { target, .. } => Some(target),
+            Edit::Delete { .. } => None,
+        }
+    }
+
+    /// Like [`Edit::map`], but from a reference
+    pub fn clone_as<U>(&self, mut f: impl FnMut(&T) -> U) -> Edit<U> {
+        match self {
+            Edit::Unchange { source, target } => Edit::Unchange {
+                source: f(source),
+                target: f(target),
+            },
+            Edit::Delete { source } => Edit::Delete { source: f(source) },
+            Edit::Insert { target } => Edit::Insert { target: f(target) },
+            Edit::Substitute { source, target } => Edit::Substitute {
+                source: f(source),
+                target: f(target),
+            },
+        }
+    }
+
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Edit<U> {
+        match self {
+            Edit::Unchange { source, target } => Edit::Unchange {
+                source: f(source),
+                target: f(target),
+            },
+            Edit::Delete { source } => Edit::Delete { source: f(source) },
+            Edit::Insert { target } => Edit::Insert { target: f(target) },
+            Edit::Substitute { source, target } => Edit::Substitute {
+                source: f(source),
+                target: f(target),
+            },
+        }
+    }
+}
+
+impl<T: Clone> Edit<T> {
+    /// Unchanged item which is the same on both sides
+    pub fn unchanged(item: T) -> Self {
+        Edit::Unchange {
+            source: item.clone(),
+            target: item,
+        }
+    }
+}
+
+impl<T: Clone> From<EditInfo<'_, T>> for Edit<T> {
+    fn from(value: EditInfo<'_, T>) -> Self {
+        match value {
+            EditInfo::Unchange { source } => Edit::unchanged(source.clone()),
+            EditInfo::Delete { source } => Edit::Delete {
+                source: source.clone(),
+            },
+            EditInfo::Insert { target } => Edit::Insert {
+                target: target.clone(),
+            },
+            EditInfo::Substitute { source, target } => Edit::Substitute {
+                source: source.clone(),
+                target: target.clone(),
+            },
+        }
+    }
+}
+
+/// Sequence of edits turning the source into the target
+pub type EditScript<T> = Vec<Edit<T>>;
+
+/// Backend used to compute insert/delete edit scripts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Myers' O(ND) algorithm in linear space, fast when the inputs are similar
+    #[default]
+    Myers,
+    /// Patience diff, anchors on unique items, more readable for code with moved blocks
+    Patience,
+    /// Full O(NM) matrix, see [`Differ`], which is also the only substitution aware backend
+    WagnerFischer,
+}
+
+/// Shortest edit script between two slices, using only insertions and deletions.
+pub fn diff_slices<T: PartialEq + Clone>(source: &[T], target: &[T]) -> EditScript<T> {
+    script_from_ops(myers::diff(source, target), source, target)
+}
+
+/// Diff with `algorithm`, [`Algorithm::WagnerFischer`] falls back to [`Algorithm::Myers`]
+/// when its matrix would need more than [`DEFAULT_MAX_CELLS`] cells.
+pub fn diff_slices_with<T: Hash + Eq + Clone>(
+    algorithm: Algorithm,
+    source: &[T],
+    target: &[T],
+) -> EditScript<T> {
+    diff_slices_capped(algorithm, DEFAULT_MAX_CELLS, source, target)
+}
+
+/// Like [`diff_slices_with`], with a cap of `max_cells` matrix cells
+pub fn diff_slices_capped<T: Hash + Eq + Clone>(
+    algorithm: Algorithm,
+    max_cells: usize,
+    source: &[T],
+    target: &[T],
+) -> EditScript<T> {
+    match algorithm {
+        Algorithm::Myers => diff_slices(source, target),
+        Algorithm::Patience => script_from_ops(patience::diff(source, target), source, target),
+        Algorithm::WagnerFischer if matrix_cells(source.len(), target.len()) > max_cells => {
+            diff_slices(source, target)
+        }
+        Algorithm::WagnerFischer => {
+            let differ = Differ::new(source.iter().collect(), target.iter().collect(), true);
+            differ
+                .gen_diff()
+                .into_iter()
+                .map(|edit| Edit::from(edit).map(|it| (*it).clone()))
+                .collect()
+        }
+    }
+}
+
+/// Cells of the [`Differ`] matrix for inputs of these lengths
+pub fn matrix_cells(source_len: usize, target_len: usize) -> usize {
+    (source_len + 1).saturating_mul(target_len + 1)
+}
+
+/// Pair up ops without substitutions with the items they apply to
+fn script_from_ops<T: Clone>(ops: Vec<EditType>, source: &[T], target: &[T]) -> EditScript<T> {
+    let (mut source_iter, mut target_iter) = (source.iter().cloned(), target.iter().cloned());
+    ops.into_iter()
+        .map(|op| {
+            let mut source = || source_iter.next().expect("edit script past source");
+            let mut target = || target_iter.next().expect("edit script past target");
+            match op {
+                EditType::N => Edit::Unchange {
+                    source: source(),
+                    target: target(),
+                },
+                EditType::D => Edit::Delete { source: source() },
+                EditType::I => Edit::Insert { target: target() },
+                EditType::S => unreachable!("only the matrix backend substitutes"),
+            }
+        })
+        .collect()
+}
+
+/// Character level diff
+pub fn diff_chars(source: &str, target: &str) -> EditScript<char> {
+    let source = source.chars().collect::<Vec<_>>();
+    let target = target.chars().collect::<Vec<_>>();
+    diff_slices(&source, &target)
+}
+
+/// Word level diff, runs of whitespace and punctuation are tokens of their own
+pub fn diff_words<'a>(source: &'a str, target: &'a str) -> EditScript<&'a str> {
+    diff_slices(&split_words(source), &split_words(target))
+}
+
+/// Split text into runs of alphanumeric characters, runs of whitespace,
+/// and single other characters, concatenating the tokens gives back the text.
+pub fn split_words(text: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let continues = chars
+            .peek()
+            .is_some_and(|&(_, next)| class(c) != Class::Other && class(next) == class(c));
+        if !continues {
+            let end = idx + c.len_utf8();
+            tokens.push(&text[start..end]);
+            start = end;
+        }
+    }
+    tokens
+}
+
+/// Line level diff, line endings are not part of the lines
+pub fn diff_lines<'a>(source: &'a str, target: &'a str) -> EditScript<&'a str> {
+    let source = source.lines().collect::<Vec<_>>();
+    let target = target.lines().collect::<Vec<_>>();
+    diff_slices(&source, &target)
+}
+
+pub fn diff_lines_with<'a>(
+    algorithm: Algorithm,
+    source: &'a str,
+    target: &'a str,
+) -> EditScript<&'a str> {
+    let source = source.lines().collect::<Vec<_>>();
+    let target = target.lines().collect::<Vec<_>>();
+    diff_slices_with(algorithm, &source, &target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply a script to its source, which must give back the target
+    fn apply<T: Clone>(script: &[Edit<T>]) -> (Vec<T>, Vec<T>) {
+        let source = script.iter().filter_map(Edit::source).cloned().collect();
+        let target = script.iter().filter_map(Edit::target).cloned().collect();
+        (source, target)
+    }
+
+    #[test]
+    fn test_diff_chars() {
+        let script = diff_chars("test source", "test target");
+        let (source, target) = apply(&script);
+        assert_eq!(source.into_iter().collect::<String>(), "test source");
+        assert_eq!(target.into_iter().collect::<String>(), "test target");
+        assert!(script.iter().all(|edit| edit.edit_type() != EditType::S));
+        assert_eq!(
+            &script[..5],
+            "test ".chars().map(Edit::unchanged).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let script = diff_lines("a\nb\nc", "a\nc\nd");
+        assert_eq!(
+            script,
+            vec![
+                Edit::unchanged("a"),
+                Edit::Delete { source: "b" },
+                Edit::unchanged("c"),
+                Edit::Insert { target: "d" },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_algorithms_agree_on_distance() {
+        let source = "kitten sitting on the mitten".chars().collect::<Vec<_>>();
+        let target = "sitting kitten in the mittens".chars().collect::<Vec<_>>();
+
+        let changes = |algorithm| {
+            diff_slices_with(algorithm, &source, &target)
+                .iter()
+                .filter(|edit| edit.edit_type() != EditType::N)
+                .count()
+        };
+        assert_eq!(changes(Algorithm::Myers), changes(Algorithm::WagnerFischer));
+        assert!(changes(Algorithm::Patience) >= changes(Algorithm::Myers));
+    }
+
+    #[test]
+    fn test_matrix_cap() {
+        // the full matrix would need 10^10 cells, more than fits into memory
+        let source = vec![0u8; 100_000];
+        let mut target = source.clone();
+        target[50_000] = 1;
+
+        let script = diff_slices_with(Algorithm::WagnerFischer, &source, &target);
+        assert_eq!(script, diff_slices(&source, &target));
+        assert_eq!(apply(&script), (source, target));
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words("let x  = foo(1, 2);"),
+            ["let", " ", "x", "  ", "=", " ", "foo", "(", "1", ",", " ", "2", ")", ";"]
+        );
+        assert_eq!(split_words(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_diff_slices_empty() {
+        assert_eq!(diff_slices::<u8>(&[], &[]), vec![]);
+        assert_eq!(
+            diff_slices(&[], &[1, 2]),
+            vec![Edit::Insert { target: 1 }, Edit::Insert { target: 2 }]
+        );
+    }
+}
@@ -1,203 +1,335 @@
-use std::fmt::Display;
-
-#[warn(missing_debug_implementations)]
-
-fn main() {
-    let input_1 = r#"test source"#;
-    let input_2 = r#"test target"#;
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::{anyhow, Context};
+use clap::{Parser, ValueEnum};
+use text_diff::{
+    cleanup::{self, char_boundary_score},
+    html::{render_html, Theme},
+    json, stream, unified, Algorithm, DiffOptions, Edit,
+};
+
+#[derive(Debug, Parser)]
+#[command(about = "Compare two files, or two directories recursively")]
+struct Cli {
+    source: PathBuf,
+    target: PathBuf,
+
+    /// Granularity of the colored diff
+    #[arg(long, value_enum, default_value_t = Mode::Lines)]
+    mode: Mode,
+    /// Algorithm used for line diffs
+    #[arg(long, value_enum, default_value_t = LineAlgorithm::Myers)]
+    algorithm: LineAlgorithm,
+    /// Ignore all whitespace
+    #[arg(short = 'w', long)]
+    ignore_all_space: bool,
+    /// Ignore case differences
+    #[arg(short, long)]
+    ignore_case: bool,
+    /// Ignore changes which only insert or delete blank lines
+    #[arg(short = 'B', long)]
+    ignore_blank_lines: bool,
+
+    /// Print changes inline with colors, the default
+    #[arg(long, conflicts_with = "unified")]
+    color: bool,
+    /// Print a unified diff with N lines of context, always line based
+    #[arg(short, long, value_name = "N")]
+    unified: Option<usize>,
+    /// Diff huge files without loading them into memory, prints a unified diff
+    #[arg(long, conflicts_with_all = ["color", "mode"])]
+    stream: bool,
+    /// Print the diff as an HTML page
+    #[arg(long, conflicts_with_all = ["color", "unified", "stream"])]
+    html: bool,
+    /// Print the structural diff as a JSON Patch, implies `--mode json` unless it's `toml`
+    #[arg(long, conflicts_with_all = ["unified", "stream", "html"])]
+    json_patch: bool,
+}
 
-    fn print_diff<T: PartialEq + Display>(diff: Vec<EditInfo<'_, T>>) {
-        for edit in diff {
-            match edit {
-                EditInfo::Unchange { source } => {
-                    print!("{source}");
-                }
-                EditInfo::Delete { source } => {
-                    print!("[91m{source}[m");
-                }
-                EditInfo::Insert { target } => {
-                    print!("[92m{target}[m");
-                }
-                EditInfo::Substitute { target, .. } => {
-                    print!("[93m{target}[m");
-                }
-            }
+impl Cli {
+    /// Options for line and word diffs, char diffs ignore nothing
+    fn diff_options(&self) -> DiffOptions {
+        DiffOptions {
+            algorithm: self.algorithm.into(),
+            ignore_whitespace: self.ignore_all_space,
+            ignore_case: self.ignore_case,
+            ignore_blank_lines: self.ignore_blank_lines,
+            normalizer: None,
         }
     }
+}
 
-    let differ = Differ::new(input_1.chars().collect(), input_2.chars().collect(), true);
-    print_diff(differ.gen_diff());
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Chars,
+    Words,
+    Lines,
+    /// Parse both files as JSON and diff them by keys and array elements
+    Json,
+    /// Parse both files as TOML and diff them like JSON
+    Toml,
+}
 
-    println!("\n==================================================================\n");
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LineAlgorithm {
+    /// Shortest diff
+    Myers,
+    /// Anchors on unique lines, often more readable for code with moved blocks
+    Patience,
+}
+
+impl From<LineAlgorithm> for Algorithm {
+    fn from(value: LineAlgorithm) -> Self {
+        match value {
+            LineAlgorithm::Myers => Algorithm::Myers,
+            LineAlgorithm::Patience => Algorithm::Patience,
+        }
+    }
+}
 
-    let differ = Differ::new(input_1.lines().collect(), input_2.lines().collect(), false);
-    for edit in differ.gen_diff() {
+fn print_diff<T: Display>(diff: &[Edit<T>]) {
+    for edit in diff {
         match edit {
-            EditInfo::Unchange { source } => {
+            Edit::Unchange { source, .. } => {
                 print!("{source}");
             }
-            EditInfo::Delete { source } => {
-                print!("[91m{source}[m");
+            Edit::Delete { source } => {
+                print!("\x1b[91m{source}\x1b[m");
             }
-            EditInfo::Insert { target } => {
-                print!("[92m{target}[m");
+            Edit::Insert { target } => {
+                print!("\x1b[92m{target}\x1b[m");
             }
-            EditInfo::Substitute { source, target } => {
-                let differ = Differ::new(source.chars().collect(), target.chars().collect(), true);
-                let diff = differ.gen_diff();
-
-                print_diff(diff)
+            Edit::Substitute { target, .. } => {
+                print!("\x1b[93m{target}\x1b[m");
             }
         }
-        println!();
     }
 }
 
-pub struct Differ<T: PartialEq> {
-    disable_substitution: bool,
+/// Lines diff, changed lines which pair up are shown with an inline char diff
+fn print_line_diff(options: &DiffOptions, source: &str, target: &str) {
+    let script = cleanup::cleanup_merge(options.diff_lines(source, target));
 
-    source: Vec<T>,
-    target: Vec<T>,
-    height: usize,
+    let mut idx = 0;
+    while idx < script.len() {
+        if let Edit::Unchange { source, .. } = script[idx] {
+            println!("{source}");
+            idx += 1;
+            continue;
+        }
 
-    distance_matrix: Vec<(u64, EditType)>,
+        let change_len = script[idx..]
+            .iter()
+            .take_while(|edit| !matches!(edit, Edit::Unchange { .. }))
+            .count();
+        let change = &script[idx..idx + change_len];
+        let deleted = change.iter().filter_map(Edit::source).collect::<Vec<_>>();
+        let inserted = change.iter().filter_map(Edit::target).collect::<Vec<_>>();
+
+        for pair in 0..deleted.len().max(inserted.len()) {
+            match (deleted.get(pair), inserted.get(pair)) {
+                (Some(source), Some(target)) => print_diff(&cleanup::cleanup_semantic(
+                    text_diff::diff_chars(source, target),
+                    char_boundary_score,
+                )),
+                (Some(source), None) => print_diff(&[Edit::Delete { source }]),
+                (None, Some(target)) => print_diff(&[Edit::Insert { target }]),
+                (None, None) => unreachable!(),
+            }
+            println!();
+        }
+        idx += change_len;
+    }
 }
 
-impl<T: PartialEq> Differ<T> {
-    pub fn new(source: Vec<T>, target: Vec<T>, disable_substitution: bool) -> Self {
-        let height = target.len() + 1;
-        let distance_matrix = vec![(0, EditType::N); (source.len() + 1) * height];
-        let mut this = Self {
-            disable_substitution,
+/// Returns whether the files differ
+fn diff_files(cli: &Cli, source_path: &Path, target_path: &Path) -> anyhow::Result<bool> {
+    if cli.stream {
+        let mut out = std::io::stdout().lock();
+        return stream::diff_files(source_path, target_path, cli.unified.unwrap_or(3), &mut out)
+            .context("failed to diff files");
+    }
 
-            source,
-            target,
-            height,
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+    };
+    let (source, target) = (read(source_path)?, read(target_path)?);
+    if source == target {
+        return Ok(false);
+    }
+    if cli.json_patch || matches!(cli.mode, Mode::Json | Mode::Toml) {
+        return diff_json(cli, &source, &target);
+    }
+    let options = cli.diff_options();
+    if options.ignores_anything() && !options.has_changes(&options.diff_lines(&source, &target)) {
+        return Ok(false);
+    }
 
-            distance_matrix,
+    if cli.html {
+        let theme = Some(Theme::default());
+        let html = match cli.mode {
+            Mode::Chars => render_html(
+                &cleanup::cleanup_semantic(
+                    text_diff::diff_chars(&source, &target),
+                    char_boundary_score,
+                ),
+                theme.as_ref(),
+            ),
+            Mode::Words => render_html(
+                &cleanup::cleanup_merge(options.diff_words(&source, &target)),
+                theme.as_ref(),
+            ),
+            Mode::Lines => {
+                let script = options.diff_lines(&source, &target);
+                let script = cleanup::cleanup_merge(script)
+                    .iter()
+                    .map(|edit| edit.clone_as(|line| format!("{line}\n")))
+                    .collect::<Vec<_>>();
+                render_html(&script, theme.as_ref())
+            }
+            Mode::Json | Mode::Toml => unreachable!("JSON and TOML are diffed by diff_json"),
         };
-        this.calc_distance();
-        this
+        print!("{html}");
+        return Ok(true);
     }
 
-    fn coord_to_idx(&self, idx_source: usize, idx_target: usize) -> usize {
-        idx_source * self.height + idx_target
+    if let Some(context) = cli.unified {
+        let script = cleanup::cleanup_merge(options.diff_lines(&source, &target));
+        print!(
+            "{}",
+            unified::format(
+                &source_path.display().to_string(),
+                &target_path.display().to_string(),
+                &script,
+                context
+            )
+        );
+        return Ok(true);
     }
 
-    fn get(&self, idx_source: usize, idx_target: usize) -> (u64, EditType) {
-        self.distance_matrix[self.coord_to_idx(idx_source, idx_target)]
+    match cli.mode {
+        Mode::Chars => print_diff(&cleanup::cleanup_semantic(
+            text_diff::diff_chars(&source, &target),
+            char_boundary_score,
+        )),
+        Mode::Words => print_diff(&cleanup::cleanup_merge(
+            options.diff_words(&source, &target),
+        )),
+        Mode::Lines => print_line_diff(&options, &source, &target),
+        Mode::Json | Mode::Toml => unreachable!("JSON and TOML are diffed by diff_json"),
     }
-
-    fn set(&mut self, idx_source: usize, idx_target: usize, new: (u64, EditType)) {
-        let idx = self.coord_to_idx(idx_source, idx_target);
-        self.distance_matrix[idx] = new;
+    if cli.mode != Mode::Lines && !target.ends_with('\n') {
+        println!();
     }
 
-    // https://en.wikipedia.org/wiki/Wagner%E2%80%93Fischer_algorithm
-    fn calc_distance(&mut self) {
-        for idx_source in 1..=self.source.len() {
-            self.set(idx_source, 0, (idx_source as u64, EditType::D));
-        }
-
-        for idx_target in 1..=self.target.len() {
-            self.set(0, idx_target, (idx_target as u64, EditType::I));
-        }
+    Ok(true)
+}
 
-        for idx_source in 1..=self.source.len() {
-            for idx_target in 1..=self.target.len() {
-                let deletion = (self.get(idx_source - 1, idx_target).0 + 1, EditType::D);
-                let insertion = (self.get(idx_source, idx_target - 1).0 + 1, EditType::I);
-                let substitution = self.get(idx_source - 1, idx_target - 1).0;
-                let substitution = if self.source[idx_source - 1] == self.target[idx_target - 1] {
-                    (substitution, EditType::N)
-                } else {
-                    (
-                        substitution + if self.disable_substitution { 114514 } else { 1 },
-                        EditType::S,
-                    )
-                };
+/// Returns whether the documents differ
+fn diff_json(cli: &Cli, source: &str, target: &str) -> anyhow::Result<bool> {
+    let changes = if cli.mode == Mode::Toml {
+        json::diff_toml(source, target).context("failed to parse TOML")?
+    } else {
+        json::diff_json(source, target).context("failed to parse JSON")?
+    };
+    if changes.is_empty() {
+        return Ok(false);
+    }
 
-                let result = if deletion.0 <= insertion.0 && deletion.0 <= substitution.0 {
-                    deletion
-                } else if insertion.0 <= deletion.0 && insertion.0 <= substitution.0 {
-                    insertion
-                } else {
-                    substitution
-                };
+    if cli.json_patch {
+        println!("{:#}", json::to_json_patch(&changes));
+        return Ok(true);
+    }
+    for line in json::render_tree(&changes).lines() {
+        let color = match line.trim_start().chars().next() {
+            Some('+') => "92",
+            Some('-') => "91",
+            Some('~' | '>') => "93",
+            _ => {
+                println!("{line}");
+                continue;
+            }
+        };
+        println!("\x1b[{color}m{line}\x1b[m");
+    }
+    Ok(true)
+}
 
-                self.set(idx_source, idx_target, result);
+/// Relative paths of every file under `root`
+fn list_files(root: &Path) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let mut files = BTreeSet::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry.context("failed to read directory entry")?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.insert(path.strip_prefix(root)?.to_path_buf());
             }
         }
     }
+    Ok(files)
+}
 
-    pub fn gen_diff(&self) -> Vec<EditInfo<'_, T>> {
-        let mut cur_pos = (self.source.len(), self.target.len());
-        let mut diff = vec![];
-
-        while cur_pos.0 > 0 || cur_pos.1 > 0 {
-            let cur = self.get(cur_pos.0, cur_pos.1);
-
-            let source = &self.source[cur_pos.0.saturating_sub(1)];
-            let target = &self.target[cur_pos.1.saturating_sub(1)];
-
-            let v = match cur.1 {
-                EditType::N => EditInfo::Unchange { source },
-                EditType::D => EditInfo::Delete { source },
-                EditType::I => EditInfo::Insert { target },
-                EditType::S => EditInfo::Substitute { source, target },
-            };
-            diff.push(v);
+/// Returns whether the directories differ
+fn diff_dirs(cli: &Cli) -> anyhow::Result<bool> {
+    let source_files = list_files(&cli.source)?;
+    let target_files = list_files(&cli.target)?;
+
+    let mut differs = false;
+    for path in source_files.union(&target_files) {
+        let (source, target) = (cli.source.join(path), cli.target.join(path));
+        match (source_files.contains(path), target_files.contains(path)) {
+            (true, false) => println!("\x1b[91mremoved\x1b[m:  {}", path.display()),
+            (false, true) => println!("\x1b[92madded\x1b[m:    {}", path.display()),
+            _ => {
+                let read = |path: &Path| {
+                    std::fs::read(path)
+                        .with_context(|| format!("failed to read {}", path.display()))
+                };
+                if read(&source)? == read(&target)? {
+                    continue;
+                }
 
-            cur_pos = match cur.1 {
-                EditType::N | EditType::S => (cur_pos.0 - 1, cur_pos.1 - 1),
-                EditType::D => (cur_pos.0 - 1, cur_pos.1),
-                EditType::I => (cur_pos.0, cur_pos.1 - 1),
-            };
-            // println!("{cur_pos:?} {cur:?}");
+                println!("\x1b[93mmodified\x1b[m: {}", path.display());
+                if cli.unified.is_some() {
+                    if let Err(err) = diff_files(cli, &source, &target) {
+                        eprintln!("{err:#}");
+                    }
+                }
+            }
         }
-
-        diff.reverse();
-
-        diff
-    }
-
-    pub fn step_count(&self) -> u64 {
-        self.distance_matrix[self.distance_matrix.len() - 1].0
+        differs = true;
     }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum EditType {
-    N, // None
-    D, // Delete
-    I, // Insert
-    S, // Substitute
-}
 
-#[derive(Debug, Clone, Copy)]
-pub enum EditInfo<'value, T> {
-    Unchange {
-        source: &'value T,
-    },
-    Delete {
-        source: &'value T,
-    },
-    Insert {
-        target: &'value T,
-    },
-    Substitute {
-        source: &'value T,
-        target: &'value T,
-    },
+    Ok(differs)
 }
 
-impl<'value, T> EditInfo<'value, T> {
-    pub fn to_num(&self) -> u64 {
-        match self {
-            EditInfo::Unchange { .. } => 0,
-            EditInfo::Delete { .. } => 1,
-            EditInfo::Insert { .. } => 2,
-            EditInfo::Substitute { .. } => 3,
+/// Exit code follows `diff`: 0 if equal, 1 if different, 2 on errors
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match (cli.source.is_dir(), cli.target.is_dir()) {
+        (true, true) => diff_dirs(&cli),
+        (false, false) => diff_files(&cli, &cli.source, &cli.target),
+        _ => Err(anyhow!("can't compare a file with a directory")),
+    };
+
+    match result {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::from(2)
         }
     }
 }
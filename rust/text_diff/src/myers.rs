@@ -0,0 +1,217 @@
+//! Myers' O(ND) difference algorithm with the linear space refinement,
+//! <http://www.xmailserver.org/diff2.pdf>.
+//!
+//! The bisection follows diff-match-patch: find the middle snake by running
+//! the forward and reverse searches at once, then recurse on both halves.
+
+use crate::differ::EditType;
+
+/// Shortest edit script as a list of [`EditType::N`], [`EditType::D`] and [`EditType::I`].
+pub fn diff<T: PartialEq>(source: &[T], target: &[T]) -> Vec<EditType> {
+    let mut ops = Vec::with_capacity(source.len().max(target.len()));
+    diff_rec(source, target, &mut ops);
+    ops
+}
+
+fn push_n(ops: &mut Vec<EditType>, edit_type: EditType, count: usize) {
+    ops.extend(std::iter::repeat_n(edit_type, count));
+}
+
+fn diff_rec<T: PartialEq>(source: &[T], target: &[T], ops: &mut Vec<EditType>) {
+    let prefix = source
+        .iter()
+        .zip(target)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (source, target) = (&source[prefix..], &target[prefix..]);
+
+    let suffix = source
+        .iter()
+        .rev()
+        .zip(target.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let source = &source[..source.len() - suffix];
+    let target = &target[..target.len() - suffix];
+
+    push_n(ops, EditType::N, prefix);
+    if source.is_empty() {
+        push_n(ops, EditType::I, target.len());
+    } else if target.is_empty() {
+        push_n(ops, EditType::D, source.len());
+    } else if let Some((x, y)) = bisect(source, target) {
+        diff_rec(&source[..x], &target[..y], ops);
+        diff_rec(&source[x..], &target[y..], ops);
+    } else {
+        push_n(ops, EditType::D, source.len());
+        push_n(ops, EditType::I, target.len());
+    }
+    push_n(ops, EditType::N, suffix);
+}
+
+/// Point on the middle snake to split the problem at,
+/// `None` if there's nothing in common.
+fn bisect<T: PartialEq>(source: &[T], target: &[T]) -> Option<(usize, usize)> {
+    let (len_source, len_target) = (source.len() as isize, target.len() as isize);
+    let max_d = (len_source + len_target + 1) / 2;
+    let offset = max_d;
+    let len = 2 * max_d + 2;
+
+    // furthest reaching x on each diagonal, forward from the start and backward from the end
+    let mut forward = vec![-1_isize; len as usize];
+    let mut backward = vec![-1_isize; len as usize];
+    forward[offset as usize + 1] = 0;
+    backward[offset as usize + 1] = 0;
+
+    let delta = len_source - len_target;
+    // with an odd delta the paths overlap during the forward pass, otherwise the backward
+    let front = delta % 2 != 0;
+
+    // diagonals which ran off the edges and don't need to be searched anymore
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d {
+        for k1 in (-d + k1_start..=d - k1_end).step_by(2) {
+            let k1_idx = (offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && forward[k1_idx - 1] < forward[k1_idx + 1]) {
+                forward[k1_idx + 1]
+            } else {
+                forward[k1_idx - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < len_source && y1 < len_target && source[x1 as usize] == target[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            forward[k1_idx] = x1;
+
+            if x1 > len_source {
+                k1_end += 2;
+            } else if y1 > len_target {
+                k1_start += 2;
+            } else if front {
+                let k2_idx = offset + delta - k1;
+                if (0..len).contains(&k2_idx) && backward[k2_idx as usize] != -1 {
+                    let x2 = len_source - backward[k2_idx as usize];
+                    if x1 >= x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+        }
+
+        for k2 in (-d + k2_start..=d - k2_end).step_by(2) {
+            let k2_idx = (offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && backward[k2_idx - 1] < backward[k2_idx + 1]) {
+                backward[k2_idx + 1]
+            } else {
+                backward[k2_idx - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < len_source
+                && y2 < len_target
+                && source[(len_source - x2 - 1) as usize] == target[(len_target - y2 - 1) as usize]
+            {
+                x2 += 1;
+                y2 += 1;
+            }
+            backward[k2_idx] = x2;
+
+            if x2 > len_source {
+                k2_end += 2;
+            } else if y2 > len_target {
+                k2_start += 2;
+            } else if !front {
+                let k1_idx = offset + delta - k2;
+                if (0..len).contains(&k1_idx) && forward[k1_idx as usize] != -1 {
+                    let x1 = forward[k1_idx as usize];
+                    let y1 = offset + x1 - k1_idx;
+                    if x1 >= len_source - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::differ::Differ;
+
+    fn distance(ops: &[EditType]) -> usize {
+        ops.iter().filter(|&&it| it != EditType::N).count()
+    }
+
+    /// Small alphabet pseudo random strings, lots of partial matches
+    fn random_strings(count: usize) -> Vec<(String, String)> {
+        let mut state = 0x2545_f491_u64;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        let string = |next: &mut dyn FnMut(u64) -> u64| {
+            let len = next(40);
+            (0..len).map(|_| (b'a' + next(3) as u8) as char).collect()
+        };
+
+        (0..count)
+            .map(|_| (string(&mut next), string(&mut next)))
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_wagner_fischer_distance() {
+        let random = random_strings(200);
+        let inputs = [
+            ("", ""),
+            ("abc", ""),
+            ("", "abc"),
+            ("abcabba", "cbabac"),
+            ("test source", "test target"),
+            ("the quick brown fox", "a quick brown dog jumps"),
+            ("aaaaaaaaaa", "aaaabaaaaa"),
+        ]
+        .into_iter()
+        .chain(random.iter().map(|(a, b)| (a.as_str(), b.as_str())));
+
+        for (source, target) in inputs {
+            let source = source.chars().collect::<Vec<_>>();
+            let target = target.chars().collect::<Vec<_>>();
+
+            let ops = diff(&source, &target);
+            let differ = Differ::new(source.clone(), target.clone(), true);
+            assert_eq!(
+                distance(&ops) as u64,
+                differ.step_count(),
+                "{source:?} {target:?}"
+            );
+
+            let mut applied = vec![];
+            let mut idx_source = 0;
+            let mut idx_target = 0;
+            for op in ops {
+                match op {
+                    EditType::N => {
+                        assert_eq!(source[idx_source], target[idx_target]);
+                        applied.push(source[idx_source]);
+                        idx_source += 1;
+                        idx_target += 1;
+                    }
+                    EditType::D => idx_source += 1,
+                    EditType::I => {
+                        applied.push(target[idx_target]);
+                        idx_target += 1;
+                    }
+                    EditType::S => unreachable!(),
+                }
+            }
+            assert_eq!((idx_source, applied), (source.len(), target));
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Diffs which ignore some differences, like `diff -w`, `-i` and `-B`.
+
+use std::{fmt::Debug, sync::Arc};
+
+use crate::{diff_slices_with, split_words, Algorithm, Edit, EditScript};
+
+/// Custom normalization, items with equal results compare equal
+pub type Normalizer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// What to ignore when comparing, the edit script still holds the original text of both sides.
+#[derive(Clone, Default)]
+pub struct DiffOptions {
+    pub algorithm: Algorithm,
+    /// Ignore all whitespace, `a b` equals `ab`
+    pub ignore_whitespace: bool,
+    pub ignore_case: bool,
+    /// Blank items don't take part in matching, blank items in the same gap
+    /// between two other items are paired up as unchanged,
+    /// the rest are still in the script but [`DiffOptions::has_changes`] skips them
+    pub ignore_blank_lines: bool,
+    /// Applied after the other normalizations
+    pub normalizer: Option<Normalizer>,
+}
+
+impl Debug for DiffOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiffOptions")
+            .field("algorithm", &self.algorithm)
+            .field("ignore_whitespace", &self.ignore_whitespace)
+            .field("ignore_case", &self.ignore_case)
+            .field("ignore_blank_lines", &self.ignore_blank_lines)
+            .field("normalizer", &self.normalizer.is_some())
+            .finish()
+    }
+}
+
+impl DiffOptions {
+    /// Whether any difference is ignored
+    pub fn ignores_anything(&self) -> bool {
+        self.ignore_whitespace
+            || self.ignore_case
+            || self.ignore_blank_lines
+            || self.normalizer.is_some()
+    }
+
+    /// Whether a script from these options has changes which aren't ignored
+    pub fn has_changes(&self, script: &[Edit<&str>]) -> bool {
+        script.iter().any(|edit| match edit {
+            Edit::Unchange { .. } => false,
+            Edit::Delete { source: item } | Edit::Insert { target: item } => {
+                !(self.ignore_blank_lines && item.trim().is_empty())
+            }
+            Edit::Substitute { .. } => true,
+        })
+    }
+
+    /// Text that is actually compared
+    pub fn normalize(&self, text: &str) -> String {
+        let mut text = if self.ignore_whitespace {
+            text.chars().filter(|c| !c.is_whitespace()).collect()
+        } else {
+            text.to_string()
+        };
+        if self.ignore_case {
+            text = text.to_lowercase();
+        }
+        if let Some(normalizer) = &self.normalizer {
+            text = normalizer(&text);
+        }
+        text
+    }
+
+    /// Line level diff, like [`crate::diff_lines`]
+    pub fn diff_lines<'a>(&self, source: &'a str, target: &'a str) -> EditScript<&'a str> {
+        self.diff_tokens(
+            &source.lines().collect::<Vec<_>>(),
+            &target.lines().collect::<Vec<_>>(),
+        )
+    }
+
+    /// Word level diff, like [`crate::diff_words`]
+    pub fn diff_words<'a>(&self, source: &'a str, target: &'a str) -> EditScript<&'a str> {
+        self.diff_tokens(&split_words(source), &split_words(target))
+    }
+
+    /// Diff the normalized tokens, then map the result back to the original ones
+    pub fn diff_tokens<'a>(&self, source: &[&'a str], target: &[&'a str]) -> EditScript<&'a str> {
+        let is_blank = |token: &&str| self.ignore_blank_lines && token.trim().is_empty();
+        let matched = |tokens: &[&str]| -> (Vec<usize>, Vec<String>) {
+            tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, token)| !is_blank(token))
+                .map(|(idx, token)| (idx, self.normalize(token)))
+                .unzip()
+        };
+        let (source_idx, source_keys) = matched(source);
+        let (target_idx, target_keys) = matched(target);
+        let script = diff_slices_with(self.algorithm, &source_keys, &target_keys);
+
+        let mut out = Vec::with_capacity(script.len());
+        // next original token on each side, and next matched one
+        let (mut source_pos, mut target_pos) = (0, 0);
+        let (mut source_matched, mut target_matched) = (0, 0);
+        for edit in script.into_iter().map(Some).chain([None]) {
+            let source_next = source_idx.get(source_matched).copied();
+            let target_next = target_idx.get(target_matched).copied();
+
+            let pairs = (source_next.unwrap_or(source.len()) - source_pos)
+                .min(target_next.unwrap_or(target.len()) - target_pos);
+            out.extend(
+                source[source_pos..source_pos + pairs]
+                    .iter()
+                    .zip(&target[target_pos..target_pos + pairs])
+                    .map(|(&source, &target)| Edit::Unchange { source, target }),
+            );
+            source_pos += pairs;
+            target_pos += pairs;
+
+            let Some(edit) = edit else {
+                out.extend(
+                    source[source_pos..]
+                        .iter()
+                        .map(|&source| Edit::Delete { source }),
+                );
+                out.extend(
+                    target[target_pos..]
+                        .iter()
+                        .map(|&target| Edit::Insert { target }),
+                );
+                break;
+            };
+
+            // blanks without a partner before the items of this edit become part of the change
+            let source_item = edit.source().map(|_| {
+                let idx = source_next.expect("edit script past source");
+                out.extend(
+                    source[source_pos..idx]
+                        .iter()
+                        .map(|&source| Edit::Delete { source }),
+                );
+                source_matched += 1;
+                source_pos = idx + 1;
+                source[idx]
+            });
+            let target_item = edit.target().map(|_| {
+                let idx = target_next.expect("edit script past target");
+                out.extend(
+                    target[target_pos..idx]
+                        .iter()
+                        .map(|&target| Edit::Insert { target }),
+                );
+                target_matched += 1;
+                target_pos = idx + 1;
+                target[idx]
+            });
+
+            out.push(match (edit, source_item, target_item) {
+                (Edit::Unchange { .. }, Some(source), Some(target)) => {
+                    Edit::Unchange { source, target }
+                }
+                (Edit::Delete { .. }, Some(source), _) => Edit::Delete { source },
+                (Edit::Insert { .. }, _, Some(target)) => Edit::Insert { target },
+                (Edit::Substitute { .. }, Some(source), Some(target)) => {
+                    Edit::Substitute { source, target }
+                }
+                _ => unreachable!(),
+            });
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cleanup, EditType};
+
+    fn changes(script: &[Edit<&str>]) -> usize {
+        script
+            .iter()
+            .filter(|edit| edit.edit_type() != EditType::N)
+            .count()
+    }
+
+    #[test]
+    fn test_ignore() {
+        let source = "fn main() {\n    Foo(1, 2);\n\n}\n";
+        let target = "fn main() {\n\n  foo(1,2);\n\n\n}\n";
+        assert!(changes(&DiffOptions::default().diff_lines(source, target)) > 0);
+
+        let options = DiffOptions {
+            ignore_whitespace: true,
+            ignore_case: true,
+            ignore_blank_lines: true,
+            ..Default::default()
+        };
+        let script = options.diff_lines(source, target);
+        let (applied_source, applied_target) = (
+            script
+                .iter()
+                .filter_map(Edit::source)
+                .copied()
+                .collect::<Vec<_>>(),
+            script
+                .iter()
+                .filter_map(Edit::target)
+                .copied()
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(applied_source, source.lines().collect::<Vec<_>>());
+        assert_eq!(applied_target, target.lines().collect::<Vec<_>>());
+        assert!(script.contains(&Edit::Unchange {
+            source: "    Foo(1, 2);",
+            target: "  foo(1,2);"
+        }));
+        // merging keeps both sides too
+        assert_eq!(cleanup::cleanup_merge(script.clone()), script);
+        // the extra blank lines of the target
+        assert_eq!(changes(&script), 2);
+        assert!(!options.has_changes(&script));
+    }
+
+    #[test]
+    fn test_normalizer() {
+        let options = DiffOptions {
+            normalizer: Some(Arc::new(|line| {
+                line.split("//").next().unwrap_or_default().to_string()
+            })),
+            ..Default::default()
+        };
+        let script = options.diff_lines("a // old\nb\n", "a // new\nc\n");
+        assert_eq!(
+            script,
+            vec![
+                Edit::Unchange {
+                    source: "a // old",
+                    target: "a // new"
+                },
+                Edit::Delete { source: "b" },
+                Edit::Insert { target: "c" },
+            ]
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Applying line edit scripts, and a stored patch format which can be applied
+//! to a source that changed since the diff was taken.
+//!
+//! The text format is the hunk part of a unified diff, `---`/`+++` headers are
+//! accepted and ignored when parsing.
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::{unified, Edit, EditScript};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The source doesn't match the source side of an edit script, `line` is 1 based
+    ContextMismatch {
+        line: usize,
+        expected: Option<String>,
+        found: Option<String>,
+    },
+    /// No position in the source matches the context of a hunk, `hunk` is 1 based
+    HunkFailed { hunk: usize },
+    /// Malformed patch text, `line` is 1 based
+    Parse { line: usize, message: String },
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::ContextMismatch {
+                line,
+                expected,
+                found,
+            } => {
+                let show = |it: &Option<String>| match it {
+                    Some(it) => format!("{it:?}"),
+                    None => "end of input".to_string(),
+                };
+                write!(
+                    f,
+                    "source line {line}: expected {}, found {}",
+                    show(expected),
+                    show(found)
+                )
+            }
+            PatchError::HunkFailed { hunk } => write!(f, "hunk {hunk} doesn't match the source"),
+            PatchError::Parse { line, message } => write!(f, "patch line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Join lines back into text, with a trailing newline if the original had one
+fn join_lines(lines: &[&str], trailing_newline: bool) -> String {
+    let mut out = lines.join("\n");
+    if trailing_newline && !lines.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Apply a line edit script to `source`, every unchanged and deleted line is
+/// checked against the source.
+pub fn apply<S: AsRef<str>>(source: &str, script: &[Edit<S>]) -> Result<String, PatchError> {
+    let mut source_lines = source.lines();
+    let mut line = 0;
+    let mut out = vec![];
+
+    for edit in script {
+        if let Some(expected) = edit.source() {
+            line += 1;
+            let found = source_lines.next();
+            if found != Some(expected.as_ref()) {
+                return Err(PatchError::ContextMismatch {
+                    line,
+                    expected: Some(expected.as_ref().to_string()),
+                    found: found.map(str::to_string),
+                });
+            }
+        }
+        if let Some(target) = edit.target() {
+            out.push(target.as_ref());
+        }
+    }
+
+    if let Some(found) = source_lines.next() {
+        return Err(PatchError::ContextMismatch {
+            line: line + 1,
+            expected: None,
+            found: Some(found.to_string()),
+        });
+    }
+
+    Ok(join_lines(&out, source.ends_with('\n')))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchHunk {
+    /// 1 based line the hunk starts at in the original source
+    pub source_start: usize,
+    pub edits: EditScript<String>,
+}
+
+impl PatchHunk {
+    fn source_lines(&self) -> impl Iterator<Item = &str> {
+        self.edits
+            .iter()
+            .filter_map(Edit::source)
+            .map(String::as_str)
+    }
+
+    fn target_lines(&self) -> impl Iterator<Item = &str> {
+        self.edits
+            .iter()
+            .filter_map(Edit::target)
+            .map(String::as_str)
+    }
+}
+
+/// Line based patch, only the changes with some context around them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    pub hunks: Vec<PatchHunk>,
+}
+
+impl Patch {
+    /// Keep `context` unchanged lines around each change
+    pub fn from_script<S: AsRef<str>>(script: &[Edit<S>], context: usize) -> Self {
+        let hunks = unified::hunks(script, context)
+            .into_iter()
+            .map(|hunk| PatchHunk {
+                source_start: hunk.source_start,
+                edits: hunk
+                    .edits
+                    .iter()
+                    .map(|edit| edit.clone_as(|it| it.as_ref().to_string()))
+                    .collect(),
+            })
+            .collect();
+        Self { hunks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// Apply the patch, hunks whose context moved are searched for up to
+    /// `max_offset` lines away from where they were expected.
+    pub fn apply(&self, source: &str, max_offset: usize) -> Result<String, PatchError> {
+        let lines = source.lines().collect::<Vec<_>>();
+        let mut out = vec![];
+        let mut pos = 0;
+        // how far the previous hunks were shifted, later ones likely moved the same
+        let mut drift = 0_isize;
+
+        for (idx, hunk) in self.hunks.iter().enumerate() {
+            let old = hunk.source_lines().collect::<Vec<_>>();
+            let matches_at = |start: usize| {
+                lines
+                    .get(start..start + old.len())
+                    .is_some_and(|it| it == old.as_slice())
+            };
+
+            let expected = (hunk.source_start.saturating_sub(1) as isize + drift).max(0) as usize;
+            let start = (0..=max_offset)
+                .flat_map(|offset| [expected.checked_add(offset), expected.checked_sub(offset)])
+                .flatten()
+                .find(|&start| start >= pos && matches_at(start))
+                .ok_or(PatchError::HunkFailed { hunk: idx + 1 })?;
+
+            out.extend_from_slice(&lines[pos..start]);
+            out.extend(hunk.target_lines());
+            pos = start + old.len();
+            drift = start as isize - hunk.source_start.saturating_sub(1) as isize;
+        }
+        out.extend_from_slice(&lines[pos..]);
+
+        Ok(join_lines(&out, source.ends_with('\n')))
+    }
+}
+
+impl Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `diff -u` starts empty ranges one line earlier
+        let range = |start: usize, len: usize| match len {
+            0 => format!("{},0", start.saturating_sub(1)),
+            1 => start.to_string(),
+            len => format!("{start},{len}"),
+        };
+
+        let mut target_drift = 0_isize;
+        for hunk in &self.hunks {
+            let (source_len, target_len) =
+                (hunk.source_lines().count(), hunk.target_lines().count());
+            let target_start = (hunk.source_start as isize + target_drift) as usize;
+            writeln!(
+                f,
+                "@@ -{} +{} @@",
+                range(hunk.source_start, source_len),
+                range(target_start, target_len)
+            )?;
+            target_drift += target_len as isize - source_len as isize;
+
+            for edit in &hunk.edits {
+                match edit {
+                    Edit::Unchange { source, .. } => writeln!(f, " {source}")?,
+                    Edit::Delete { source } => writeln!(f, "-{source}")?,
+                    Edit::Insert { target } => writeln!(f, "+{target}")?,
+                    Edit::Substitute { source, target } => writeln!(f, "-{source}\n+{target}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Patch {
+    type Err = PatchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hunks: Vec<PatchHunk> = vec![];
+        // lines left in the current hunk, on the source and target side
+        let mut remaining = (0, 0);
+
+        for (idx, line) in s.lines().enumerate() {
+            let error = |message: &str| PatchError::Parse {
+                line: idx + 1,
+                message: message.to_string(),
+            };
+
+            if remaining == (0, 0) {
+                if line.starts_with("---") || line.starts_with("+++") || line.is_empty() {
+                    continue;
+                }
+                let (source_start, source_len, target_len) =
+                    parse_header(line).ok_or_else(|| error("expected a hunk header"))?;
+                hunks.push(PatchHunk {
+                    source_start,
+                    edits: vec![],
+                });
+                remaining = (source_len, target_len);
+                continue;
+            }
+
+            let Some(hunk) = hunks.last_mut() else {
+                unreachable!("lines are only expected after a header");
+            };
+            if line.starts_with('\\') {
+                // "\ No newline at end of file"
+                continue;
+            }
+
+            let (marker, content) = match line.chars().next() {
+                Some(marker) => (marker, line[marker.len_utf8()..].to_string()),
+                // some tools strip the space of empty context lines
+                None => (' ', String::new()),
+            };
+            let edit = match marker {
+                ' ' => Edit::unchanged(content),
+                '-' => Edit::Delete { source: content },
+                '+' => Edit::Insert { target: content },
+                _ => return Err(error("expected a line starting with ' ', '-' or '+'")),
+            };
+
+            let source_side = edit.source().is_some() as usize;
+            let target_side = edit.target().is_some() as usize;
+            if source_side > remaining.0 || target_side > remaining.1 {
+                return Err(error("hunk is longer than its header says"));
+            }
+            remaining = (remaining.0 - source_side, remaining.1 - target_side);
+            hunk.edits.push(edit);
+        }
+
+        if remaining != (0, 0) {
+            return Err(PatchError::Parse {
+                line: s.lines().count(),
+                message: "hunk is shorter than its header says".to_string(),
+            });
+        }
+        Ok(Self { hunks })
+    }
+}
+
+/// `@@ -start[,len] +start[,len] @@`, returns `(source_start, source_len, target_len)`
+fn parse_header(line: &str) -> Option<(usize, usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?;
+    let (ranges, _) = ranges.split_once(" @@")?;
+    let (source, target) = ranges.split_once(" +")?;
+
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (source_start, source_len) = parse_range(source)?;
+    let (_, target_len) = parse_range(target)?;
+
+    // empty ranges point at the line before
+    let source_start = if source_len == 0 {
+        source_start + 1
+    } else {
+        source_start
+    };
+    Some((source_start, source_len, target_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cleanup::cleanup_merge, diff_lines};
+
+    const SOURCE: &str = "a\nb\nc\nd\ne\nf\ng\nh\n";
+    const TARGET: &str = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+
+    #[test]
+    fn test_apply_script() {
+        let script = diff_lines(SOURCE, TARGET);
+        assert_eq!(apply(SOURCE, &script).unwrap(), TARGET);
+
+        let err = apply("a\nx\n", &script).unwrap_err();
+        assert_eq!(
+            err,
+            PatchError::ContextMismatch {
+                line: 2,
+                expected: Some("b".to_string()),
+                found: Some("x".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let patch = Patch::from_script(&cleanup_merge(diff_lines(SOURCE, TARGET)), 1);
+        let text = patch.to_string();
+        assert_eq!(
+            text,
+            "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -8 +8,2 @@\n h\n+i\n"
+        );
+
+        let parsed = text.parse::<Patch>().unwrap();
+        assert_eq!(parsed, patch);
+        assert_eq!(parsed.apply(SOURCE, 0).unwrap(), TARGET);
+    }
+
+    #[test]
+    fn test_fuzzy_offset() {
+        let patch = Patch::from_script(&diff_lines(SOURCE, TARGET), 1);
+        let drifted = format!("new\nlines\n{SOURCE}");
+
+        assert_eq!(
+            patch.apply(&drifted, 0),
+            Err(PatchError::HunkFailed { hunk: 1 })
+        );
+        assert_eq!(
+            patch.apply(&drifted, 2).unwrap(),
+            format!("new\nlines\n{TARGET}")
+        );
+    }
+}
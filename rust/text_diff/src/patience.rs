@@ -0,0 +1,170 @@
+//! Patience diff, <https://bramcohen.livejournal.com/73318.html>.
+//!
+//! Items which occur exactly once on both sides are matched up, the longest
+//! run of them in the same order becomes fixed, and the gaps between them are
+//! diffed recursively. Sections without unique items fall back to Myers.
+//! The result isn't always minimal, but it lines up distinctive lines like
+//! function signatures instead of braces and blank lines, which reads better
+//! for source code with moved blocks.
+
+use std::{collections::HashMap, hash::Hash, ops::Range};
+
+use crate::{myers, EditType};
+
+/// Edit script as a list of [`EditType::N`], [`EditType::D`] and [`EditType::I`].
+pub fn diff<T: Hash + Eq>(source: &[T], target: &[T]) -> Vec<EditType> {
+    let mut ops = Vec::with_capacity(source.len().max(target.len()));
+    diff_rec(source, target, 0..source.len(), 0..target.len(), &mut ops);
+    ops
+}
+
+fn diff_rec<T: Hash + Eq>(
+    source: &[T],
+    target: &[T],
+    mut source_range: Range<usize>,
+    mut target_range: Range<usize>,
+    ops: &mut Vec<EditType>,
+) {
+    let mut prefix = 0;
+    while !source_range.is_empty()
+        && !target_range.is_empty()
+        && source[source_range.start] == target[target_range.start]
+    {
+        source_range.start += 1;
+        target_range.start += 1;
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while !source_range.is_empty()
+        && !target_range.is_empty()
+        && source[source_range.end - 1] == target[target_range.end - 1]
+    {
+        source_range.end -= 1;
+        target_range.end -= 1;
+        suffix += 1;
+    }
+
+    ops.extend(std::iter::repeat_n(EditType::N, prefix));
+    let anchors = find_anchors(source, target, source_range.clone(), target_range.clone());
+    if anchors.is_empty() {
+        ops.extend(myers::diff(&source[source_range], &target[target_range]));
+    } else {
+        let (mut source_start, mut target_start) = (source_range.start, target_range.start);
+        for (source_anchor, target_anchor) in anchors {
+            diff_rec(
+                source,
+                target,
+                source_start..source_anchor,
+                target_start..target_anchor,
+                ops,
+            );
+            ops.push(EditType::N);
+            (source_start, target_start) = (source_anchor + 1, target_anchor + 1);
+        }
+        diff_rec(
+            source,
+            target,
+            source_start..source_range.end,
+            target_start..target_range.end,
+            ops,
+        );
+    }
+    ops.extend(std::iter::repeat_n(EditType::N, suffix));
+}
+
+/// Items unique on both sides of the ranges, reduced to the longest run
+/// in the same order on both sides.
+pub(crate) fn find_anchors<T: Hash + Eq>(
+    source: &[T],
+    target: &[T],
+    source_range: Range<usize>,
+    target_range: Range<usize>,
+) -> Vec<(usize, usize)> {
+    // item -> (count and line in source, count and line in target)
+    type Seen = ((u32, usize), (u32, usize));
+    let mut seen: HashMap<&T, Seen> = HashMap::new();
+    for line in source_range {
+        let entry = &mut seen.entry(&source[line]).or_default().0;
+        *entry = (entry.0 + 1, line);
+    }
+    for line in target_range {
+        let entry = &mut seen.entry(&target[line]).or_default().1;
+        *entry = (entry.0 + 1, line);
+    }
+
+    let mut unique = seen
+        .into_values()
+        .filter(|((source_count, _), (target_count, _))| *source_count == 1 && *target_count == 1)
+        .map(|((_, source_line), (_, target_line))| (source_line, target_line))
+        .collect::<Vec<_>>();
+    unique.sort_unstable();
+
+    longest_increasing(&unique)
+}
+
+/// Longest subsequence with increasing target lines, by patience sorting
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // tops[len] = index of the pair ending the best run of length len + 1
+    let mut tops: Vec<usize> = vec![];
+    let mut prev = vec![None; pairs.len()];
+
+    for (idx, &(_, target_line)) in pairs.iter().enumerate() {
+        let pile = tops.partition_point(|&top| pairs[top].1 < target_line);
+        if pile > 0 {
+            prev[idx] = Some(tops[pile - 1]);
+        }
+        if pile == tops.len() {
+            tops.push(idx);
+        } else {
+            tops[pile] = idx;
+        }
+    }
+
+    let mut run = vec![];
+    let mut cur = tops.last().copied();
+    while let Some(idx) = cur {
+        run.push(pairs[idx]);
+        cur = prev[idx];
+    }
+    run.reverse();
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchors() {
+        let pairs = [(0, 3), (1, 0), (2, 1), (3, 4), (4, 2), (5, 5)];
+        assert_eq!(longest_increasing(&pairs), [(1, 0), (2, 1), (4, 2), (5, 5)]);
+    }
+
+    #[test]
+    fn test_moved_function() {
+        let source = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n";
+        let target = "fn b() {\n    2\n}\n\nfn a() {\n    1\n}\n";
+        let source = source.lines().collect::<Vec<_>>();
+        let target = target.lines().collect::<Vec<_>>();
+
+        let ops = diff(&source, &target);
+        let unchanged = ops
+            .iter()
+            .scan((0, 0), |pos, op| {
+                let line = source.get(pos.0).copied();
+                match op {
+                    EditType::N => *pos = (pos.0 + 1, pos.1 + 1),
+                    EditType::D => pos.0 += 1,
+                    EditType::I => pos.1 += 1,
+                    EditType::S => unreachable!(),
+                }
+                Some((*op == EditType::N).then_some(line))
+            })
+            .flatten()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // one function stays whole instead of matching up braces
+        assert_eq!(unchanged, ["fn b() {", "    2", "}"]);
+    }
+}
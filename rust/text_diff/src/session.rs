@@ -0,0 +1,320 @@
+//! Diff kept up to date while one side is being edited, for live views.
+//!
+//! Tokens are split by rules that only look at the two characters around a
+//! boundary, so after a splice only the tokens touching it are split again.
+//! The edit script is then rediffed between the nearest unchanged tokens
+//! around the splice, the rest of the cached script is kept.
+
+use std::ops::Range;
+
+use crate::{granularity::Granularity, myers, Edit, EditScript, EditType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Source,
+    Target,
+}
+
+/// Both texts with their tokens and the edit script between them
+#[derive(Debug, Clone)]
+pub struct DiffSession {
+    granularity: Granularity,
+    texts: [String; 2],
+    /// Byte ranges of the tokens of each side
+    tokens: [Vec<Range<usize>>; 2],
+    /// Insertions and deletions over the tokens, no substitutions
+    ops: Vec<EditType>,
+}
+
+impl DiffSession {
+    pub fn new(
+        granularity: Granularity,
+        source: impl Into<String>,
+        target: impl Into<String>,
+    ) -> Self {
+        let texts = [source.into(), target.into()];
+        let tokens = texts
+            .each_ref()
+            .map(|text| token_ranges(granularity, text, 0));
+
+        let mut this = Self {
+            granularity,
+            texts,
+            tokens,
+            ops: vec![],
+        };
+        this.ops = myers::diff(
+            &this.token_strs(Side::Source),
+            &this.token_strs(Side::Target),
+        );
+        this
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    pub fn source(&self) -> &str {
+        &self.texts[0]
+    }
+
+    pub fn target(&self) -> &str {
+        &self.texts[1]
+    }
+
+    fn token_strs(&self, side: Side) -> Vec<&str> {
+        self.token_window(side, 0..self.tokens[side as usize].len())
+    }
+
+    /// Tokens with the indices `window`
+    fn token_window(&self, side: Side, window: Range<usize>) -> Vec<&str> {
+        let text = &self.texts[side as usize];
+        self.tokens[side as usize][window]
+            .iter()
+            .map(|range| &text[range.clone()])
+            .collect()
+    }
+
+    /// Current edit script, tokens borrowed from the session
+    pub fn script(&self) -> EditScript<&str> {
+        let [mut source, mut target] =
+            [Side::Source, Side::Target].map(|side| self.token_strs(side).into_iter());
+        self.ops
+            .iter()
+            .map(|op| {
+                let mut source = || source.next().expect("edit script past source");
+                let mut target = || target.next().expect("edit script past target");
+                match op {
+                    EditType::N => Edit::Unchange {
+                        source: source(),
+                        target: target(),
+                    },
+                    EditType::D => Edit::Delete { source: source() },
+                    EditType::I => Edit::Insert { target: target() },
+                    EditType::S => unreachable!("sessions don't substitute"),
+                }
+            })
+            .collect()
+    }
+
+    /// Replace the bytes `range` of one side with `replacement` and update the diff.
+    ///
+    /// Panics if `range` is out of bounds or not on char boundaries,
+    /// like [`String::replace_range`].
+    pub fn splice(&mut self, side: Side, range: Range<usize>, replacement: &str) {
+        let edited = side as usize;
+        let tokens = &self.tokens[edited];
+        let token_at = |byte: usize| tokens.partition_point(|token| token.end <= byte);
+
+        // tokens whose boundaries may move, with one unchanged character on each side
+        let first = if range.start == 0 {
+            0
+        } else {
+            token_at(range.start - 1)
+        };
+        let end = if range.end < self.texts[edited].len() {
+            token_at(range.end) + 1
+        } else {
+            tokens.len()
+        };
+        let start_byte = tokens.get(first).map_or(0, |token| token.start);
+        let end_byte = if end > first {
+            tokens[end - 1].end
+        } else {
+            start_byte
+        };
+
+        self.texts[edited].replace_range(range.clone(), replacement);
+        let delta = replacement.len() as isize - range.len() as isize;
+        let end_byte = end_byte.checked_add_signed(delta).unwrap();
+
+        let split = token_ranges(
+            self.granularity,
+            &self.texts[edited][start_byte..end_byte],
+            start_byte,
+        );
+        let split_len = split.len();
+        let tokens = &mut self.tokens[edited];
+        for token in &mut tokens[end..] {
+            *token = token.start.checked_add_signed(delta).unwrap()
+                ..token.end.checked_add_signed(delta).unwrap();
+        }
+        tokens.splice(first..end, split);
+
+        self.rediff(side, first..end, split_len);
+    }
+
+    /// Replace the ops covering the old tokens `replaced` of `side`, now `new_len` tokens,
+    /// widened to the unchanged tokens around them
+    fn rediff(&mut self, side: Side, replaced: Range<usize>, new_len: usize) {
+        let edited = side as usize;
+
+        // tokens of both sides consumed before each op
+        let mut positions = Vec::with_capacity(self.ops.len() + 1);
+        let mut position = [0, 0];
+        positions.push(position);
+        for op in &self.ops {
+            for (side, position) in position.iter_mut().enumerate() {
+                *position += consumes(*op, side) as usize;
+            }
+            positions.push(position);
+        }
+
+        let mut lo = positions.partition_point(|position| position[edited] < replaced.start);
+        while lo > 0 && self.ops[lo - 1] != EditType::N {
+            lo -= 1;
+        }
+        let mut hi = positions.partition_point(|position| position[edited] <= replaced.end) - 1;
+        while hi < self.ops.len() && self.ops[hi] != EditType::N {
+            hi += 1;
+        }
+
+        let [mut source, mut target] = [0, 1].map(|side| positions[lo][side]..positions[hi][side]);
+        let window = if side == Side::Source {
+            &mut source
+        } else {
+            &mut target
+        };
+        window.end = window.end + new_len - replaced.len();
+
+        let ops = myers::diff(
+            &self.token_window(Side::Source, source),
+            &self.token_window(Side::Target, target),
+        );
+        self.ops.splice(lo..hi, ops);
+    }
+}
+
+/// Whether `op` takes a token from the side with index `side`
+fn consumes(op: EditType, side: usize) -> bool {
+    match op {
+        EditType::N | EditType::S => true,
+        EditType::D => side == 0,
+        EditType::I => side == 1,
+    }
+}
+
+/// Byte ranges of the tokens of `text`, which starts at `offset`
+fn token_ranges(granularity: Granularity, text: &str, offset: usize) -> Vec<Range<usize>> {
+    let mut start = offset;
+    granularity
+        .split(text)
+        .into_iter()
+        .map(|token| {
+            let range = start..start + token.len();
+            start = range.end;
+            range
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift, to stay free of dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    fn check(session: &DiffSession) {
+        let script = session.script();
+        let source = script
+            .iter()
+            .filter_map(Edit::source)
+            .copied()
+            .collect::<String>();
+        let target = script
+            .iter()
+            .filter_map(Edit::target)
+            .copied()
+            .collect::<String>();
+        assert_eq!(source, session.source());
+        assert_eq!(target, session.target());
+
+        for side in [Side::Source, Side::Target] {
+            let text = &session.texts[side as usize];
+            assert_eq!(
+                session.token_strs(side),
+                session.granularity.split(text),
+                "{side:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_splice() {
+        let mut session = DiffSession::new(
+            Granularity::Words,
+            "let x = 1;\nlet y = 2;\n",
+            "let x = 1;\nlet y = 2;\n",
+        );
+        assert!(session
+            .script()
+            .iter()
+            .all(|edit| edit.edit_type() == EditType::N));
+
+        session.splice(Side::Target, 4..5, "value");
+        check(&session);
+        assert_eq!(
+            session
+                .script()
+                .into_iter()
+                .filter(|edit| edit.edit_type() != EditType::N)
+                .collect::<Vec<_>>(),
+            [
+                Edit::Delete { source: "x" },
+                Edit::Insert { target: "value" }
+            ]
+        );
+
+        // typing into the same word again, then undoing it
+        session.splice(Side::Target, 9..9, "s");
+        check(&session);
+        session.splice(Side::Target, 4..10, "x");
+        check(&session);
+        assert!(session
+            .script()
+            .iter()
+            .all(|edit| edit.edit_type() == EditType::N));
+    }
+
+    #[test]
+    fn test_random_splices() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let alphabet = ["a", "b", " ", "\n", ",", "é", "ab\ncd"];
+
+        for granularity in [Granularity::Chars, Granularity::Words, Granularity::Lines] {
+            let mut session = DiffSession::new(granularity, "", "");
+            for _ in 0..500 {
+                let side = if rng.below(2) == 0 {
+                    Side::Source
+                } else {
+                    Side::Target
+                };
+                let text = &session.texts[side as usize];
+                let boundaries = text
+                    .char_indices()
+                    .map(|(idx, _)| idx)
+                    .chain([text.len()])
+                    .collect::<Vec<_>>();
+
+                let start = rng.below(boundaries.len());
+                let end = start + rng.below((boundaries.len() - start).min(4));
+                let replacement = (0..rng.below(3))
+                    .map(|_| alphabet[rng.below(alphabet.len())])
+                    .collect::<String>();
+
+                session.splice(side, boundaries[start]..boundaries[end], &replacement);
+                check(&session);
+            }
+        }
+    }
+}
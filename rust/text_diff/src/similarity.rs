@@ -0,0 +1,133 @@
+//! Normalized edit distance and fuzzy lookup.
+//!
+//! Distances use Ukkonen's banded dynamic programming: only cells within `max`
+//! of the diagonal can be part of a path costing at most `max`, so a bounded
+//! distance costs O(max * len) instead of O(len^2), and can stop as soon as
+//! every cell of a row exceeds the bound.
+
+/// Levenshtein distance if it's at most `max`
+pub fn bounded_distance<T: PartialEq>(source: &[T], target: &[T], max: usize) -> Option<usize> {
+    let (n, m) = (source.len(), target.len());
+    if n.abs_diff(m) > max {
+        return None;
+    }
+
+    // anything above `max` is as good as infinite
+    let inf = max + 1;
+    let mut prev = (0..=m).map(|j| j.min(inf)).collect::<Vec<_>>();
+    let mut cur = vec![inf; m + 1];
+
+    for i in 1..=n {
+        let lo = i.saturating_sub(max);
+        let hi = (i + max).min(m);
+
+        if lo > 0 {
+            cur[lo - 1] = inf;
+        } else {
+            cur[0] = i.min(inf);
+        }
+        if hi < m {
+            cur[hi + 1] = inf;
+        }
+
+        let mut row_min = cur[lo];
+        for j in lo.max(1)..=hi {
+            let substitution = prev[j - 1] + (source[i - 1] != target[j - 1]) as usize;
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(substitution).min(inf);
+            row_min = row_min.min(cur[j]);
+        }
+        if row_min > max {
+            return None;
+        }
+
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    (prev[m] <= max).then_some(prev[m])
+}
+
+/// `1 - levenshtein distance / longer length`, 1 for equal strings, 0 for nothing in common.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let distance = bounded_distance(&a, &b, max_len).unwrap_or(max_len);
+    1.0 - distance as f64 / max_len as f64
+}
+
+/// Candidate most similar to `query` as `(index, similarity)`, the first one wins ties.
+/// Candidates which can't beat the best one so far are abandoned early.
+pub fn best_match<S: AsRef<str>>(query: &str, candidates: &[S]) -> Option<(usize, f64)> {
+    let query = query.chars().collect::<Vec<_>>();
+    let mut best: Option<(usize, f64)> = None;
+    let mut candidate_chars = vec![];
+
+    for (idx, candidate) in candidates.iter().enumerate() {
+        candidate_chars.clear();
+        candidate_chars.extend(candidate.as_ref().chars());
+
+        let max_len = query.len().max(candidate_chars.len());
+        if max_len == 0 {
+            // two empty strings, can't do better than that
+            return Some((idx, 1.0));
+        }
+
+        // largest distance which still strictly beats the best similarity
+        let bound = match best {
+            Some((_, similarity)) => {
+                let allowed = (1.0 - similarity) * max_len as f64;
+                match allowed.ceil() as usize {
+                    0 => continue,
+                    allowed => allowed - 1,
+                }
+            }
+            None => max_len,
+        };
+
+        if let Some(distance) = bounded_distance(&query, &candidate_chars, bound) {
+            let similarity = 1.0 - distance as f64 / max_len as f64;
+            if best.is_none_or(|(_, best)| similarity > best) {
+                best = Some((idx, similarity));
+            }
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        let (kitten, sitting) = (chars("kitten"), chars("sitting"));
+
+        assert_eq!(bounded_distance(&kitten, &sitting, 10), Some(3));
+        assert_eq!(bounded_distance(&kitten, &sitting, 3), Some(3));
+        assert_eq!(bounded_distance(&kitten, &sitting, 2), None);
+        assert_eq!(bounded_distance(&chars(""), &chars("abc"), 3), Some(3));
+        assert_eq!(bounded_distance(&chars("abc"), &chars("abc"), 0), Some(0));
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_best_match() {
+        let candidates = ["apple", "banana", "grape", "grapefruit", "pineapple"];
+        assert_eq!(best_match("grap", &candidates).map(|it| it.0), Some(2));
+        assert_eq!(best_match("pinapple", &candidates).map(|it| it.0), Some(4));
+        assert_eq!(best_match("x", &[] as &[&str]), None);
+    }
+}
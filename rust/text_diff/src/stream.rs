@@ -0,0 +1,408 @@
+//! Line diff for files too large to hold in memory.
+//!
+//! A pre-pass reads both files once and keeps only a hash and the offset of
+//! every line. Lines unique in both files are matched up like patience diff,
+//! the longest increasing run of them becomes the anchors, and only the small
+//! chunks between anchors are diffed with Myers, on hashes.
+//! Lines are read back from disk when the result is written out.
+//!
+//! Lines are compared by 64 bit hash, a collision could make two different
+//! lines look unchanged.
+
+use std::{
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+};
+
+use crate::{myers, patience, EditType};
+
+/// Chunks with at most this many lines on both sides go straight to Myers,
+/// tiny in tests so the anchor search gets exercised
+const MYERS_CHUNK_LINES: usize = if cfg!(test) { 16 } else { 20_000 };
+
+/// Hash and position of every line of a file
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    hashes: Vec<u64>,
+    /// Start of every line, plus the end of the file
+    offsets: Vec<u64>,
+}
+
+impl LineIndex {
+    pub fn build(mut reader: impl BufRead) -> io::Result<Self> {
+        let mut this = Self {
+            hashes: vec![],
+            offsets: vec![0],
+        };
+
+        let mut line = vec![];
+        let mut offset = 0;
+        loop {
+            line.clear();
+            let len = reader.read_until(b'\n', &mut line)?;
+            if len == 0 {
+                break;
+            }
+            offset += len as u64;
+
+            let mut hasher = DefaultHasher::new();
+            trim_line_ending(&line).hash(&mut hasher);
+            this.hashes.push(hasher.finish());
+            this.offsets.push(offset);
+        }
+
+        Ok(this)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+}
+
+fn trim_line_ending(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Diff two indexed files, `emit(edit_type, source_line, target_line)` is called
+/// in order with 0 based line numbers, the line on the side an edit doesn't
+/// touch is where it would be.
+pub fn diff_indexed(
+    source: &LineIndex,
+    target: &LineIndex,
+    mut emit: impl FnMut(EditType, usize, usize),
+) {
+    diff_range(
+        &source.hashes,
+        &target.hashes,
+        0..source.len(),
+        0..target.len(),
+        &mut emit,
+    );
+}
+
+fn diff_range(
+    source: &[u64],
+    target: &[u64],
+    mut source_range: Range<usize>,
+    mut target_range: Range<usize>,
+    emit: &mut impl FnMut(EditType, usize, usize),
+) {
+    // common prefix and suffix, cheap and very common in logs
+    while !source_range.is_empty()
+        && !target_range.is_empty()
+        && source[source_range.start] == target[target_range.start]
+    {
+        emit(EditType::N, source_range.start, target_range.start);
+        source_range.start += 1;
+        target_range.start += 1;
+    }
+    let mut suffix = 0;
+    while suffix < source_range.len()
+        && suffix < target_range.len()
+        && source[source_range.end - suffix - 1] == target[target_range.end - suffix - 1]
+    {
+        suffix += 1;
+    }
+    let suffix_start = (source_range.end - suffix, target_range.end - suffix);
+    source_range.end -= suffix;
+    target_range.end -= suffix;
+
+    if source_range.len() <= MYERS_CHUNK_LINES && target_range.len() <= MYERS_CHUNK_LINES {
+        let (mut source_line, mut target_line) = (source_range.start, target_range.start);
+        for op in myers::diff(&source[source_range], &target[target_range]) {
+            emit(op, source_line, target_line);
+            match op {
+                EditType::N | EditType::S => {
+                    source_line += 1;
+                    target_line += 1;
+                }
+                EditType::D => source_line += 1,
+                EditType::I => target_line += 1,
+            }
+        }
+    } else {
+        let anchors =
+            patience::find_anchors(source, target, source_range.clone(), target_range.clone());
+        if anchors.is_empty() {
+            // nothing to line up on, and too large to search for a minimal diff
+            for line in source_range.clone() {
+                emit(EditType::D, line, target_range.start);
+            }
+            for line in target_range.clone() {
+                emit(EditType::I, source_range.end, line);
+            }
+        } else {
+            let (mut source_start, mut target_start) = (source_range.start, target_range.start);
+            for (source_anchor, target_anchor) in anchors {
+                diff_range(
+                    source,
+                    target,
+                    source_start..source_anchor,
+                    target_start..target_anchor,
+                    emit,
+                );
+                emit(EditType::N, source_anchor, target_anchor);
+                (source_start, target_start) = (source_anchor + 1, target_anchor + 1);
+            }
+            diff_range(
+                source,
+                target,
+                source_start..source_range.end,
+                target_start..target_range.end,
+                emit,
+            );
+        }
+    }
+
+    for idx in 0..suffix {
+        emit(EditType::N, suffix_start.0 + idx, suffix_start.1 + idx);
+    }
+}
+
+/// Reads lines back by their offsets, seeking only when not reading sequentially
+#[derive(Debug)]
+struct LineReader<'index> {
+    file: BufReader<File>,
+    index: &'index LineIndex,
+    pos: u64,
+}
+
+impl<'index> LineReader<'index> {
+    fn new(file: File, index: &'index LineIndex) -> Self {
+        Self {
+            file: BufReader::new(file),
+            index,
+            pos: 0,
+        }
+    }
+
+    fn line(&mut self, line: usize) -> io::Result<String> {
+        let (start, end) = (self.index.offsets[line], self.index.offsets[line + 1]);
+        if self.pos != start {
+            self.file.seek(SeekFrom::Start(start))?;
+        }
+
+        let mut buf = vec![0; (end - start) as usize];
+        self.file.read_exact(&mut buf)?;
+        self.pos = end;
+        Ok(String::from_utf8_lossy(trim_line_ending(&buf)).into_owned())
+    }
+}
+
+/// Write a unified diff of two files with `context` lines around each change,
+/// holding at most one hunk in memory. Returns whether the files differ.
+pub fn diff_files(
+    source_path: &Path,
+    target_path: &Path,
+    context: usize,
+    out: &mut impl Write,
+) -> io::Result<bool> {
+    let source_index = LineIndex::build(BufReader::new(File::open(source_path)?))?;
+    let target_index = LineIndex::build(BufReader::new(File::open(target_path)?))?;
+
+    let mut writer = HunkWriter {
+        source: LineReader::new(File::open(source_path)?, &source_index),
+        target: LineReader::new(File::open(target_path)?, &target_index),
+        context,
+        header: Some(format!(
+            "--- {}\n+++ {}\n",
+            source_path.display(),
+            target_path.display()
+        )),
+        hunk: vec![],
+        trailing_unchanged: 0,
+        out,
+        error: None,
+    };
+
+    diff_indexed(
+        &source_index,
+        &target_index,
+        |op, source_line, target_line| {
+            if writer.error.is_none() {
+                if let Err(err) = writer.push(op, source_line, target_line) {
+                    writer.error = Some(err);
+                }
+            }
+        },
+    );
+    if let Some(err) = writer.error.take() {
+        return Err(err);
+    }
+    writer.flush()?;
+
+    Ok(writer.header.is_none())
+}
+
+/// Collects ops into hunks and writes each one once it's followed by enough unchanged lines
+struct HunkWriter<'index, 'out, W: Write> {
+    source: LineReader<'index>,
+    target: LineReader<'index>,
+    context: usize,
+    /// File header, written before the first hunk
+    header: Option<String>,
+
+    /// `(op, source_line, target_line)`, starting with up to `context` unchanged lines
+    hunk: Vec<(EditType, usize, usize)>,
+    /// Unchanged lines at the end of `hunk`
+    trailing_unchanged: usize,
+    out: &'out mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> HunkWriter<'_, '_, W> {
+    fn push(&mut self, op: EditType, source_line: usize, target_line: usize) -> io::Result<()> {
+        let has_change = self.hunk.len() > self.trailing_unchanged;
+
+        if op == EditType::N {
+            self.hunk.push((op, source_line, target_line));
+            self.trailing_unchanged += 1;
+
+            if has_change && self.trailing_unchanged > 2 * self.context {
+                self.flush()?;
+            } else if !has_change && self.hunk.len() > self.context {
+                // only leading context so far, keep the last `context` lines
+                self.hunk.remove(0);
+                self.trailing_unchanged -= 1;
+            }
+        } else {
+            self.hunk.push((op, source_line, target_line));
+            self.trailing_unchanged = 0;
+        }
+        Ok(())
+    }
+
+    /// Write the pending hunk, keeping the unchanged lines after its trailing
+    /// context as leading context of the next one
+    fn flush(&mut self) -> io::Result<()> {
+        if self.hunk.len() == self.trailing_unchanged {
+            return Ok(());
+        }
+
+        let keep_from =
+            self.hunk.len() - self.trailing_unchanged + self.trailing_unchanged.min(self.context);
+        let hunk = self.hunk.drain(..).collect::<Vec<_>>();
+        let (written, rest) = hunk.split_at(keep_from);
+
+        // deletions before insertions within each change, like `cleanup_merge`
+        let mut written = written.to_vec();
+        for change in written.split_mut(|(op, ..)| *op == EditType::N) {
+            change.sort_by_key(|(op, ..)| *op == EditType::I);
+        }
+
+        if let Some(header) = self.header.take() {
+            self.out.write_all(header.as_bytes())?;
+        }
+
+        let count =
+            |side: fn(&EditType) -> bool| written.iter().filter(|(op, ..)| side(op)).count();
+        let source_len = count(|op| *op != EditType::I);
+        let target_len = count(|op| *op != EditType::D);
+        let range = |start: usize, len: usize| match len {
+            0 => format!("{start},0"),
+            1 => (start + 1).to_string(),
+            len => format!("{},{len}", start + 1),
+        };
+        let (_, source_start, target_start) = written[0];
+        writeln!(
+            self.out,
+            "@@ -{} +{} @@",
+            range(source_start, source_len),
+            range(target_start, target_len)
+        )?;
+
+        for &(op, source_line, target_line) in &written {
+            match op {
+                EditType::N => writeln!(self.out, " {}", self.source.line(source_line)?)?,
+                EditType::D => writeln!(self.out, "-{}", self.source.line(source_line)?)?,
+                EditType::I => writeln!(self.out, "+{}", self.target.line(target_line)?)?,
+                EditType::S => unreachable!("the streaming diff doesn't substitute"),
+            }
+        }
+
+        let rest = &rest[rest.len().saturating_sub(self.context)..];
+        self.hunk.extend_from_slice(rest);
+        self.trailing_unchanged = rest.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cleanup::cleanup_merge, diff_lines, unified};
+
+    fn index(text: &str) -> LineIndex {
+        LineIndex::build(text.as_bytes()).unwrap()
+    }
+
+    /// The anchors make the result a valid diff, but not always a minimal one
+    #[test]
+    fn test_valid_diff() {
+        let source = (0..200)
+            .map(|it| format!("line {}\n", it % 37))
+            .collect::<String>();
+        let target = (0..230)
+            .map(|it| format!("line {}\n", (it * 7) % 41))
+            .collect::<String>();
+
+        let (source_lines, target_lines) = (
+            source.lines().collect::<Vec<_>>(),
+            target.lines().collect::<Vec<_>>(),
+        );
+        let (mut deleted, mut applied) = (vec![], vec![]);
+        diff_indexed(
+            &index(&source),
+            &index(&target),
+            |op, source_line, target_line| match op {
+                EditType::N => {
+                    assert_eq!(source_lines[source_line], target_lines[target_line]);
+                    deleted.push(source_lines[source_line]);
+                    applied.push(source_lines[source_line]);
+                }
+                EditType::D => deleted.push(source_lines[source_line]),
+                EditType::I => applied.push(target_lines[target_line]),
+                EditType::S => unreachable!(),
+            },
+        );
+
+        assert_eq!(deleted, source_lines);
+        assert_eq!(applied, target_lines);
+    }
+
+    #[test]
+    fn test_diff_files() {
+        let dir = std::env::temp_dir().join(format!("text_diff_stream_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (source_path, target_path) = (dir.join("a.txt"), dir.join("b.txt"));
+
+        let source = (0..100).map(|it| format!("{it}\n")).collect::<String>();
+        let target = source.replace("\n10\n", "\nten\n").replace("\n50\n", "\n") + "end\n";
+        std::fs::write(&source_path, &source).unwrap();
+        std::fs::write(&target_path, &target).unwrap();
+
+        let mut out = vec![];
+        assert!(diff_files(&source_path, &target_path, 3, &mut out).unwrap());
+        let expected = unified::format(
+            &source_path.display().to_string(),
+            &target_path.display().to_string(),
+            &cleanup_merge(diff_lines(&source, &target)),
+            3,
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let mut out = vec![];
+        assert!(!diff_files(&source_path, &source_path, 3, &mut out).unwrap());
+        assert!(out.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
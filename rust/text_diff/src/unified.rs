@@ -0,0 +1,96 @@
+//! Unified diff output, the format of `diff -u`.
+
+use crate::{patch::Patch, Edit, EditType};
+
+/// A group of changes with surrounding context, line numbers are 1 based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'script, T> {
+    pub source_start: usize,
+    pub source_len: usize,
+    pub target_start: usize,
+    pub target_len: usize,
+    pub edits: &'script [Edit<T>],
+}
+
+/// Split an edit script into hunks with `context` unchanged items around each change,
+/// changes closer than `2 * context` share a hunk.
+pub fn hunks<T>(script: &[Edit<T>], context: usize) -> Vec<Hunk<'_, T>> {
+    let changes = script
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| edit.edit_type() != EditType::N)
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    // ranges of the script covered by each hunk
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for idx in changes {
+        let start = idx.saturating_sub(context);
+        let end = (idx + 1 + context).min(script.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // line number of the item at the start of each range, on both sides
+    let (mut source_line, mut target_line, mut pos) = (1, 1, 0);
+    let mut advance = |to: usize, source_line: &mut usize, target_line: &mut usize| {
+        for edit in &script[pos..to] {
+            *source_line += edit.source().is_some() as usize;
+            *target_line += edit.target().is_some() as usize;
+        }
+        pos = to;
+    };
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            advance(start, &mut source_line, &mut target_line);
+            let edits = &script[start..end];
+            Hunk {
+                source_start: source_line,
+                source_len: edits.iter().filter(|it| it.source().is_some()).count(),
+                target_start: target_line,
+                target_len: edits.iter().filter(|it| it.target().is_some()).count(),
+                edits,
+            }
+        })
+        .collect()
+}
+
+/// Format a line diff as a unified diff, empty if there are no changes.
+pub fn format<S: AsRef<str>>(
+    source_name: &str,
+    target_name: &str,
+    script: &[Edit<S>],
+    context: usize,
+) -> String {
+    let patch = Patch::from_script(script, context);
+    if patch.is_empty() {
+        return String::new();
+    }
+
+    format!("--- {source_name}\n+++ {target_name}\n{patch}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cleanup::cleanup_merge, diff_lines};
+
+    #[test]
+    fn test_format() {
+        let source = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let target = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+        let script = cleanup_merge(diff_lines(source, target));
+
+        assert_eq!(
+            format("a.txt", "b.txt", &script, 1),
+            "--- a.txt\n+++ b.txt\n\
+             @@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n\
+             @@ -8 +8,2 @@\n h\n+i\n"
+        );
+        assert_eq!(format("a", "b", &diff_lines(source, source), 3), "");
+    }
+}
@@ -1,5 +1,8 @@
+// template, `{{MEMBER_DEF}}`, `{{CMP}}`, `{{WORKGROUP_SIZE}}` and
+// `{{OPS_PER_INVOCATION}}` are filled in by `shader_source`
+
 struct Data {
-    value: u32,
+    {{MEMBER_DEF}}
 }
 
 @group(0) @binding(0) var<storage, read_write> data: array<Data>;
@@ -9,17 +12,30 @@ struct Param {
 
     step: u32,
     op_len: u32,
+    op_count: u32,
+
+    offset: u32,
+    len: u32,
 }
 
 var<push_constant> param: Param;
 
 @compute
-@workgroup_size(1)
+@workgroup_size({{WORKGROUP_SIZE}})
 fn bitonic_sort_op(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let y = global_id.y * param.dimension_size;
-    let z = global_id.z * param.dimension_size * param.dimension_size;
-    let op_id = global_id.x + y + z;
+    // x and y are enough for any u32 length
+    let invocation_id = global_id.x + global_id.y * param.dimension_size;
+
+    for (var i = 0u; i < {{OPS_PER_INVOCATION}}u; i++) {
+        let op_id = invocation_id * {{OPS_PER_INVOCATION}}u + i;
+        if op_id >= param.op_count {
+            return;
+        }
+        compare_and_swap(op_id);
+    }
+}
 
+fn compare_and_swap(op_id: u32) {
     let op_len = param.op_len;
 
     let op_offset_group = (op_id / op_len) * op_len * 2;
@@ -31,17 +47,17 @@ fn bitonic_sort_op(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let op_size_step_1 = (op_size_max - ((op_id * 2) % op_size_max)) - 1;
     let op_size = select(op_len, op_size_step_1, param.step == 1);
 
-    let left = op_offset;
-    let right = (op_offset + op_size);
+    let left = param.offset + op_offset;
+    let right = param.offset + op_offset + op_size;
 
-    if right >= arrayLength(&data) {
+    if op_offset + op_size >= param.len || right >= arrayLength(&data) {
         return;
     }
 
     let a = data[left];
     let b = data[right];
 
-    let need_swap = a.value > b.value;
+    let need_swap = {{CMP}};
     if need_swap {
         let temp = data[left];
         data[left] = data[right];
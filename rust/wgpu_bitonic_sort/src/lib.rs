@@ -1,16 +1,29 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
 use bytemuck::cast_slice;
 use param::Param;
 use wgpu::{
+    naga::{
+        front::wgsl,
+        valid::{Capabilities, ValidationFlags, Validator},
+    },
     BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, Buffer,
-    CommandBuffer, CommandEncoderDescriptor, ComputePassDescriptor,
-    ComputePipeline, ComputePipelineDescriptor, Device,
+    CommandBuffer, CommandEncoder, CommandEncoderDescriptor,
+    ComputePassDescriptor,
+    ComputePipeline, ComputePipelineDescriptor, Device, Maintain,
     PipelineCompilationOptions, PipelineLayoutDescriptor,
     PushConstantRange, Queue, ShaderModuleDescriptor, ShaderSource,
     ShaderStages,
 };
 
+pub use workgroup::WorkgroupConfig;
+
 pub mod param;
+pub mod workgroup;
 
 #[derive(Debug)]
 pub struct BitonicSorter {
@@ -18,20 +31,58 @@ pub struct BitonicSorter {
     bind_group: BindGroup,
 
     pipeline: ComputePipeline,
+    config: WorkgroupConfig,
 }
 
 impl BitonicSorter {
+    /// Panics if the shader is invalid, see [`BitonicSorter::try_new`]
     pub fn new(
         device: &Device,
         target_buffer: &Buffer,
         data_member_def: &str,
         data_cmp_expr: &str,
     ) -> Self {
-        let shader_src = include_str!("./bitonic_sort.wgsl");
+        Self::try_new(
+            device,
+            target_buffer,
+            data_member_def,
+            data_cmp_expr,
+        )
+        .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// `data_member_def`: members of the sorted struct, like `value: u32,`
+    ///
+    /// `data_cmp_expr`: whether `a` goes after `b`,
+    /// like `a.value > b.value`
+    ///
+    /// The workgroup config is picked from the device limits,
+    /// see [`BitonicSorter::autotune`] to measure instead
+    pub fn try_new(
+        device: &Device,
+        target_buffer: &Buffer,
+        data_member_def: &str,
+        data_cmp_expr: &str,
+    ) -> Result<Self, ShaderError> {
+        Self::try_with_config(
+            device,
+            target_buffer,
+            data_member_def,
+            data_cmp_expr,
+            WorkgroupConfig::from_limits(&device.limits()),
+        )
+    }
 
-        let shader_src = shader_src
-            .replace("value: u32,", data_member_def)
-            .replace("a.value > b.value", data_cmp_expr);
+    pub fn try_with_config(
+        device: &Device,
+        target_buffer: &Buffer,
+        data_member_def: &str,
+        data_cmp_expr: &str,
+        config: WorkgroupConfig,
+    ) -> Result<Self, ShaderError> {
+        let shader_src =
+            shader_source(data_member_def, data_cmp_expr, config);
+        validate_shader(&shader_src)?;
 
         let shader = device.create_shader_module({
             ShaderModuleDescriptor {
@@ -69,7 +120,7 @@ impl BitonicSorter {
                 bind_group_layouts: &[&bind_group_layout],
                 push_constant_ranges: &[PushConstantRange {
                     stages: ShaderStages::COMPUTE,
-                    range: 0..(4 * 3),
+                    range: 0..size_of::<Param>() as u32,
                 }],
             });
 
@@ -83,13 +134,70 @@ impl BitonicSorter {
                 ),
             });
 
-        Self {
+        Ok(Self {
             bind_group_layout,
             bind_group,
             pipeline,
+            config,
+        })
+    }
+
+    /// Time sorting `target_buffer` with each of
+    /// [`WorkgroupConfig::candidates`] and keep the fastest,
+    /// blocks until done and leaves the buffer sorted
+    pub fn autotune(
+        device: &Device,
+        queue: &Queue,
+        target_buffer: &Buffer,
+        data_member_def: &str,
+        data_cmp_expr: &str,
+        data_len: u32,
+    ) -> Result<Self, ShaderError> {
+        const RUNS: u32 = 4;
+
+        let mut fastest: Option<(Duration, Self)> = None;
+        for config in WorkgroupConfig::candidates(&device.limits()) {
+            let sorter = Self::try_with_config(
+                device,
+                target_buffer,
+                data_member_def,
+                data_cmp_expr,
+                config,
+            )?;
+
+            // the first run also waits for the pipeline
+            sorter.sort(device, queue, data_len);
+            device.poll(Maintain::Wait);
+
+            let start = Instant::now();
+            for _ in 0..RUNS {
+                sorter.sort(device, queue, data_len);
+            }
+            device.poll(Maintain::Wait);
+            let elapsed = start.elapsed();
+
+            if fastest.as_ref().is_none_or(|(time, _)| elapsed < *time) {
+                fastest = Some((elapsed, sorter));
+            }
+        }
+
+        match fastest {
+            Some((_, sorter)) => Ok(sorter),
+            // no candidates on tiny limits
+            None => Self::try_new(
+                device,
+                target_buffer,
+                data_member_def,
+                data_cmp_expr,
+            ),
         }
     }
 
+    /// Workgroup config the shader was built with, for diagnostics
+    pub fn config(&self) -> WorkgroupConfig {
+        self.config
+    }
+
     fn create_bind_group(
         device: &Device,
         target_buffer: &Buffer,
@@ -121,70 +229,149 @@ impl BitonicSorter {
         queue.submit([self.sort_command_buffer(device, data_len)]);
     }
 
+    /// Sort `data[offset..offset + len]` only, leaving the rest as is
+    pub fn sort_range(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        offset: u32,
+        len: u32,
+    ) {
+        let mut encoder =
+            device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("bitonic sort command encoder"),
+            });
+        self.encode_range(device, &mut encoder, offset, len);
+        queue.submit([encoder.finish()]);
+    }
+
     pub fn sort_command_buffer(
         &self,
         device: &Device,
         data_len: u32,
     ) -> CommandBuffer {
+        let mut encoder =
+            device.create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("bitonic sort command encoder"),
+            });
+        self.encode(device, &mut encoder, data_len);
+        encoder.finish()
+    }
+
+    /// Record the sort into `encoder`, to submit it together with
+    /// other work
+    pub fn encode(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        data_len: u32,
+    ) {
+        self.encode_range(device, encoder, 0, data_len);
+    }
+
+    /// Record the sort of `data[offset..offset + len]` into `encoder`
+    pub fn encode_range(
+        &self,
+        device: &Device,
+        encoder: &mut CommandEncoder,
+        offset: u32,
+        len: u32,
+    ) {
         let max_size =
             device.limits().max_compute_workgroups_per_dimension;
-        let max_size_f64 = max_size as f64;
+        let WorkgroupConfig {
+            workgroup_size,
+            ops_per_invocation,
+        } = self.config;
 
-        let stage_num = (data_len as f64).log2().ceil() as u32;
+        let stage_num = (len as f64).log2().ceil() as u32;
 
-        let mut encoder =
-            device.create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("bitonic sort command encoder"),
+        let mut pass =
+            encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("bitonic sort compute pass"),
+                timestamp_writes: None,
             });
 
-        {
-            let mut pass =
-                encoder.begin_compute_pass(&ComputePassDescriptor {
-                    label: Some("bitonic sort compute pass"),
-                    timestamp_writes: None,
-                });
-
-            pass.set_bind_group(0, &self.bind_group, &[]);
-            pass.set_pipeline(&self.pipeline);
-
-            for stage in 1..=stage_num {
-                for step in 1..=stage {
-                    let op_len = 2_u32.pow(stage - step);
-                    let op_count = 2_u32.pow(stage_num - 1);
-
-                    let size = op_count as f64;
-                    let x = size;
-                    let y = x / max_size_f64;
-                    let z = y / max_size_f64;
-
-                    let x = (x.ceil() as u32).min(max_size);
-                    let y = (y.ceil() as u32).min(max_size);
-                    let z = z.ceil() as u32;
-
-                    pass.set_push_constants(
-                        0,
-                        cast_slice(&[Param {
-                            dimension_size: max_size,
-                            step,
-                            op_len,
-                        }]),
-                    );
-
-                    pass.dispatch_workgroups(x, y, z);
-                }
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_pipeline(&self.pipeline);
+
+        for stage in 1..=stage_num {
+            for step in 1..=stage {
+                let op_len = 2_u32.pow(stage - step);
+                let op_count = 2_u32.pow(stage_num - 1);
+
+                let workgroups = op_count
+                    .div_ceil(ops_per_invocation)
+                    .div_ceil(workgroup_size);
+                let x = workgroups.min(max_size);
+                let y = workgroups.div_ceil(max_size);
+
+                pass.set_push_constants(
+                    0,
+                    cast_slice(&[Param {
+                        dimension_size: max_size * workgroup_size,
+                        step,
+                        op_len,
+                        op_count,
+                        offset,
+                        len,
+                    }]),
+                );
+
+                pass.dispatch_workgroups(x, y, 1);
             }
         }
+    }
+}
 
-        encoder.finish()
+/// Sort shader with the data struct's members, the comparison
+/// and the workgroup config filled in
+pub fn shader_source(
+    data_member_def: &str,
+    data_cmp_expr: &str,
+    config: WorkgroupConfig,
+) -> String {
+    include_str!("./bitonic_sort.wgsl")
+        .replace("{{MEMBER_DEF}}", data_member_def)
+        .replace("{{CMP}}", data_cmp_expr)
+        .replace("{{WORKGROUP_SIZE}}", &config.workgroup_size.to_string())
+        .replace(
+            "{{OPS_PER_INVOCATION}}",
+            &config.ops_per_invocation.to_string(),
+        )
+}
+
+/// Parse and validate the shader, to report a bad member definition or
+/// comparison before the device does
+pub fn validate_shader(src: &str) -> Result<(), ShaderError> {
+    let module = wgsl::parse_str(src)
+        .map_err(|err| ShaderError(err.emit_to_string(src)))?;
+    Validator::new(ValidationFlags::all(), Capabilities::PUSH_CONSTANT)
+        .validate(&module)
+        .map_err(|err| ShaderError(err.emit_to_string(src)))?;
+    Ok(())
+}
+
+/// Generated sort shader is invalid, with the compiler's report
+#[derive(Debug, Clone)]
+pub struct ShaderError(pub String);
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid bitonic sort shader:\n{}", self.0)
     }
 }
 
+impl std::error::Error for ShaderError {}
+
 #[cfg(test)]
 mod tests {
+    use std::ops::Range;
+
     use rand::{Rng as _, SeedableRng};
     use wgpu::{
         util::DeviceExt as _, BufferAddress, BufferUsages, Features,
-        MapMode, RequestAdapterOptions,
+        Limits, MapMode, RequestAdapterOptions,
     };
 
     use super::*;
@@ -211,7 +398,21 @@ mod tests {
             .expect("falied to request device")
     }
 
-    async fn sort(mut data: Vec<u32>) {
+    async fn sort(data: Vec<u32>) {
+        let len = data.len();
+        sort_range(data, 0..len).await;
+    }
+
+    async fn sort_range(data: Vec<u32>, range: Range<usize>) {
+        sort_with_config(data, range, None).await;
+    }
+
+    /// Config from the device limits if `None`
+    async fn sort_with_config(
+        mut data: Vec<u32>,
+        range: Range<usize>,
+        config: Option<WorkgroupConfig>,
+    ) {
         // prepare
         let (device, queue) = init_ctx().await;
 
@@ -232,13 +433,23 @@ mod tests {
         );
 
         // GPU sort
-        let sorter = BitonicSorter::new(
+        let config = config.unwrap_or_else(|| {
+            WorkgroupConfig::from_limits(&device.limits())
+        });
+        let sorter = BitonicSorter::try_with_config(
             &device,
             &data_buffer,
             "value: u32",
             "a.value > b.value",
+            config,
+        )
+        .unwrap();
+        sorter.sort_range(
+            &device,
+            &queue,
+            range.start as u32,
+            range.len() as u32,
         );
-        sorter.sort(&device, &queue, data.len() as u32);
 
         // copy buffer
         let mut encoder =
@@ -264,13 +475,85 @@ mod tests {
         let gpu_sorted: &[u32] = cast_slice(&view);
 
         // std sort
-        data.sort();
+        data[range].sort();
         let std_sorted = data;
 
         // assert_eq would cause huge output when failed
         assert!(gpu_sorted == std_sorted);
     }
 
+    #[test]
+    fn test_shader_valid() {
+        let configs = [
+            ("value: u32,", "a.value > b.value"),
+            ("value: u32,", "a.value < b.value"),
+            ("key: f32, id: u32,", "a.key > b.key"),
+            (
+                "hash: u32, index: u32,",
+                "a.hash > b.hash \
+                 || (a.hash == b.hash && a.index > b.index)",
+            ),
+        ];
+        let workgroup_configs =
+            WorkgroupConfig::candidates(&Limits::default());
+        for (member_def, cmp_expr) in configs {
+            for config in &workgroup_configs {
+                let src = shader_source(member_def, cmp_expr, *config);
+                if let Err(err) = validate_shader(&src) {
+                    panic!("{member_def} {cmp_expr} {config:?}: {err}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shader_invalid() {
+        let configs = [
+            ("value: u32,", "a.missing > b.missing"),
+            ("value: u32,", "a.value +"),
+            ("value: u32,", "a.value + b.value"),
+            ("value u32", "a.value > b.value"),
+        ];
+        for (member_def, cmp_expr) in configs {
+            let config = WorkgroupConfig::from_limits(&Limits::default());
+            let src = shader_source(member_def, cmp_expr, config);
+            assert!(
+                validate_shader(&src).is_err(),
+                "{member_def} {cmp_expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_workgroup_config() {
+        let limits = Limits::downlevel_defaults();
+        let config = WorkgroupConfig::from_limits(&limits);
+        assert_eq!(config.workgroup_size, 256);
+
+        let limits = Limits {
+            max_compute_invocations_per_workgroup: 100,
+            ..Limits::downlevel_defaults()
+        };
+        let config = WorkgroupConfig::from_limits(&limits);
+        assert_eq!(config.workgroup_size, 64);
+
+        let candidates = WorkgroupConfig::candidates(&limits);
+        assert!(candidates.contains(&config));
+        assert!(candidates.iter().all(|config| {
+            config.workgroup_size.is_power_of_two()
+                && (32..=64).contains(&config.workgroup_size)
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_sort_workgroup_configs() {
+        let data: Vec<u32> = (0..17408).rev().collect();
+        for config in WorkgroupConfig::candidates(&Limits::default()) {
+            sort_with_config(data.clone(), 0..17408, Some(config)).await;
+            sort_with_config(data.clone(), 100..16485, Some(config)).await;
+        }
+    }
+
     #[tokio::test]
     async fn test_sort_rand() {
         run_sort_rand(1, 16384).await;
@@ -282,15 +565,22 @@ mod tests {
     async fn run_sort_rand(seed: u64, n: usize) {
         let mut rng = rand::rngs::SmallRng::seed_from_u64(seed);
 
-        let data = std::iter::repeat(0)
-            .into_iter()
-            .take(n)
+        let data = (0..n)
             .map(|_| rng.gen_range(0..u32::MAX))
             .collect();
 
         sort(data).await;
     }
 
+    #[tokio::test]
+    async fn test_sort_range() {
+        let data: Vec<u32> = (0..40000).rev().collect();
+        sort_range(data.clone(), 0..16384).await;
+        sort_range(data.clone(), 100..16485).await;
+        sort_range(data.clone(), 20000..37408).await;
+        sort_range(data, 39999..40000).await;
+    }
+
     #[tokio::test]
     async fn test_sort_seq() {
         sort((0..16384).collect()).await;
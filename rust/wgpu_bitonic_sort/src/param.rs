@@ -1,7 +1,14 @@
 #[derive(Debug, Clone, Copy, Default, bytemuck::NoUninit)]
 #[repr(C)]
 pub struct Param {
+    /// Invocations along x of a full row of workgroups
     pub dimension_size: u32,
     pub step: u32,
     pub op_len: u32,
+    /// Compare and swap ops in the step
+    pub op_count: u32,
+    /// First element of the sorted range
+    pub offset: u32,
+    /// Elements in the sorted range
+    pub len: u32,
 }
@@ -0,0 +1,49 @@
+use wgpu::Limits;
+
+/// Shape of the sort dispatches, filled into the shader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkgroupConfig {
+    /// Invocations per workgroup, a power of two
+    pub workgroup_size: u32,
+    /// Compare and swap ops done by each invocation
+    pub ops_per_invocation: u32,
+}
+
+impl WorkgroupConfig {
+    const MAX_WORKGROUP_SIZE: u32 = 256;
+    const OPS_PER_INVOCATION: [u32; 3] = [1, 2, 4];
+
+    /// Largest workgroup the device allows, up to 256,
+    /// one op per invocation
+    pub fn from_limits(limits: &Limits) -> Self {
+        Self {
+            workgroup_size: Self::max_workgroup_size(limits),
+            ops_per_invocation: 1,
+        }
+    }
+
+    /// Configs tried by [`crate::BitonicSorter::autotune`]
+    pub fn candidates(limits: &Limits) -> Vec<Self> {
+        let max = Self::max_workgroup_size(limits);
+        let sizes = (0..=Self::MAX_WORKGROUP_SIZE.ilog2())
+            .map(|exp| 1 << exp)
+            .filter(|&size| (32.min(max)..=max).contains(&size));
+
+        sizes
+            .flat_map(|workgroup_size| {
+                Self::OPS_PER_INVOCATION.map(|ops_per_invocation| Self {
+                    workgroup_size,
+                    ops_per_invocation,
+                })
+            })
+            .collect()
+    }
+
+    fn max_workgroup_size(limits: &Limits) -> u32 {
+        let max = Self::MAX_WORKGROUP_SIZE
+            .min(limits.max_compute_invocations_per_workgroup)
+            .min(limits.max_compute_workgroup_size_x)
+            .max(1);
+        1 << max.ilog2()
+    }
+}